@@ -1,27 +1,146 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
 
 use windows::Win32::Foundation::{GetLastError, HANDLE};
 use windows::Win32::UI::Shell::{FOLDERID_ProgramData, SHGetKnownFolderPath, KF_FLAG_DEFAULT};
 
+use crate::audit::{self, AuditAction, AuditLog, Trigger};
 use crate::config::{self, Config};
-use crate::cursor::Cursor;
+use crate::cursor::theme::CursorTheme;
+use crate::history::{History, HistoryEntry, HISTORY_CAPACITY};
 use crate::input::{Input, KeyCode, KeyState, KeyStatus, Keybind};
-use crate::tray::{Tray, TrayEvent};
-use crate::ui::taskdialog::{self, TaskDialog};
+use crate::notification::{Notification, Notifications, Severity};
+use crate::process::{self, LaunchSpec, Process, ProcessInfo, TerminationOutcome};
+use crate::rules::{self, HangAction, Rule};
+use crate::tray::appearance::{self, TrayStatus};
+use crate::tray::{MenuState, RecentAction, Tray, TrayEvent};
+use crate::ui::dialogs::{Choice, Countdown, Dialog, Dialogs, Verification};
+use crate::ui::filedialog;
+use crate::watchdog::{HangReport, HangTracker, HungWindow};
 use crate::window::Window;
-use crate::{cursor, logf};
+use crate::{clipboard, crash, cursor, diagnostics, logf, tr, trn, wide};
 use crate::{registry, updater};
 
 /// The path to the cursor file relative to the executable's working directory
 const CURSOR_FILENAME: &str = "cursor.cur";
-const ICON_FILENAME: &str = "icon.ico";
 const CONFIG_FILENAME: &str = "config.toml";
 const LOGFILES_PATH: &str = "logs\\";
+const AUDIT_PATH: &str = "audit\\";
+const AUDIT_KEY_FILENAME: &str = "audit.key";
+const CRASH_REPORTS_PATH: &str = "crashes\\";
+/// How often the cursor is updated for the window under it in termination mode
+const CURSOR_HOVER_INTERVAL_MS: u32 = 100;
+const GENERATED_CURSORS_PATH: &str = "cursors\\generated\\";
+const CURSOR_MARKER_FILENAME: &str = "cursors.swapped";
+
+/// The ids of the choices of the dialog shown by [`App::confirm`]
+const CONFIRM_ACCEPT_ID: i32 = 100;
+const CONFIRM_ACCEPT_TREE_ID: i32 = 101;
+
+/// The ids of the choices of the dialog shown for unresponsive windows
+const HANG_TERMINATE_ID: i32 = 100;
+const HANG_WAIT_ID: i32 = 101;
+
+/// Posted to the thread running the event loop once the user has answered
+/// the prompt for an unresponsive window, see [`App::hang_answered`]
+const WM_HANG_ANSWERED: u32 = windows::Win32::UI::WindowsAndMessaging::WM_APP;
+
+/// Posted to the thread running the event loop once a process told to
+/// terminate has exited or timed out, see [`App::terminated`]
+const WM_TERMINATED: u32 = WM_HANG_ANSWERED + 1;
+
+/// What clicking a window does after entering a click mode, e.g. termination mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickAction {
+    /// Terminate the window's process
+    Terminate,
+    /// Suspend the window's process, or resume it if xterminate suspended it
+    Suspend,
+    /// Show details about the window's process
+    Info,
+}
+
+/// The choice made in the dialog shown by [`App::confirm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirmation {
+    Accept,
+
+    /// Accept and terminate the processes started by the process as well
+    AcceptTree,
+    Cancel,
+}
+
+/// The prompts for unresponsive windows that are currently open. Each prompt
+/// runs on its own thread and sends the chosen option back through `sender`.
+struct HangPrompts {
+    /// The windows a prompt is open for
+    open: HashSet<isize>,
+    sender: Sender<(HungWindow, Option<i32>)>,
+    receiver: Receiver<(HungWindow, Option<i32>)>,
+}
+
+impl HangPrompts {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            open: HashSet::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+/// A process that has been told to terminate, see [`App::await_termination`].
+struct Termination {
+    action: AuditAction,
+    trigger: Trigger,
+    info: ProcessInfo,
+
+    /// Captured before the process was terminated, for relaunching it
+    launch: Option<LaunchSpec>,
+
+    /// The processes started by it, terminated once it has exited
+    tree: Vec<u32>,
+}
+
+/// A [`Termination`] once the process has exited or timed out, sent
+/// from the worker thread waiting for it to [`App::terminated`].
+struct Terminated {
+    termination: Termination,
+    outcome: TerminationOutcome,
+    descendants: Vec<(ProcessInfo, TerminationOutcome)>,
+}
+
+/// The channel the worker threads waiting for terminated processes report
+/// back through, see [`App::await_termination`].
+struct Terminations {
+    sender: Sender<Terminated>,
+    receiver: Receiver<Terminated>,
+}
+
+impl Terminations {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self { sender, receiver }
+    }
+}
+
+/// Hotkeys paused by the user, see [`App::pause_hotkeys`].
+struct Pause {
+    /// When the hotkeys are resumed automatically, if configured
+    resume_at: Option<chrono::DateTime<chrono::Local>>,
+
+    /// The timer that resumes the hotkeys at `resume_at`
+    timer: Option<usize>,
+}
 
-#[derive(PartialEq, Eq)]
-enum AppState {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppState {
     Standby,
     Active,
     Shutdown,
@@ -29,51 +148,208 @@ enum AppState {
 
 pub struct App {
     config: Rc<RefCell<Config>>,
+
+    /// The file `config` is saved to
+    config_path: std::path::PathBuf,
     appstate: AppState,
-    cursor_path: String,
+
+    /// What entered termination mode, recorded in the audit log
+    /// for the process terminated in it
+    activated_by: Trigger,
+
+    /// What clicking a window does in the click mode that is active
+    click_action: ClickAction,
+    cursors: CursorTheme,
+
+    /// The timer that updates the cursor while hovering windows in termination
+    /// mode, or `None` if it is not running
+    cursor_timer: Option<usize>,
+
+    /// The process under the mouse pointer when the timer last fired
+    /// and whether it is protected
+    hovered: Option<(u32, bool)>,
     keybinds: HashMap<String, Keybind>,
+    history: History,
+    hang_tracker: HangTracker,
+    hang_prompts: HangPrompts,
+    terminations: Terminations,
+    notifications: Notifications,
+    dialogs: Arc<dyn Dialogs>,
+
+    /// Problems found in the config when it was last loaded
+    config_warnings: Vec<String>,
+
+    /// The number of processes terminated since xterminate started
+    kills: usize,
+
+    /// The processes suspended in suspend mode, which are resumed on exit
+    suspended: HashSet<u32>,
+
+    /// A short description of the last action, shown in the tray tooltip
+    last_action: Option<String>,
+
+    /// The version of a newer release found by the last update check
+    update_available: Option<String>,
+
+    /// Set while hotkeys are paused. Survives config reloads.
+    pause: Option<Pause>,
+
+    /// The audit log, or `None` if disabled in the config
+    audit: Option<AuditLog>,
 }
 
 impl Drop for App {
     fn drop(&mut self) {
         logf!("Application dropping - saving config and freeing resources");
 
-        config::save(&self.config.borrow_mut());
+        config::save(&self.config.borrow_mut(), &self.config_path);
 
         // Reset cursor in case xterminate exits mid-termination
         cursor::reset();
 
+        // Suspended processes could not be resumed once xterminate has exited
+        for pid in self.suspended.drain() {
+            if let Err(error) = process::resume(pid) {
+                logf!("ERROR: failed to resume suspended process {pid} [{error}]");
+            }
+        }
+
         logf!("Goodbye");
     }
 }
 
 impl App {
-    /// Creates a new singleton instance of [`App`] and returns it.
+    /// Creates a new singleton instance of [`App`] showing its dialogs
+    /// through `dialogs` and returns it.
     #[must_use]
-    pub fn new() -> Rc<RefCell<Self>> {
-        crate::logger::initialize();
-
+    pub fn new(dialogs: Arc<dyn Dialogs>) -> Rc<RefCell<Self>> {
         logf!("Creating application instance");
 
         logf!("Loading application configuration");
-        let config = Rc::new(RefCell::new(config::load()));
+        let config = config::load();
+        let config_path = std::path::PathBuf::from(config_path());
 
         logf!(
             "Application configuration version: {}",
-            config.borrow().compatibility.version_major
+            config.compatibility.version_major
         );
 
+        let mut config_warnings = crate::logger::configure(&config.logging);
+        config_warnings.extend(crate::locale::configure(&config.locale));
+
         logf!("Setting up keybinds");
-        let keybinds = Self::setup_keybinds(&mut config.borrow_mut());
+        let keybinds = Self::setup_keybinds(&config);
 
-        logf!("Application instance created successfully");
+        let audit = Self::open_audit_log(&config);
+        let (cursors, cursor_warnings) = Self::load_cursors(&config, &config_path);
+        config_warnings.extend(cursor_warnings);
 
-        Rc::new(RefCell::new(Self {
+        let app = Self::from_parts(
             config,
+            config_path,
+            keybinds,
+            cursors,
+            audit,
+            config_warnings,
+            dialogs,
+        );
+
+        logf!("Application instance created successfully");
+
+        Rc::new(RefCell::new(app))
+    }
+
+    /// Creates an [`App`] in standby from a loaded config and the
+    /// resources set up for it, see [`Self::new`].
+    fn from_parts(
+        config: Config,
+        config_path: std::path::PathBuf,
+        keybinds: HashMap<String, Keybind>,
+        cursors: CursorTheme,
+        audit: Option<AuditLog>,
+        config_warnings: Vec<String>,
+        dialogs: Arc<dyn Dialogs>,
+    ) -> Self {
+        let hang_tracker = HangTracker::new(std::time::Duration::from_secs(
+            config.watchdog.threshold_seconds,
+        ));
+
+        let notifications = Notifications::new(config.notifications.verbosity);
+
+        Self {
+            config: Rc::new(RefCell::new(config)),
+            config_path,
             appstate: AppState::Standby,
-            cursor_path: cursor_path(),
+            activated_by: Trigger::Hotkey,
+            click_action: ClickAction::Terminate,
+            cursors,
+            cursor_timer: None,
+            hovered: None,
             keybinds,
-        }))
+            history: History::new(HISTORY_CAPACITY),
+            hang_tracker,
+            hang_prompts: HangPrompts::new(),
+            terminations: Terminations::new(),
+            notifications,
+            dialogs,
+            config_warnings,
+            kills: 0,
+            suspended: HashSet::new(),
+            last_action: None,
+            update_available: None,
+            pause: None,
+            audit,
+        }
+    }
+
+    /// Resolves the cursors configured for termination mode. Problems
+    /// with the configured files are logged and returned.
+    fn load_cursors(config: &Config, config_path: &std::path::Path) -> (CursorTheme, Vec<String>) {
+        let (cursors, errors) = CursorTheme::load(
+            &config.cursor,
+            config_path.parent().unwrap_or(config_path),
+            &make_rel_appdata_path_abs(GENERATED_CURSORS_PATH),
+            std::path::PathBuf::from(cursor_path()),
+        );
+
+        for error in &errors {
+            logf!("ERROR: invalid cursor configuration: {error}");
+        }
+
+        logf!(Debug: "Using cursors {cursors:?}");
+
+        (cursors, errors)
+    }
+
+    /// Opens the audit log and deletes records that have
+    /// expired, or returns `None` if it is disabled.
+    #[must_use]
+    pub fn open_audit_log(config: &Config) -> Option<AuditLog> {
+        if !config.audit.enabled {
+            logf!("Audit log is disabled");
+            return None;
+        }
+
+        let key = match audit::load_or_create_key(&audit_key_path()) {
+            Ok(key) => key,
+            Err(error) => {
+                logf!("ERROR: failed to load audit key, audit log is disabled: {error}");
+                return None;
+            }
+        };
+
+        let audit = AuditLog::new(
+            audit_path(),
+            key,
+            config.audit.retention_days,
+            current_user(),
+        );
+
+        if let Err(error) = audit.prune() {
+            logf!("ERROR: failed to delete expired audit records: {error}");
+        }
+
+        Some(audit)
     }
 
     #[allow(clippy::never_loop, clippy::missing_panics_doc)]
@@ -87,22 +363,44 @@ impl App {
         let autostart_enabled = Self::autostart();
         Self::set_autostart(autostart_enabled);
 
-        // Check for updates on startup only if autoupdate is enabled
-        if Self::autoupdate() {
-            Self::update_check(false);
+        // Restore cursors left swapped by a previous run that crashed or was terminated
+        cursor::restore_if_swapped();
+
+        if app.borrow().config.borrow().cursor.guard {
+            if let Err(error) = cursor::guard::spawn() {
+                logf!("ERROR: failed to start cursor guard: {error}");
+            }
         }
 
+        app.borrow().offer_crash_report();
+
         logf!("Creating input processor");
         let input = Input::create(app.clone());
 
         logf!("Creating system tray");
-        let tray = Tray::create(&icon_path(), app.clone(), app.borrow().keybinds.clone());
+        let tray = Tray::create(app.clone());
+        app.borrow_mut()
+            .notifications
+            .set_notifier(Box::new(tray.borrow().notifier()));
+
+        if !app.borrow().config_warnings.is_empty() {
+            app.borrow()
+                .notify_config_warnings(&tr!("notification.config_loaded"));
+        }
+
+        // Check for updates on startup only if autoupdate is enabled. This
+        // happens after the tray icon exists so errors can be shown there.
+        if Self::autoupdate() {
+            app.borrow_mut().update_check(false);
+        }
+
+        let watchdog_timer = Self::start_watchdog(&app.borrow().config.borrow());
 
         logf!("Starting event loop");
         while app.borrow().appstate != AppState::Shutdown {
             use windows::Win32::UI::WindowsAndMessaging::{
                 DispatchMessageA, PeekMessageA, TranslateMessage, WaitMessage, MSG, PM_REMOVE,
-                WM_CLOSE, WM_QUIT,
+                WM_CLOSE, WM_QUIT, WM_TIMER,
             };
 
             let mut msg = MSG::default();
@@ -129,6 +427,21 @@ impl App {
                         logf!("Got exit signal!");
                         app.borrow_mut().shutdown();
                         // return;
+                    } else if msg.message == WM_TIMER && Some(msg.wParam.0) == watchdog_timer {
+                        app.borrow_mut().watchdog_tick();
+                    } else if msg.message == WM_HANG_ANSWERED {
+                        app.borrow_mut().hang_answered();
+                    } else if msg.message == WM_TERMINATED {
+                        app.borrow_mut().terminated();
+                    } else if msg.message == WM_TIMER
+                        && Some(msg.wParam.0) == app.borrow().cursor_timer
+                    {
+                        app.borrow_mut().termination_mode_hover();
+                    } else if msg.message == WM_TIMER
+                        && Some(msg.wParam.0)
+                            == app.borrow().pause.as_ref().and_then(|pause| pause.timer)
+                    {
+                        app.borrow_mut().resume_hotkeys();
                     } else {
                         // Dispatch the messages so the windows receive them
                         TranslateMessage(&msg);
@@ -139,46 +452,262 @@ impl App {
                     tray.borrow().poll();
                 }
             };
+
+            let appearance = appearance::appearance(&app.borrow().tray_status());
+            tray.borrow_mut().set_appearance(appearance);
         }
 
         logf!("Event loop exited");
+
+        if let Some(timer) = watchdog_timer {
+            unsafe {
+                let _ = windows::Win32::UI::WindowsAndMessaging::KillTimer(
+                    windows::Win32::Foundation::HWND(0),
+                    timer,
+                );
+            }
+        }
     }
 
-    fn setup_keybinds(config: &mut Config) -> HashMap<String, Keybind> {
-        let mut keybinds: HashMap<String, Keybind> = HashMap::new();
+    /// Starts the thread timer that periodically triggers [`Self::watchdog_tick`]
+    /// if the watchdog is enabled. Returns the timer ID, or `None` if the
+    /// watchdog is disabled.
+    fn start_watchdog(config: &Config) -> Option<usize> {
+        use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+
+        if !config.watchdog.enabled {
+            return None;
+        }
 
-        keybinds.insert(
-            String::from("terminate_immediate"),
-            Self::keybind_from_config(&config.keybinds.terminate_immediate),
+        logf!(
+            "Starting hung-window watchdog (interval: {}s, threshold: {}s)",
+            config.watchdog.interval_seconds,
+            config.watchdog.threshold_seconds
         );
-        keybinds.insert(
-            String::from("terminate_click"),
-            Self::keybind_from_config(&config.keybinds.terminate_click),
+
+        let interval_ms = u32::try_from(config.watchdog.interval_seconds.saturating_mul(1000))
+            .unwrap_or(u32::MAX);
+
+        // Thread timers post WM_TIMER to the thread's message queue, where it is
+        // picked up by the event loop. The returned ID is never 0 on success.
+        let timer = unsafe { SetTimer(windows::Win32::Foundation::HWND(0), 0, interval_ms, None) };
+
+        if timer == 0 {
+            logf!("ERROR: failed to start watchdog timer [{}]", unsafe {
+                GetLastError().unwrap_err()
+            });
+
+            return None;
+        }
+
+        Some(timer)
+    }
+
+    /// Checks all top-level windows for unresponsiveness and acts on the ones
+    /// that have been hung for longer than the configured threshold.
+    pub fn watchdog_tick(&mut self) {
+        let own_pid = std::process::id();
+
+        let hung = Window::windows()
+            .into_iter()
+            .filter(|window| window.is_visible() && window.is_hung())
+            .filter_map(|window| {
+                let pid = window.process_id();
+
+                if pid == own_pid {
+                    return None;
+                }
+
+                // Protected processes cannot be opened and are skipped
+                let process = Process::try_open(pid).ok()?;
+
+                Some(HungWindow {
+                    handle: window.handle(),
+                    title: window.title().to_string_lossy().into_owned(),
+                    process: process.info(),
+                })
+            })
+            .collect::<Vec<HungWindow>>();
+
+        let reports = self.hang_tracker.update(
+            &hung,
+            std::time::Instant::now(),
+            &self.config.borrow().rules,
         );
-        keybinds.insert(
-            String::from("terminate_click_confirm"),
-            Self::keybind_from_config(&config.keybinds.terminate_click_confirm),
+
+        for report in reports {
+            self.handle_hang(&report);
+        }
+    }
+
+    /// Acts on a window reported by the watchdog, either by terminating
+    /// it outright or by asking the user what to do with it.
+    fn handle_hang(&mut self, report: &HangReport) {
+        logf!(
+            "Window '{}' of {} has not responded for {} seconds",
+            report.window.title,
+            report.window.process,
+            report.duration.as_secs()
         );
-        keybinds.insert(
-            String::from("terminate_abort"),
-            Self::keybind_from_config(&config.keybinds.terminate_abort),
+
+        match report.action {
+            HangAction::Kill => {
+                logf!("Terminating unresponsive window as configured by rule");
+                self.terminate_hung(&report.window, Trigger::Rule);
+            }
+
+            HangAction::Prompt => self.prompt_hang(report),
+
+            HangAction::Ignore => {}
+        }
+    }
+
+    /// Asks the user whether to terminate an unresponsive window or to keep
+    /// waiting for it. The prompt is shown on a thread of its own so that the
+    /// watchdog keeps running while it is open, and the answer is acted on by
+    /// [`Self::hang_answered`] once the event loop picks it up.
+    fn prompt_hang(&mut self, report: &HangReport) {
+        use windows::Win32::System::Threading::GetCurrentThreadId;
+
+        if !self.hang_prompts.open.insert(report.window.handle) {
+            logf!("A prompt for the unresponsive window is open already");
+            return;
+        }
+
+        let dialog = Dialog::new(
+            Severity::Warning,
+            tr!("dialog.not_responding.title"),
+            tr!(
+                "dialog.not_responding.heading",
+                name = report.window.process.name
+            ),
+            trn!(
+                "dialog.not_responding.content",
+                usize::try_from(report.duration.as_secs()).unwrap_or(usize::MAX),
+                title = report.window.title
+            ),
         );
 
-        keybinds
+        let choices = [
+            Choice {
+                id: HANG_TERMINATE_ID,
+                label: tr!("dialog.not_responding.terminate"),
+                note: Some(tr!("dialog.not_responding.terminate_note")),
+            },
+            Choice {
+                id: HANG_WAIT_ID,
+                label: tr!("dialog.not_responding.wait"),
+                note: Some(tr!("dialog.not_responding.wait_note")),
+            },
+        ];
+
+        let dialogs = Arc::clone(&self.dialogs);
+        let sender = self.hang_prompts.sender.clone();
+        let window = report.window.clone();
+        let thread_id = unsafe { GetCurrentThreadId() };
+
+        std::thread::spawn(move || {
+            let answer = dialogs.choice(&dialog, &choices, None, None);
+
+            if sender.send((window, answer.value)).is_ok() {
+                wake_event_loop(thread_id, WM_HANG_ANSWERED);
+            }
+        });
     }
 
-    /// Creates a [`Keybind`] from a given keybinding in the [`Config`].
-    fn keybind_from_config(cfg_value: &Vec<String>) -> Keybind {
+    /// Acts on the answers to the prompts shown by [`Self::prompt_hang`].
+    pub fn hang_answered(&mut self) {
+        while let Ok((window, choice)) = self.hang_prompts.receiver.try_recv() {
+            self.hang_prompts.open.remove(&window.handle);
+
+            match choice {
+                Some(HANG_TERMINATE_ID) => {
+                    logf!("User chose to terminate the unresponsive window");
+                    self.terminate_hung(&window, Trigger::Watchdog);
+                }
+
+                Some(HANG_WAIT_ID) => {
+                    logf!("User chose to keep waiting for the unresponsive window");
+                    self.hang_tracker
+                        .wait(window.handle, std::time::Instant::now());
+                }
+
+                _ => {
+                    logf!("User chose to ignore the unresponsive window");
+                }
+            }
+        }
+    }
+
+    /// Terminates the process of a window reported by the watchdog. The window
+    /// may have been closed or its process may have exited in the meantime,
+    /// e.g. while the user was asked, in which case there is nothing left to do.
+    fn terminate_hung(&mut self, hung: &HungWindow, trigger: Trigger) {
+        let mut window = Window::from_handle(hung.handle);
+
+        if window.process_id() != hung.process.pid || Process::try_open(hung.process.pid).is_err() {
+            logf!(
+                "{} has exited already, no need to terminate it",
+                hung.process
+            );
+
+            return;
+        }
+
+        self.terminate(&mut window, trigger, false);
+    }
+
+    /// Creates the keybinds specified in the [`Config`], keyed by their name in the config file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the config contains an unrecognized key-code.
+    #[must_use]
+    pub fn setup_keybinds(config: &Config) -> HashMap<String, Keybind> {
+        Self::try_setup_keybinds(config)
+            .expect("config contains an invalid keybind (unrecognized key-code)")
+    }
+
+    /// Creates the keybinds specified in the [`Config`] like [`Self::setup_keybinds`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first keybind containing an unrecognized key-code.
+    pub fn try_setup_keybinds(config: &Config) -> Result<HashMap<String, Keybind>, String> {
+        let mut keybinds: HashMap<String, Keybind> = HashMap::new();
+
+        for (name, keys) in [
+            ("terminate_immediate", &config.keybinds.terminate_immediate),
+            ("terminate_click", &config.keybinds.terminate_click),
+            (
+                "terminate_click_confirm",
+                &config.keybinds.terminate_click_confirm,
+            ),
+            ("terminate_abort", &config.keybinds.terminate_abort),
+            ("restart_immediate", &config.keybinds.restart_immediate),
+            ("pause_toggle", &config.keybinds.pause_toggle),
+            ("suspend_click", &config.keybinds.suspend_click),
+            ("info_click", &config.keybinds.info_click),
+        ] {
+            let keybind = Self::keybind_from_config(keys)
+                .map_err(|key| format!("keybinds.{name}: unrecognized key-code '{key}'"))?;
+
+            keybinds.insert(String::from(name), keybind);
+        }
+
+        Ok(keybinds)
+    }
+
+    /// Creates a [`Keybind`] from a given keybinding in the [`Config`],
+    /// or returns the first key that is not a recognized key-code.
+    fn keybind_from_config(cfg_value: &Vec<String>) -> Result<Keybind, String> {
         let mut keybind = Keybind::empty();
 
         for key in cfg_value {
-            keybind.add(
-                KeyCode::from_string(key.as_str())
-                    .expect("config contains an invalid keybind (unrecognized key-code)"),
-            );
+            keybind.add(KeyCode::from_string(key.as_str()).ok_or_else(|| key.clone())?);
         }
 
-        keybind
+        Ok(keybind)
     }
 
     /// Sets the autostart registry value if `enabled` is true.
@@ -193,7 +722,7 @@ impl App {
                 "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run",
                 "xterminate",
                 registry::ValueType::Sz,
-                executable_path().as_str(),
+                std::env::current_exe().expect("failed to get path to executable"),
             );
         } else if registry::exists(
             // Todo: duplicated code, fn autostart() already exists
@@ -212,7 +741,8 @@ impl App {
     }
 
     /// Returns true if the autostart registry value exists or false otherwise.
-    fn autostart() -> bool {
+    #[must_use]
+    pub fn autostart() -> bool {
         registry::exists(
             registry::HKey::HKeyCurrentUser,
             "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run",
@@ -250,7 +780,8 @@ impl App {
     }
 
     /// Returns true if xterminate is set to check for updates on startup.
-    fn autoupdate() -> bool {
+    #[must_use]
+    pub fn autoupdate() -> bool {
         !registry::exists(
             registry::HKey::HKeyCurrentUser,
             "SOFTWARE\\xterminate",
@@ -258,56 +789,285 @@ impl App {
         )
     }
 
+    /// Asks the user where to save a diagnostics bundle and writes it there.
+    fn export_diagnostics(&self) {
+        let Some(path) = filedialog::save_file(
+            &tr!("dialog.diagnostics.title"),
+            &diagnostics::default_file_name(),
+            &tr!("dialog.diagnostics.filter"),
+            "zip",
+        ) else {
+            return;
+        };
+
+        logf!("Exporting diagnostics bundle to '{}'", path.display());
+
+        match diagnostics::create_bundle(&path, &self.keybinds) {
+            Ok(()) => {
+                self.notifications
+                    .info(tr!("notification.diagnostics_saved", path = path.display()));
+            }
+
+            Err(error) => {
+                logf!("ERROR: failed to export diagnostics bundle: {error}");
+
+                self.notifications
+                    .error(tr!("notification.diagnostics_failed", error = error));
+            }
+        }
+    }
+
+    /// Performs an action selected for an entry of the "Recently terminated" tray submenu.
+    fn handle_recent_action(&mut self, id: u16, action: RecentAction) {
+        let Some(entry) = self.history.get(id).cloned() else {
+            logf!("WARNING: history entry (id: {id}) is no longer available");
+            return;
+        };
+
+        logf!(
+            "Selected {action:?} for recently terminated {} (pid: {})",
+            entry.name,
+            entry.pid
+        );
+
+        match action {
+            RecentAction::Relaunch => {
+                if let Some(launch) = &entry.launch {
+                    self.relaunch(launch);
+                }
+            }
+
+            RecentAction::CopyPath => {
+                if let Err(error) = clipboard::set_text(&entry.path) {
+                    logf!("ERROR: failed to copy path to clipboard: {error}");
+
+                    self.notifications
+                        .error(tr!("notification.copy_path_failed", error = error));
+                }
+            }
+
+            RecentAction::OpenFolder => {
+                let select = format!("/select,\"{}\"", entry.path);
+
+                if let Err(error) = run_executable("C:\\Windows\\explorer.exe", &[select.as_str()])
+                {
+                    logf!("ERROR: failed to open containing folder: {error}");
+
+                    self.notifications
+                        .error(tr!("notification.open_folder_failed", error = error));
+                }
+            }
+
+            RecentAction::Protect => self.protect(&entry.name),
+        }
+    }
+
+    /// Adds a rule protecting processes with the specified executable name
+    /// to the config file, unless such a rule exists already.
+    fn protect(&mut self, name: &str) {
+        let mut config = self.config.borrow_mut();
+
+        let exists = config.rules.iter().any(|rule| {
+            rule.protected == Some(true)
+                && rule
+                    .process
+                    .as_ref()
+                    .is_some_and(|process| process.eq_ignore_ascii_case(name))
+                && rule.path.is_none()
+                && rule.user.is_none()
+                && rule.elevated.is_none()
+        });
+
+        if !exists {
+            logf!("Adding {name} to the protected processes");
+
+            // Inserted first, so it takes precedence over rules allowing the process
+            config.rules.insert(
+                0,
+                Rule {
+                    process: Some(name.to_owned()),
+                    protected: Some(true),
+                    ..Rule::default()
+                },
+            );
+
+            config::save(&config, &self.config_path);
+        }
+
+        self.notifications
+            .info(tr!("notification.protected", name = name));
+    }
+
+    /// Tells the user about a crash report written when xterminate last
+    /// crashed, if any, and offers to open the folder containing it.
+    fn offer_crash_report(&self) {
+        let Some(report) = crash::take_pending() else {
+            return;
+        };
+
+        logf!("Found crash report from previous run: {}", report.display());
+
+        let answer = self.dialogs.confirm(
+            &Dialog::new(
+                Severity::Warning,
+                tr!("dialog.crashed.title"),
+                tr!("dialog.crashed.heading"),
+                tr!("dialog.crashed.content", path = report.display()),
+            ),
+            None,
+        );
+
+        if answer.value {
+            let select = format!("/select,{}", report.display());
+
+            if let Err(result) = run_executable("C:\\Windows\\explorer.exe", &[select.as_str()]) {
+                logf!("ERROR: failed to open crash report directory: {result}");
+            }
+        }
+    }
+
+    /// Reads the config file again and applies it, so that edits take effect
+    /// without restarting xterminate. If the config cannot be read or contains
+    /// invalid keybinds, the current config is kept. Paused hotkeys stay paused.
+    fn reload_config(&mut self) {
+        logf!("Reloading application configuration");
+
+        let config = match config::reload() {
+            Ok(config) => config,
+            Err(error) => {
+                logf!("ERROR: failed to reload config: {error}");
+                self.notifications
+                    .error(tr!("notification.config_not_reloaded", error = error));
+                return;
+            }
+        };
+
+        let keybinds = match Self::try_setup_keybinds(&config) {
+            Ok(keybinds) => keybinds,
+            Err(error) => {
+                logf!("ERROR: failed to reload config: {error}");
+                self.notifications
+                    .error(tr!("notification.config_not_reloaded", error = error));
+                return;
+            }
+        };
+
+        let mut warnings = crate::logger::configure(&config.logging);
+        warnings.extend(crate::locale::configure(&config.locale));
+
+        let (cursors, cursor_warnings) = Self::load_cursors(&config, &self.config_path);
+        warnings.extend(cursor_warnings);
+
+        {
+            let current = self.config.borrow();
+
+            if config.watchdog.enabled != current.watchdog.enabled
+                || config.watchdog.interval_seconds != current.watchdog.interval_seconds
+            {
+                let warning = "watchdog: enabling, disabling or changing the interval \
+                    of the watchdog takes effect after restarting xterminate";
+
+                logf!("WARNING: {warning}");
+                warnings.push(warning.to_owned());
+            }
+        }
+
+        self.keybinds = keybinds;
+        self.cursors = cursors;
+        self.hang_tracker = HangTracker::new(std::time::Duration::from_secs(
+            config.watchdog.threshold_seconds,
+        ));
+        self.audit = Self::open_audit_log(&config);
+        self.notifications
+            .set_verbosity(config.notifications.verbosity);
+        self.config_warnings = warnings;
+
+        *self.config.borrow_mut() = config;
+
+        self.notify_config_warnings(&tr!("notification.config_reloaded"));
+    }
+
+    /// Tells the user how many problems were found in the config when it was
+    /// last loaded, e.g. "Config reloaded with 2 warnings".
+    fn notify_config_warnings(&self, action: &str) {
+        match self.config_warnings.len() {
+            0 => self.notifications.info(action),
+            1 => self.notifications.warning(tr!(
+                "notification.config_warning",
+                action = action,
+                warning = self.config_warnings[0]
+            )),
+            count => self.notifications.warning(trn!(
+                "notification.config_warnings",
+                count,
+                action = action
+            )),
+        }
+    }
+
     /// Runs an update-check followed by a self-update if the user agrees to updating.
     ///
     /// # Arguments
     ///
-    /// * `verbose` - If no update is found and `verbose` is true, a dialog is
-    ///               displayed informing the user that they have the latest version.
-    fn update_check(verbose: bool) {
+    /// * `verbose` - If true, the check was started by the user, who is notified
+    ///               if they have the latest version or if the check failed.
+    fn update_check(&mut self, verbose: bool) {
         logf!("Checking for updates...");
 
-        if let Some(version) = crate::updater::check() {
+        let release = match crate::updater::check() {
+            Ok(release) => release,
+            Err(error) => {
+                let message = tr!("notification.update_check_failed", error = error);
+
+                if verbose {
+                    self.notifications.error(message);
+                } else {
+                    self.notifications.warning(message);
+                }
+
+                return;
+            }
+        };
+
+        if let Some(version) = release {
             logf!(
                 "A new version of xterminate was found (v{})",
                 version.version
             );
 
-            let result = taskdialog::TaskDialog::new()
-                .set_title("Update xterminate")
-                .set_icon(taskdialog::TaskDialogIcon::InformationIcon)
-                .set_heading(format!(
-                    "Update xterminate from v{} to v{}?",
-                    env!("CARGO_PKG_VERSION"),
-                    version.version
-                ))
-                .set_content(
-                    "A new version of xterminate was found! Do you wish to download the update now?",
-                )
-                .add_button(taskdialog::TaskDialogAction::Yes)
-                .add_button(taskdialog::TaskDialogAction::No)
-                .set_verification("Do not check for updates in the future", !Self::autoupdate())
-                .display()
-                .result();
+            self.update_available = Some(version.version.clone());
+
+            let answer = self.dialogs.confirm(
+                &Dialog::new(
+                    Severity::Information,
+                    tr!("dialog.update.title"),
+                    tr!(
+                        "dialog.update.heading",
+                        current = env!("CARGO_PKG_VERSION"),
+                        version = version.version
+                    ),
+                    tr!("dialog.update.content"),
+                ),
+                Some(&Verification {
+                    label: tr!("dialog.update.dont_check"),
+                    checked: !Self::autoupdate(),
+                }),
+            );
 
-            if result.verified {
+            if answer.verified {
                 logf!("User wishes not to be reminded of updates in the future");
                 Self::set_autoupdate(false);
             } else {
                 Self::set_autoupdate(true);
             }
 
-            if let taskdialog::TaskDialogAction::Yes = result.action {
+            if answer.value {
                 logf!("User wants to download and install the update");
 
                 // Probably not the greatest idea but it works
                 let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
 
-                tokio_runtime.block_on(
-                    std::thread::spawn(|| async { updater::update(version).await })
-                        .join()
-                        .unwrap(),
-                );
+                tokio_runtime.block_on(updater::update(version, self.dialogs.as_ref()));
             } else {
                 logf!("User does not want to download the update");
             }
@@ -315,25 +1075,546 @@ impl App {
             logf!("No new update was found");
 
             if verbose {
-                taskdialog::TaskDialog::new()
-                    .set_title("Up-to-date")
-                    .set_heading("You are up-to-date")
-                    .set_content("You already have the latest version of xterminate.")
-                    .display()
-                    .result();
+                self.notifications.info(tr!(
+                    "notification.up_to_date",
+                    version = env!("CARGO_PKG_VERSION")
+                ));
+            }
+        }
+    }
+
+    /// Forces the process associated with the specified [Window] to terminate.
+    /// If `confirm` is true or a rule requires it, the user is asked first and
+    /// may choose to terminate the processes started by it as well. Nothing is
+    /// done if the process is protected by a rule or the user cancelled.
+    ///
+    /// The process is waited for on a worker thread, and the outcome is
+    /// reported by [`Self::terminated`] once it has exited or timed out.
+    fn terminate(&mut self, window: &mut Window, trigger: Trigger, confirm: bool) {
+        let mut target_process = match window.try_process() {
+            Ok(process) => process,
+            Err(error) => {
+                logf!(
+                    "WARNING: unable to open the process of window {} [{error}], it may have exited",
+                    window.handle()
+                );
+
+                return;
+            }
+        };
+
+        let info = target_process.info();
+
+        if self.refuse_protected(&info) {
+            return;
+        }
+
+        let mut tree = Vec::new();
+
+        if rules::requires_confirmation(&self.config.borrow().rules, &info, confirm) {
+            let descendants = process::descendants(info.pid);
+
+            let title = window.title().to_string_lossy().into_owned();
+
+            match self.confirm(&title, &info, descendants.len(), false) {
+                Confirmation::Accept => {}
+                Confirmation::AcceptTree => tree = descendants,
+                Confirmation::Cancel => {
+                    logf!("User cancelled the termination of {}", info.name);
+                    return;
+                }
+            }
+        }
+
+        logf!("Will terminate process {info}");
+
+        let launch = target_process.launch_spec();
+        if launch.is_none() {
+            logf!(
+                "WARNING: unable to capture launch information for {}, it cannot be relaunched",
+                info.name
+            );
+        }
+
+        if let Err(error) = target_process.terminate() {
+            self.report_terminate_failed(&info, &error);
+            return;
+        }
+
+        self.suspended.remove(&info.pid);
+
+        self.await_termination(
+            target_process,
+            Termination {
+                action: AuditAction::Terminate,
+                trigger,
+                info,
+                launch,
+                tree,
+            },
+        );
+    }
+
+    /// Waits for a process that has been told to terminate on a worker thread,
+    /// so that the hotkey handler or menu item that terminated it returns right
+    /// away. Once it has exited, the processes in [`Termination::tree`] are
+    /// terminated as well. The outcomes are handled by [`Self::terminated`].
+    fn await_termination(&self, process: Process, termination: Termination) {
+        use windows::Win32::System::Threading::GetCurrentThreadId;
+
+        let rules = self.config.borrow().rules.clone();
+        let sender = self.terminations.sender.clone();
+        let thread_id = unsafe { GetCurrentThreadId() };
+
+        std::thread::spawn(move || {
+            let outcome = process.outcome(process::TERMINATION_TIMEOUT);
+
+            logf!(
+                "Termination outcome for process (pid: {}): {outcome}",
+                process.id()
+            );
+
+            let descendants = terminate_descendants(&termination.tree, &rules);
+
+            let terminated = Terminated {
+                termination,
+                outcome,
+                descendants,
+            };
+
+            if sender.send(terminated).is_ok() {
+                wake_event_loop(thread_id, WM_TERMINATED);
+            }
+        });
+    }
+
+    /// Reports the outcomes of the terminations waited for by
+    /// [`Self::await_termination`] and relaunches restarted processes.
+    pub fn terminated(&mut self) {
+        while let Ok(terminated) = self.terminations.receiver.try_recv() {
+            let Terminated {
+                termination,
+                outcome,
+                descendants,
+            } = terminated;
+
+            let Termination {
+                action,
+                trigger,
+                info,
+                launch,
+                tree,
+            } = termination;
+
+            let ProcessInfo {
+                name, pid, path, ..
+            } = info.clone();
+
+            self.report_termination(&name, pid, outcome);
+            self.record_audit(action, trigger, info, outcome);
+
+            if !tree.is_empty() {
+                self.report_descendants(&name, descendants, trigger);
+            }
+
+            self.history
+                .push(HistoryEntry::new(name, pid, path, outcome, launch.clone()));
+
+            if action == AuditAction::Restart {
+                match launch {
+                    Some(launch) if outcome == TerminationOutcome::Exited => {
+                        self.relaunch(&launch);
+                    }
+
+                    _ => logf!(
+                        "ERROR: unable to restart process (pid: {pid}): it did not exit ({outcome})"
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Audits the processes terminated along with a process that has been
+    /// terminated, and tells the user how many of them have exited.
+    fn report_descendants(
+        &mut self,
+        parent: &str,
+        descendants: Vec<(ProcessInfo, TerminationOutcome)>,
+        trigger: Trigger,
+    ) {
+        let mut terminated = 0;
+
+        for (info, outcome) in descendants {
+            if outcome == TerminationOutcome::Exited {
+                terminated += 1;
+            }
+
+            self.record_audit(AuditAction::Terminate, trigger, info, outcome);
+        }
+
+        self.kills += terminated;
+
+        logf!("Terminated {parent} and {terminated} child processes");
+
+        let message = trn!("action.terminated_tree", terminated, name = parent);
+
+        self.notifications.info(&message);
+        self.last_action = Some(message);
+    }
+
+    /// Logs that a process could not be terminated and notifies the user about it.
+    fn report_terminate_failed(&mut self, info: &ProcessInfo, error: &windows::core::Error) {
+        logf!("ERROR: failed to terminate process {info} [{error}]");
+
+        self.last_action = Some(tr!("action.terminate_failed", name = info.name));
+
+        self.notifications.error(tr!(
+            "notification.terminate_failed",
+            name = info.name,
+            pid = info.pid,
+            error = error
+        ));
+    }
+
+    /// Asks the user whether to terminate or restart the process of the window
+    /// titled `window_title`, showing its details and the number of processes it
+    /// started. Unless cancelled, the user can choose not to be asked again for the process.
+    fn confirm(
+        &mut self,
+        window_title: &str,
+        info: &ProcessInfo,
+        descendants: usize,
+        restart: bool,
+    ) -> Confirmation {
+        let (title, heading, label, countdown_footer) = if restart {
+            (
+                tr!("dialog.confirm.restart_title"),
+                tr!("dialog.confirm.restart_heading", name = info.name),
+                tr!("dialog.confirm.restart"),
+                tr!("dialog.confirm.restart_countdown"),
+            )
+        } else {
+            (
+                tr!("dialog.confirm.terminate_title"),
+                tr!("dialog.confirm.terminate_heading", name = info.name),
+                tr!("dialog.confirm.terminate"),
+                tr!("dialog.confirm.terminate_countdown"),
+            )
+        };
+
+        let countdown_seconds = self.config.borrow().confirm.countdown_seconds;
+
+        let mut choices = vec![Choice {
+            id: CONFIRM_ACCEPT_ID,
+            label,
+            note: None,
+        }];
+
+        if !restart && descendants > 0 {
+            choices.push(Choice {
+                id: CONFIRM_ACCEPT_TREE_ID,
+                label: tr!("dialog.confirm.terminate_tree"),
+                note: Some(trn!(
+                    "dialog.confirm.terminate_tree_note",
+                    descendants,
+                    name = info.name
+                )),
+            });
+        }
+
+        let countdown = (countdown_seconds > 0).then_some(Countdown {
+            seconds: countdown_seconds,
+            choice: CONFIRM_ACCEPT_ID,
+            footer: countdown_footer,
+        });
+
+        let answer = self.dialogs.choice(
+            &Dialog::new(
+                Severity::Warning,
+                title,
+                heading,
+                trn!(
+                    "dialog.confirm.content",
+                    descendants,
+                    title = window_title,
+                    path = info.path
+                ),
+            )
+            .with_details(info.to_string()),
+            &choices,
+            Some(&Verification {
+                label: tr!("dialog.confirm.dont_ask_again", name = info.name),
+                checked: false,
+            }),
+            countdown.as_ref(),
+        );
+
+        let confirmation = match answer.value {
+            Some(CONFIRM_ACCEPT_ID) => Confirmation::Accept,
+            Some(CONFIRM_ACCEPT_TREE_ID) => Confirmation::AcceptTree,
+            _ => Confirmation::Cancel,
+        };
+
+        if answer.verified && confirmation != Confirmation::Cancel {
+            self.skip_confirmation(&info.name);
+        }
+
+        confirmation
+    }
+
+    /// Adds a rule to the config file that stops xterminate from asking
+    /// for confirmation before acting on processes with the specified name.
+    fn skip_confirmation(&mut self, name: &str) {
+        logf!("No longer asking for confirmation before acting on {name}");
+
+        let mut config = self.config.borrow_mut();
+
+        config.rules.push(Rule {
+            process: Some(name.to_owned()),
+            confirm: Some(false),
+            ..Rule::default()
+        });
+
+        config::save(&config, &self.config_path);
+    }
+
+    /// Returns true and tells the user if the process is protected
+    /// by a rule and must not be terminated, restarted or suspended.
+    fn refuse_protected(&mut self, info: &ProcessInfo) -> bool {
+        if !rules::is_protected(&self.config.borrow().rules, info) {
+            return false;
+        }
+
+        logf!("WARNING: refusing to act on protected process {info}");
+
+        self.last_action = Some(tr!("action.refused", name = info.name));
+
+        self.notifications
+            .warning(tr!("notification.refused", name = info.name));
+
+        true
+    }
+
+    /// Terminates the process associated with the specified [`Window`] and,
+    /// once it has exited, starts a new instance of it using the same command
+    /// line, working directory and environment, see [`Self::terminated`].
+    fn restart(&mut self, window: &mut Window, trigger: Trigger, confirm: bool) {
+        let mut target_process = match window.try_process() {
+            Ok(process) => process,
+            Err(error) => {
+                logf!(
+                    "WARNING: unable to open the process of window {} [{error}], it may have exited",
+                    window.handle()
+                );
+
+                return;
+            }
+        };
+
+        let info = target_process.info();
+
+        if self.refuse_protected(&info) {
+            return;
+        }
+
+        if rules::requires_confirmation(&self.config.borrow().rules, &info, confirm) {
+            let descendants = process::descendants(info.pid).len();
+
+            let title = window.title().to_string_lossy().into_owned();
+
+            if self.confirm(&title, &info, descendants, true) == Confirmation::Cancel {
+                logf!("User cancelled the restart of {}", info.name);
+                return;
+            }
+        }
+
+        logf!("Will restart process {info}");
+
+        let Some(launch) = target_process.launch_spec() else {
+            logf!(
+                "ERROR: unable to restart {}: failed to capture launch information",
+                info.name
+            );
+
+            self.notifications
+                .error(tr!("notification.restart_failed", name = info.name));
+
+            return;
+        };
+
+        if let Err(error) = target_process.terminate() {
+            self.report_terminate_failed(&info, &error);
+            return;
+        }
+
+        self.await_termination(
+            target_process,
+            Termination {
+                action: AuditAction::Restart,
+                trigger,
+                info,
+                launch: Some(launch),
+                tree: Vec::new(),
+            },
+        );
+    }
+
+    /// Appends a record of an action to the audit log, if enabled.
+    fn record_audit(
+        &self,
+        action: AuditAction,
+        trigger: Trigger,
+        target: ProcessInfo,
+        outcome: TerminationOutcome,
+    ) {
+        if let Some(audit) = &self.audit {
+            if let Err(error) = audit.record(action, trigger, target, outcome) {
+                logf!("ERROR: failed to write audit record: {error}");
             }
         }
     }
 
-    /// Forces the process associated with the specified [Window]
-    /// to terminate. If `try_graceful` is true, an attempt will be
-    /// made to gracefully exit the window before a termination is made.
-    fn terminate(window: &mut Window) {
-        let target_process = &mut window.process();
+    /// Logs the outcome of a termination and notifies the user about it.
+    fn report_termination(&mut self, name: &str, pid: u32, outcome: TerminationOutcome) {
+        match outcome {
+            TerminationOutcome::Exited => {
+                logf!("Terminated {name} (pid: {pid}) successfully");
+
+                self.kills += 1;
+                self.last_action = Some(tr!("action.terminated", name = name));
+            }
+
+            TerminationOutcome::StillRunning => {
+                logf!("ERROR: {name} (pid: {pid}) is still running after termination");
 
-        logf!("Will terminate process {}", target_process);
+                self.last_action = Some(tr!("action.still_running", name = name));
+            }
+
+            TerminationOutcome::Zombie => {
+                logf!("ERROR: {name} (pid: {pid}) was terminated but has not finished exiting");
+
+                self.last_action = Some(tr!("action.stuck_exiting", name = name));
+            }
+        }
+
+        self.notifications
+            .notify(termination_notification(name, pid, outcome));
+    }
+
+    /// Starts a new instance of a previously terminated process.
+    fn relaunch(&mut self, launch: &LaunchSpec) -> bool {
+        logf!("Relaunching '{}'", launch.command_line.to_string_lossy());
+
+        if let Err(err) = launch.launch() {
+            logf!(
+                "ERROR: failed to relaunch '{}': {err}",
+                launch.path.display()
+            );
+
+            self.notifications.error(tr!(
+                "notification.relaunch_failed",
+                path = launch.path.display(),
+                error = err
+            ));
+
+            return false;
+        }
+
+        if let Some(name) = launch.path.file_name() {
+            self.last_action = Some(tr!("action.relaunched", name = name.to_string_lossy()));
+        }
+
+        true
+    }
+
+    /// Pauses all keybinds except the one resuming them, e.g. while playing a game
+    /// that uses the same keys. If configured, a timer is started that resumes them.
+    pub fn pause_hotkeys(&mut self) {
+        use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+
+        if self.pause.is_some() {
+            return;
+        }
+
+        if self.appstate == AppState::Active {
+            self.termination_mode_deactivate();
+        }
+
+        let minutes = self.config.borrow().pause.resume_after_minutes;
+
+        let mut pause = Pause {
+            resume_at: None,
+            timer: None,
+        };
+
+        if minutes > 0 {
+            let interval_ms = u32::try_from(minutes.saturating_mul(60 * 1000)).unwrap_or(u32::MAX);
+
+            let timer =
+                unsafe { SetTimer(windows::Win32::Foundation::HWND(0), 0, interval_ms, None) };
+
+            if timer == 0 {
+                logf!("ERROR: failed to start pause timer [{}]", unsafe {
+                    GetLastError().unwrap_err()
+                });
+            } else {
+                pause.timer = Some(timer);
+                pause.resume_at = Some(
+                    chrono::Local::now() + chrono::Duration::milliseconds(i64::from(interval_ms)),
+                );
+            }
+        }
+
+        match pause.resume_at {
+            Some(resume_at) => {
+                logf!("Hotkeys paused until {resume_at}");
+                self.notifications.info(tr!(
+                    "notification.hotkeys_paused_until",
+                    time = resume_at.format("%H:%M")
+                ));
+            }
+
+            None => {
+                logf!("Hotkeys paused");
+                self.notifications.info(tr!("notification.hotkeys_paused"));
+            }
+        }
+
+        self.pause = Some(pause);
+    }
+
+    /// Resumes the keybinds paused by [`Self::pause_hotkeys`].
+    pub fn resume_hotkeys(&mut self) {
+        let Some(pause) = self.pause.take() else {
+            return;
+        };
+
+        if let Some(timer) = pause.timer {
+            unsafe {
+                let _ = windows::Win32::UI::WindowsAndMessaging::KillTimer(
+                    windows::Win32::Foundation::HWND(0),
+                    timer,
+                );
+            }
+        }
+
+        logf!("Hotkeys resumed");
+        self.notifications.info(tr!("notification.hotkeys_resumed"));
+    }
 
-        target_process.terminate();
+    /// Returns the state the appearance of the tray icon is based on.
+    #[must_use]
+    pub fn tray_status(&self) -> TrayStatus {
+        TrayStatus {
+            state: self.appstate,
+            hotkeys_paused: self.pause.is_some(),
+            resume_at: self.pause.as_ref().and_then(|pause| pause.resume_at),
+            update_available: self.update_available.clone(),
+            terminate_click_keybind: self.keybinds["terminate_click"].to_string(),
+            terminate_immediate_keybind: self.keybinds["terminate_immediate"].to_string(),
+            kills: self.kills,
+            last_action: self.last_action.clone(),
+        }
     }
 
     pub fn shutdown(&mut self) {
@@ -341,44 +1622,259 @@ impl App {
         self.appstate = AppState::Shutdown;
     }
 
-    /// Called when going from [`AppState::Standby`] to [`AppState::Active`].
-    /// Sets the system cursors to the xterminate cursor.
+    /// Called when going from [`AppState::Standby`] to [`AppState::Active`],
+    /// either by a keybind or from the tray menu as told by `trigger`.
+    /// Sets the system cursors to the cursor of the click mode `action` enters.
     ///
     /// # Panics
     /// Will panic if loading the system cursor fails.
     /// See [`windows::Windows::Win32::UI::WindowsAndMessaging::LoadImageA`].
-    pub fn termination_mode_activate(&mut self) {
-        logf!("Termination mode activated by user");
+    pub fn termination_mode_activate(&mut self, action: ClickAction, trigger: Trigger) {
+        logf!("Click mode {action:?} activated by user");
         self.appstate = AppState::Active;
+        self.activated_by = trigger;
+        self.click_action = action;
 
         logf!("Switching to active cursor");
         // Customize the system cursors to signify that xterminate is active
-        let custom_cursor = Cursor::load_from_file(self.cursor_path.as_str())
-            .expect("failed to load default cursor from file");
-        cursor::set_all(&custom_cursor);
+        self.set_cursors(&self.click_cursor());
+
+        // Info mode does not act on the process, so it is never refused
+        if self.cursors.forbidden.is_some() && action != ClickAction::Info {
+            self.start_cursor_timer();
+        }
+    }
+
+    /// Returns the cursor of the active click mode.
+    fn click_cursor(&self) -> std::path::PathBuf {
+        match self.click_action {
+            ClickAction::Terminate => self.cursors.terminate.clone(),
+            ClickAction::Suspend => self.cursors.suspend.clone(),
+            ClickAction::Info => self.cursors.info.clone(),
+        }
+    }
+
+    /// Sets all system cursors to the cursor in the specified file, falling
+    /// back to the cursor bundled with xterminate if it cannot be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if loading the bundled cursor fails.
+    fn set_cursors(&self, path: &std::path::Path) {
+        if let Err(error) = cursor::set_all_from_file(&path.display().to_string()) {
+            logf!(
+                "ERROR: failed to load cursor '{}', using default cursor: {error}",
+                path.display()
+            );
+
+            cursor::set_all_from_file(&cursor_path())
+                .expect("failed to load default cursor from file");
+        }
+    }
+
+    /// Starts the thread timer that triggers [`Self::termination_mode_hover`].
+    fn start_cursor_timer(&mut self) {
+        use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+
+        let timer = unsafe {
+            SetTimer(
+                windows::Win32::Foundation::HWND(0),
+                0,
+                CURSOR_HOVER_INTERVAL_MS,
+                None,
+            )
+        };
+
+        if timer == 0 {
+            logf!("ERROR: failed to start cursor timer [{}]", unsafe {
+                GetLastError().unwrap_err()
+            });
+
+            return;
+        }
+
+        self.cursor_timer = Some(timer);
+    }
+
+    /// Stops the timer started by [`Self::start_cursor_timer`], if running.
+    fn stop_cursor_timer(&mut self) {
+        if let Some(timer) = self.cursor_timer.take() {
+            unsafe {
+                let _ = windows::Win32::UI::WindowsAndMessaging::KillTimer(
+                    windows::Win32::Foundation::HWND(0),
+                    timer,
+                );
+            }
+        }
+
+        self.hovered = None;
     }
 
-    /// Called when the termination mode is active ([`Self::appstate`] == [`AppState::Active`]) and
-    /// the confirmation keybind is pressed by the user. This will trigger
-    /// termination of the window the mouse cursor is currently hovering over.
+    /// Called periodically while termination or suspend mode is active. Shows the
+    /// forbidden cursor while the mouse pointer is over a window whose process is
+    /// protected by a rule, and the cursor of the active mode otherwise.
+    pub fn termination_mode_hover(&mut self) {
+        let Some(forbidden) = self.cursors.forbidden.clone() else {
+            return;
+        };
+
+        let (cursor_x, cursor_y) = cursor::position();
+        let Some(window) = Window::from_point(cursor_x, cursor_y) else {
+            return;
+        };
+
+        let pid = window.process_id();
+
+        if self.hovered.is_some_and(|(hovered, _)| hovered == pid) {
+            return;
+        }
+
+        let protected = Process::try_open(pid)
+            .is_ok_and(|process| rules::is_protected(&self.config.borrow().rules, &process.info()));
+
+        let was_protected = self.hovered.is_some_and(|(_, protected)| protected);
+        self.hovered = Some((pid, protected));
+
+        if protected != was_protected {
+            self.set_cursors(&if protected {
+                forbidden
+            } else {
+                self.click_cursor()
+            });
+        }
+    }
+
+    /// Called when a click mode is active ([`Self::appstate`] == [`AppState::Active`]) and
+    /// the confirmation keybind is pressed by the user. This will trigger the
+    /// [`ClickAction`] on the window the mouse cursor is currently hovering over.
     pub fn termination_mode_confirm(&mut self) {
-        logf!("Termination confirmed by user");
+        logf!("Click mode {:?} confirmed by user", self.click_action);
 
-        // Terminate process under the cursor and reset
+        // Act on the process under the cursor and reset
         // the system cursors back to the default ones.
+        self.stop_cursor_timer();
         cursor::reset();
 
+        let confirm = self.config.borrow().confirm.terminate_click;
+
         let (cursor_x, cursor_y) = cursor::position();
         if let Some(window) = &mut Window::from_point(cursor_x, cursor_y) {
-            Self::terminate(window);
-            logf!("Terminated successfully");
+            match self.click_action {
+                ClickAction::Terminate => self.terminate(window, self.activated_by, confirm),
+                ClickAction::Suspend => self.toggle_suspended(window),
+                ClickAction::Info => self.show_info(window),
+            }
         } else {
-            logf!("ERROR: Failed to terminate: no window under mouse pointer");
+            logf!("ERROR: Failed to act on window: no window under mouse pointer");
         }
 
         self.appstate = AppState::Standby;
     }
 
+    /// Suspends the process of `window`, or resumes it if it was suspended
+    /// by xterminate. Protected processes are not suspended.
+    fn toggle_suspended(&mut self, window: &Window) {
+        let info = match window.try_process() {
+            Ok(process) => process.info(),
+            Err(error) => {
+                logf!(
+                    "WARNING: unable to open the process of window {} [{error}], it may have exited",
+                    window.handle()
+                );
+
+                return;
+            }
+        };
+
+        if self.suspended.contains(&info.pid) {
+            match process::resume(info.pid) {
+                Ok(threads) => {
+                    logf!("Resumed {threads} threads of process {info}");
+                    self.suspended.remove(&info.pid);
+                    self.last_action = Some(tr!("action.resumed", name = info.name));
+                    self.notifications.info(tr!(
+                        "notification.resumed",
+                        name = info.name,
+                        pid = info.pid
+                    ));
+                }
+
+                Err(error) => {
+                    logf!("ERROR: failed to resume process {info} [{error}]");
+                    self.notifications.error(tr!(
+                        "notification.resume_failed",
+                        name = info.name,
+                        pid = info.pid,
+                        error = error
+                    ));
+                }
+            }
+
+            return;
+        }
+
+        if self.refuse_protected(&info) {
+            return;
+        }
+
+        match process::suspend(info.pid) {
+            Ok(threads) => {
+                logf!("Suspended {threads} threads of process {info}");
+                self.suspended.insert(info.pid);
+                self.last_action = Some(tr!("action.suspended", name = info.name));
+                self.notifications.info(tr!(
+                    "notification.suspended",
+                    name = info.name,
+                    pid = info.pid
+                ));
+            }
+
+            Err(error) => {
+                logf!("ERROR: failed to suspend process {info} [{error}]");
+                self.notifications.error(tr!(
+                    "notification.suspend_failed",
+                    name = info.name,
+                    pid = info.pid,
+                    error = error
+                ));
+            }
+        }
+    }
+
+    /// Shows the details of the process of `window` in a dialog.
+    fn show_info(&self, window: &Window) {
+        let info = match window.try_process() {
+            Ok(process) => process.info(),
+            Err(error) => {
+                logf!(
+                    "WARNING: unable to open the process of window {} [{error}], it may have exited",
+                    window.handle()
+                );
+
+                return;
+            }
+        };
+
+        let descendants = process::descendants(info.pid).len();
+
+        self.dialogs.message(
+            &Dialog::new(
+                Severity::Information,
+                tr!("dialog.info.title"),
+                tr!("dialog.info.heading", name = info.name),
+                trn!(
+                    "dialog.info.content",
+                    descendants,
+                    title = window.title().to_string_lossy(),
+                    path = info.path,
+                    pid = info.pid
+                ),
+            )
+            .with_details(info.to_string()),
+            false,
+        );
+    }
+
     /// Called if the user presses the escape key while in the [`AppState::Active`] state.
     /// Resets the cursor back to system defaults.
     pub fn termination_mode_deactivate(&mut self) {
@@ -386,6 +1882,7 @@ impl App {
         self.appstate = AppState::Standby;
 
         logf!("Switching to normal cursor");
+        self.stop_cursor_timer();
         cursor::reset();
     }
 
@@ -395,26 +1892,81 @@ impl App {
     pub fn terminate_active(&mut self) -> bool {
         logf!("Immediate termination triggered by user");
 
+        let confirm = self.config.borrow().confirm.terminate_immediate;
+
         if let Some(window) = &mut Window::from_foreground() {
-            Self::terminate(window);
-            logf!("Terminated successfully");
+            self.terminate(window, Trigger::Hotkey, confirm);
             return true;
         }
 
         logf!("ERROR: failed to terminate foreground window: no valid window is in focus");
         false
     }
+
+    /// Called when the user presses the immediate restart keybind. Terminates
+    /// the currently focused window and starts a fresh instance of it.
+    pub fn restart_active(&mut self) -> bool {
+        logf!("Immediate restart triggered by user");
+
+        let confirm = self.config.borrow().confirm.restart_immediate;
+
+        if let Some(window) = &mut Window::from_foreground() {
+            self.restart(window, Trigger::Hotkey, confirm);
+            return true;
+        }
+
+        logf!("ERROR: failed to restart foreground window: no valid window is in focus");
+        false
+    }
+
+    /// Starts a new instance of the most recently terminated
+    /// process that can be relaunched.
+    pub fn relaunch_last(&mut self) {
+        logf!("Relaunch of last terminated process triggered by user");
+
+        match self.history.last_relaunchable() {
+            Some(entry) => {
+                let launch = entry.launch.clone().unwrap();
+                self.relaunch(&launch);
+            }
+
+            None => {
+                logf!("ERROR: failed to relaunch: no relaunchable process in history");
+            }
+        }
+    }
 }
 
 impl crate::input::EventHandler for App {
     fn handle(&mut self, mut state: KeyState, _keycode: KeyCode, _keystatus: KeyStatus) -> bool {
+        // While paused, keys are passed on to other applications unless they resume the hotkeys
+        if self.pause.is_some() {
+            if self.keybinds["pause_toggle"].triggered(&mut state) {
+                self.resume_hotkeys();
+                return true;
+            }
+
+            return false;
+        }
+
         match self.appstate {
             AppState::Standby => {
-                if self.keybinds["terminate_click"].triggered(&mut state) {
-                    self.termination_mode_activate();
+                if self.keybinds["pause_toggle"].triggered(&mut state) {
+                    self.pause_hotkeys();
+                    return true;
+                } else if self.keybinds["terminate_click"].triggered(&mut state) {
+                    self.termination_mode_activate(ClickAction::Terminate, Trigger::Hotkey);
+                    return true;
+                } else if self.keybinds["suspend_click"].triggered(&mut state) {
+                    self.termination_mode_activate(ClickAction::Suspend, Trigger::Hotkey);
+                    return true;
+                } else if self.keybinds["info_click"].triggered(&mut state) {
+                    self.termination_mode_activate(ClickAction::Info, Trigger::Hotkey);
                     return true;
                 } else if self.keybinds["terminate_immediate"].triggered(&mut state) {
                     return self.terminate_active();
+                } else if self.keybinds["restart_immediate"].triggered(&mut state) {
+                    return self.restart_active();
                 }
             }
 
@@ -458,32 +2010,52 @@ impl crate::tray::TrayEventHandler for App {
             }
 
             TrayEvent::OnMenuSelectEnterTerminationMode => {
-                self.termination_mode_activate();
+                self.termination_mode_activate(ClickAction::Terminate, Trigger::Tray);
             }
 
             TrayEvent::OnMenuSelectAbout => {
-                TaskDialog::new()
-                    .set_title("About xterminate")
-                    .set_icon(taskdialog::TaskDialogIcon::InformationIcon)
-                    .set_heading(format!("xterminate v{}", env!("CARGO_PKG_VERSION")))
-                    .set_content(
-                        "Easily terminate any windowed process by the press of a button.\
-                        \n\nThis software was created by <a href=\"https://github.com/imxela\">@imxela</a> and is licensed under the open-source \
-                        <a href=\"https://github.com/imxela/xterminate/blob/main/LICENSE\">MIT license</a>. \
-                        The source code is publicly available in xterminate's <a href=\"https://github.com/imxela/xterminate\">GitHub repository</a>.\n\n\
-                        Contact information can be found on my <a href=\"https://xela.me\">website</a>.\
-                        \n\nThank you for using my software! <3"
+                self.dialogs.message(
+                    &Dialog::new(
+                        Severity::Information,
+                        tr!("dialog.about.title"),
+                        tr!("dialog.about.heading", version = env!("CARGO_PKG_VERSION")),
+                        tr!("dialog.about.content"),
                     )
-                    .set_hyperlinks_enabled(true)
-                    .display();
+                    .with_hyperlinks(),
+                    false,
+                );
             }
 
             TrayEvent::OnMenuSelectOpenLoggingDirectory => {
                 open_logging_directory();
             }
 
+            TrayEvent::OnMenuSelectExportDiagnostics => {
+                self.export_diagnostics();
+            }
+
             TrayEvent::OnMenuSelectCheckForUpdates => {
-                Self::update_check(true);
+                self.update_check(true);
+            }
+
+            TrayEvent::OnMenuSelectReloadConfig => {
+                self.reload_config();
+            }
+
+            TrayEvent::OnMenuSelectPauseHotkeys => {
+                if self.pause.is_some() {
+                    self.resume_hotkeys();
+                } else {
+                    self.pause_hotkeys();
+                }
+            }
+
+            TrayEvent::OnMenuSelectRelaunchLast => {
+                self.relaunch_last();
+            }
+
+            TrayEvent::OnMenuSelectRecent { entry, action } => {
+                self.handle_recent_action(entry, action);
             }
 
             TrayEvent::OnMenuSelectUpdateOnStartup => {
@@ -496,6 +2068,74 @@ impl crate::tray::TrayEventHandler for App {
             }
         }
     }
+
+    fn menu_state(&self) -> MenuState {
+        let keybind = |name: &str| self.keybinds[name].to_string();
+
+        MenuState {
+            terminate_click_keybind: keybind("terminate_click"),
+            terminate_immediate_keybind: keybind("terminate_immediate"),
+            restart_immediate_keybind: keybind("restart_immediate"),
+            pause_toggle_keybind: keybind("pause_toggle"),
+            hotkeys_paused: self.pause.is_some(),
+            recent: self.history.entries().cloned().collect(),
+            autostart: Self::autostart(),
+            autoupdate: Self::autoupdate(),
+        }
+    }
+}
+
+/// Wakes up the event loop running on the thread with the specified ID,
+/// which waits for messages, to handle the result of a worker thread.
+fn wake_event_loop(thread_id: u32, message: u32) {
+    use windows::Win32::Foundation::{LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::PostThreadMessageW;
+
+    if let Err(error) = unsafe { PostThreadMessageW(thread_id, message, WPARAM(0), LPARAM(0)) } {
+        logf!("ERROR: failed to wake up the event loop [{error}]");
+    }
+}
+
+/// Terminates the processes started by a process that has been terminated,
+/// which are not terminated along with it, and waits for them to exit.
+/// Protected processes and processes that have exited already are skipped.
+fn terminate_descendants(
+    descendants: &[u32],
+    rules: &[Rule],
+) -> Vec<(ProcessInfo, TerminationOutcome)> {
+    let mut outcomes = Vec::new();
+
+    if descendants.is_empty() {
+        return outcomes;
+    }
+
+    logf!("Terminating {} child processes", descendants.len());
+
+    for pid in descendants {
+        let Ok(mut process) = Process::try_open(*pid) else {
+            continue;
+        };
+
+        if process.wait_for_exit(std::time::Duration::ZERO) {
+            continue;
+        }
+
+        let info = process.info();
+
+        if rules::is_protected(rules, &info) {
+            logf!("WARNING: not terminating protected child process {info}");
+            continue;
+        }
+
+        if let Err(error) = process.terminate() {
+            logf!("ERROR: failed to terminate child process {info} [{error}]");
+            continue;
+        }
+
+        outcomes.push((info, process.outcome(process::TERMINATION_TIMEOUT)));
+    }
+
+    outcomes
 }
 
 /// Runs the specified executable with the given arguments passed to it.
@@ -504,44 +2144,87 @@ impl crate::tray::TrayEventHandler for App {
 ///
 /// Will return a [`windows::core::Error`] if the executable fails to run.
 /// Make sure `executable_path` points to a valid executable.
-#[allow(clippy::missing_panics_doc)]
 pub fn run_executable(executable_path: &str, args: &[&str]) -> Result<(), windows::core::Error> {
-    use windows::core::{PCSTR, PSTR};
+    let command_line = std::iter::once(executable_path)
+        .chain(args.iter().copied())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    run_executable_in(
+        std::path::Path::new(executable_path),
+        std::ffi::OsStr::new(&command_line),
+        None,
+        None,
+    )
+}
+
+/// Runs the specified executable with a complete command line. If
+/// `working_directory` or `environment` are `None`, they are inherited
+/// from xterminate.
+///
+/// # Arguments
+///
+/// * `command_line` - The full command line, including the executable itself.
+/// * `environment` - A nul-separated, double nul-terminated block of `KEY=VALUE` pairs.
+///
+/// # Errors
+///
+/// Will return a [`windows::core::Error`] if the executable fails to run.
+#[allow(clippy::missing_panics_doc)]
+pub fn run_executable_in(
+    executable_path: &std::path::Path,
+    command_line: &std::ffi::OsStr,
+    working_directory: Option<&std::path::Path>,
+    environment: Option<&[u16]>,
+) -> Result<(), windows::core::Error> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::CloseHandle;
     use windows::Win32::System::Threading::{
-        CreateProcessA, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION, STARTUPINFOA,
+        CreateProcessW, CREATE_UNICODE_ENVIRONMENT, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION,
+        STARTUPINFOW,
     };
 
-    let mut args_formatted = String::new();
+    let executable_path = wide::to_wide_nul(executable_path);
+    let working_directory = working_directory.map(wide::to_wide_nul);
 
-    let index = 0;
-    for arg in args {
-        if index > 0 {
-            args_formatted.push(' ');
-        }
+    // CreateProcessW may modify the command line buffer so it has to be mutable
+    let mut command_line = wide::to_wide_nul(command_line);
 
-        args_formatted.push_str(arg);
+    let mut creation_flags = PROCESS_CREATION_FLAGS(0);
+    if environment.is_some() {
+        creation_flags |= CREATE_UNICODE_ENVIRONMENT;
     }
 
-    let c_filepath = std::ffi::CString::new(format!("{executable_path} {args_formatted}")).unwrap();
+    let si = STARTUPINFOW {
+        cb: u32::try_from(std::mem::size_of::<STARTUPINFOW>()).unwrap(),
+        ..Default::default()
+    };
 
-    let si = STARTUPINFOA::default();
     let mut pi = PROCESS_INFORMATION::default();
 
     unsafe {
-        CreateProcessA(
-            // PCSTR(c_notepad_path.as_ptr().cast::<u8>()),
-            PCSTR(std::ptr::null()),
-            PSTR(c_filepath.into_raw().cast::<u8>()),
+        CreateProcessW(
+            PCWSTR(executable_path.as_ptr()),
+            PWSTR(command_line.as_mut_ptr()),
             None,
             None,
             false,
-            PROCESS_CREATION_FLAGS(0),
-            None,
-            PCSTR(std::ptr::null()),
+            creation_flags,
+            environment.map(|block| block.as_ptr().cast::<std::ffi::c_void>()),
+            working_directory
+                .as_ref()
+                .map_or(PCWSTR::null(), |directory| PCWSTR(directory.as_ptr())),
             &si,
             &mut pi,
-        )
+        )?;
+
+        // The handles are not needed since xterminate does not keep track of
+        // the processes it starts, so close them right away to avoid leaking them.
+        let _ = CloseHandle(pi.hThread);
+        let _ = CloseHandle(pi.hProcess);
     }
+
+    Ok(())
 }
 
 /// Open xterminate's 'config.toml' file for editing in notepad.exe.
@@ -571,11 +2254,6 @@ pub fn cursor_path() -> String {
     resource_path(CURSOR_FILENAME)
 }
 
-#[must_use]
-pub fn icon_path() -> String {
-    resource_path(ICON_FILENAME)
-}
-
 #[must_use]
 pub fn config_path() -> String {
     make_rel_appdata_path_abs(CONFIG_FILENAME)
@@ -590,6 +2268,39 @@ pub fn logfiles_path() -> String {
         .to_string()
 }
 
+/// Returns the absolute path of the audit log directory.
+#[must_use]
+pub fn audit_path() -> std::path::PathBuf {
+    make_rel_appdata_path_abs(AUDIT_PATH)
+}
+
+/// Returns the absolute path of the file that exists while the
+/// system cursors are swapped for the termination mode cursor.
+#[must_use]
+pub fn cursor_marker_path() -> std::path::PathBuf {
+    make_rel_appdata_path_abs(CURSOR_MARKER_FILENAME)
+}
+
+/// Returns the absolute path of the crash report directory.
+#[must_use]
+pub fn crash_reports_path() -> std::path::PathBuf {
+    make_rel_appdata_path_abs(CRASH_REPORTS_PATH)
+}
+
+/// Returns the absolute path of the key used to sign the audit log. It is
+/// kept outside of the audit log directory so it is not rotated with it.
+#[must_use]
+pub fn audit_key_path() -> std::path::PathBuf {
+    make_rel_appdata_path_abs(AUDIT_KEY_FILENAME)
+}
+
+/// Returns the Windows account xterminate is running as,
+/// formatted as `DOMAIN\user`, or `None` if it cannot be determined.
+#[must_use]
+pub fn current_user() -> Option<String> {
+    Process::try_open(std::process::id()).ok()?.info().user
+}
+
 #[must_use]
 /// # Panics
 ///
@@ -663,3 +2374,306 @@ pub fn make_rel_path_abs(filename: &str) -> String {
 
     absolute.display().to_string()
 }
+
+/// Returns the notification telling the user the outcome of terminating a process.
+fn termination_notification(name: &str, pid: u32, outcome: TerminationOutcome) -> Notification {
+    let (severity, key) = match outcome {
+        TerminationOutcome::Exited => (Severity::Information, "notification.terminated"),
+        TerminationOutcome::StillRunning => (Severity::Warning, "notification.still_running"),
+        TerminationOutcome::Zombie => (Severity::Warning, "notification.stuck_exiting"),
+    };
+
+    Notification {
+        severity,
+        message: tr!(key, name = name, pid = pid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Locale;
+    use crate::notification::{Notifier, Verbosity};
+    use crate::ui::dialogs::{Answer, ScriptedDialogs, Shown};
+    use std::mem::ManuallyDrop;
+
+    /// Records the notifications it is asked to show.
+    #[derive(Clone, Default)]
+    struct StubNotifier {
+        shown: Rc<RefCell<Vec<Notification>>>,
+    }
+
+    impl Notifier for StubNotifier {
+        fn notify(&self, notification: &Notification) {
+            self.shown.borrow_mut().push(notification.clone());
+        }
+    }
+
+    fn use_english() {
+        let _ = crate::locale::configure(&Locale {
+            language: Some(String::from("en")),
+        });
+    }
+
+    fn notifications(verbosity: Verbosity) -> (Notifications, StubNotifier) {
+        let stub = StubNotifier::default();
+        let mut notifications = Notifications::new(verbosity);
+        notifications.set_notifier(Box::new(stub.clone()));
+
+        (notifications, stub)
+    }
+
+    #[test]
+    fn notifies_termination_outcomes() {
+        use_english();
+
+        let (notifications, stub) = notifications(Verbosity::All);
+
+        for outcome in [
+            TerminationOutcome::Exited,
+            TerminationOutcome::StillRunning,
+            TerminationOutcome::Zombie,
+        ] {
+            notifications.notify(termination_notification("notepad.exe", 42, outcome));
+        }
+
+        assert_eq!(
+            *stub.shown.borrow(),
+            [
+                Notification {
+                    severity: Severity::Information,
+                    message: String::from("Terminated notepad.exe (pid 42)"),
+                },
+                Notification {
+                    severity: Severity::Warning,
+                    message: String::from("notepad.exe (pid 42) is still running"),
+                },
+                Notification {
+                    severity: Severity::Warning,
+                    message: String::from("notepad.exe (pid 42) is stuck exiting"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_termination_outcomes_by_verbosity() {
+        use_english();
+
+        let (notifications, stub) = notifications(Verbosity::Warnings);
+
+        notifications.notify(termination_notification(
+            "notepad.exe",
+            42,
+            TerminationOutcome::Exited,
+        ));
+        notifications.notify(termination_notification(
+            "notepad.exe",
+            42,
+            TerminationOutcome::Zombie,
+        ));
+
+        let shown = stub.shown.borrow();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].severity, Severity::Warning);
+    }
+
+    /// Creates an [`App`] with the default config, saved to `config_path`,
+    /// whose dialogs are answered by `dialogs`. It is never dropped, since
+    /// dropping it would reset the system cursors.
+    fn app(dialogs: &ScriptedDialogs, config_path: &std::path::Path) -> ManuallyDrop<App> {
+        use_english();
+
+        let mut config = config::defaults();
+        config.confirm.countdown_seconds = 0;
+
+        let cursor = std::path::PathBuf::from("cursor.cur");
+
+        ManuallyDrop::new(App::from_parts(
+            config,
+            config_path.to_owned(),
+            HashMap::new(),
+            CursorTheme {
+                terminate: cursor.clone(),
+                suspend: cursor.clone(),
+                info: cursor,
+                forbidden: None,
+            },
+            None,
+            Vec::new(),
+            Arc::new(dialogs.clone()),
+        ))
+    }
+
+    fn notepad() -> ProcessInfo {
+        ProcessInfo {
+            pid: 42,
+            name: String::from("notepad.exe"),
+            path: String::from("C:\\Windows\\notepad.exe"),
+            ..ProcessInfo::default()
+        }
+    }
+
+    /// Returns the rules of the config saved to `path`.
+    fn saved_rules(path: &std::path::Path) -> Vec<Rule> {
+        toml::from_str::<Config>(&std::fs::read_to_string(path).unwrap())
+            .unwrap()
+            .rules
+    }
+
+    #[test]
+    fn confirms_termination_of_process_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let dialogs = ScriptedDialogs::new();
+        dialogs.answer_choice(Answer {
+            value: Some(CONFIRM_ACCEPT_TREE_ID),
+            verified: false,
+        });
+
+        let mut app = app(&dialogs, &path);
+        app.config.borrow_mut().confirm.countdown_seconds = 5;
+
+        assert_eq!(
+            app.confirm("Untitled - Notepad", &notepad(), 2, false),
+            Confirmation::AcceptTree
+        );
+
+        let [Shown::Choice(dialog, choices, Some(verification))] = &dialogs.shown()[..] else {
+            panic!("unexpected dialogs {:?}", dialogs.shown());
+        };
+
+        assert!(dialog.content.contains("Untitled - Notepad"), "{dialog:?}");
+        assert_eq!(
+            choices.iter().map(|choice| choice.id).collect::<Vec<_>>(),
+            [CONFIRM_ACCEPT_ID, CONFIRM_ACCEPT_TREE_ID]
+        );
+        assert!(!verification.checked);
+
+        assert!(app.config.borrow().rules.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn restart_confirmation_cannot_include_process_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let dialogs = ScriptedDialogs::new();
+        dialogs.answer_choice(Answer {
+            value: None,
+            verified: false,
+        });
+
+        let mut app = app(&dialogs, &path);
+
+        assert_eq!(
+            app.confirm("Untitled - Notepad", &notepad(), 2, true),
+            Confirmation::Cancel
+        );
+
+        let [Shown::Choice(_, choices, _)] = &dialogs.shown()[..] else {
+            panic!("unexpected dialogs {:?}", dialogs.shown());
+        };
+
+        assert_eq!(
+            choices.iter().map(|choice| choice.id).collect::<Vec<_>>(),
+            [CONFIRM_ACCEPT_ID]
+        );
+    }
+
+    #[test]
+    fn dont_ask_again_skips_future_confirmations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let dialogs = ScriptedDialogs::new();
+        dialogs.answer_choice(Answer {
+            value: Some(CONFIRM_ACCEPT_ID),
+            verified: true,
+        });
+
+        let mut app = app(&dialogs, &path);
+
+        assert_eq!(
+            app.confirm("Untitled - Notepad", &notepad(), 0, false),
+            Confirmation::Accept
+        );
+
+        let expected = Rule {
+            process: Some(String::from("notepad.exe")),
+            confirm: Some(false),
+            ..Rule::default()
+        };
+
+        assert_eq!(app.config.borrow().rules, std::slice::from_ref(&expected));
+        assert_eq!(saved_rules(&path), [expected]);
+        assert!(!rules::requires_confirmation(
+            &app.config.borrow().rules,
+            &notepad(),
+            true
+        ));
+    }
+
+    #[test]
+    fn dont_ask_again_is_ignored_when_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let dialogs = ScriptedDialogs::new();
+        dialogs.answer_choice(Answer {
+            value: None,
+            verified: true,
+        });
+
+        let mut app = app(&dialogs, &path);
+
+        assert_eq!(
+            app.confirm("Untitled - Notepad", &notepad(), 0, false),
+            Confirmation::Cancel
+        );
+
+        assert!(app.config.borrow().rules.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn protects_process_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let dialogs = ScriptedDialogs::new();
+        let mut app = app(&dialogs, &path);
+
+        // Protection must override rules that explicitly allow the process
+        app.config.borrow_mut().rules.push(Rule {
+            process: Some(String::from("notepad.exe")),
+            protected: Some(false),
+            ..Rule::default()
+        });
+
+        assert!(!app.refuse_protected(&notepad()));
+
+        app.protect("notepad.exe");
+        app.protect("NOTEPAD.EXE");
+
+        let expected = Rule {
+            process: Some(String::from("notepad.exe")),
+            protected: Some(true),
+            ..Rule::default()
+        };
+
+        let rules = app.config.borrow().rules.clone();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0], expected);
+        assert_eq!(saved_rules(&path), rules);
+
+        assert!(app.refuse_protected(&notepad()));
+        assert_eq!(
+            app.last_action.as_deref(),
+            Some(tr!("action.refused", name = "notepad.exe").as_str())
+        );
+        assert!(dialogs.shown().is_empty());
+    }
+}
@@ -0,0 +1,1030 @@
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use chrono::{DateTime, Local, NaiveDate};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{LocalFree, BOOL, HLOCAL, PSID};
+use windows::Win32::Security::Authorization::{
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, SetNamedSecurityInfoW, SDDL_REVISION_1,
+    SE_FILE_OBJECT,
+};
+use windows::Win32::Security::{
+    GetSecurityDescriptorDacl, ACL, DACL_SECURITY_INFORMATION, PROTECTED_DACL_SECURITY_INFORMATION,
+    PSECURITY_DESCRIPTOR,
+};
+
+use crate::process::{ProcessInfo, TerminationOutcome};
+use crate::wide;
+
+/// The file extension of audit log segments. Each segment holds the
+/// records of a single day and is named after it, e.g. `2024-05-01.jsonl`.
+const SEGMENT_EXTENSION: &str = "jsonl";
+
+/// The previous hash of the head marker of the very first segment.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The file holding the signed [`ChainHead`], next to the segments.
+const HEAD_FILENAME: &str = "head.json";
+
+/// The length in bytes of keys created by [`load_or_create_key`].
+const KEY_LENGTH: usize = 32;
+
+/// The access control list of the key file: full access for the local system
+/// and administrators only, without inheriting entries from the directory.
+const KEY_SDDL: &str = "D:P(A;;FA;;;SY)(A;;FA;;;BA)";
+
+/// The kind of action xterminate took on a process.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Terminate,
+    Restart,
+}
+
+/// What caused xterminate to act on a process.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    /// A keybind pressed by the user
+    Hotkey,
+    /// A tray menu item selected by the user
+    Tray,
+    /// A command passed to xterminate on the command line
+    Cli,
+    /// A `[[rules]]` entry, e.g. `on_hang = "kill"`
+    Rule,
+    /// The user confirming a watchdog prompt about an unresponsive window
+    Watchdog,
+}
+
+/// A single line of the audit log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Local>,
+    pub action: AuditAction,
+    pub trigger: Trigger,
+    pub target: ProcessInfo,
+    pub outcome: TerminationOutcome,
+
+    /// The Windows account xterminate was running as, formatted as `DOMAIN\user`.
+    pub user: Option<String>,
+
+    /// SHA-256 hash of the line preceding this record in its segment,
+    /// which is set by [`AuditLog::append`].
+    #[serde(default)]
+    pub previous_hash: String,
+}
+
+/// The first line of every segment, linking it to the last line of the
+/// previous segment. Head markers are signed, so the chain can still be
+/// verified from the oldest remaining segment once older ones have expired.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SegmentHead {
+    pub created: DateTime<Local>,
+
+    /// File name of the previous segment, or `None` for the first segment
+    pub previous_segment: Option<String>,
+
+    /// SHA-256 hash of the last line of the previous segment
+    pub previous_hash: String,
+
+    /// HMAC-SHA256 of the fields above, keyed with the audit key
+    pub signature: String,
+}
+
+impl SegmentHead {
+    fn new(
+        created: DateTime<Local>,
+        previous_segment: Option<String>,
+        previous_hash: String,
+        key: &[u8],
+    ) -> Self {
+        let mut head = Self {
+            created,
+            previous_segment,
+            previous_hash,
+            signature: String::new(),
+        };
+
+        head.signature = to_hex(&head.mac(key).finalize().into_bytes());
+        head
+    }
+
+    /// Returns true if the head marker was signed with `key` and has not been modified since.
+    #[must_use]
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        from_hex(&self.signature)
+            .is_some_and(|signature| self.mac(key).verify_slice(&signature).is_ok())
+    }
+
+    fn mac(&self, key: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+
+        // The timestamp is signed as an instant rather than in its serialized form,
+        // which depends on the local UTC offset at the time it is read back.
+        mac.update(
+            format!(
+                "{}.{:09}\n{}\n{}",
+                self.created.timestamp(),
+                self.created.timestamp_subsec_nanos(),
+                self.previous_segment.as_deref().unwrap_or_default(),
+                self.previous_hash
+            )
+            .as_bytes(),
+        );
+
+        mac
+    }
+}
+
+/// The end of the whole chain, stored in its own file and rewritten with every
+/// record. Since the last record of a segment is not followed by anything that
+/// links to it, the signed chain head is what detects records being removed from
+/// the end of the newest segment, or the newest segment being deleted entirely.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChainHead {
+    /// File name of the newest segment
+    pub segment: String,
+
+    /// SHA-256 hash of the last line of the newest segment
+    pub hash: String,
+
+    /// HMAC-SHA256 of the fields above, keyed with the audit key
+    pub signature: String,
+}
+
+impl ChainHead {
+    fn new(segment: String, hash: String, key: &[u8]) -> Self {
+        let mut head = Self {
+            segment,
+            hash,
+            signature: String::new(),
+        };
+
+        head.signature = to_hex(&head.mac(key).finalize().into_bytes());
+        head
+    }
+
+    /// Returns true if the chain head was signed with `key` and has not been modified since.
+    #[must_use]
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        from_hex(&self.signature)
+            .is_some_and(|signature| self.mac(key).verify_slice(&signature).is_ok())
+    }
+
+    fn mac(&self, key: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(format!("{}\n{}", self.segment, self.hash).as_bytes());
+        mac
+    }
+}
+
+/// The last line of the chain, which the next record links to.
+#[derive(Debug, Clone)]
+struct Tip {
+    date: NaiveDate,
+    segment: PathBuf,
+    hash: String,
+}
+
+/// The first line in the audit log that breaks the hash chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub segment: PathBuf,
+
+    /// The 1-based line number within the segment
+    pub line: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, line {}: {}",
+            self.segment.display(),
+            self.line,
+            self.reason
+        )
+    }
+}
+
+/// The result of [`AuditLog::verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verification {
+    pub segments: usize,
+    pub records: usize,
+
+    /// The first broken link, or `None` if the whole chain is intact
+    pub broken: Option<BrokenLink>,
+}
+
+/// Filters applied when reading records back with [`AuditLog::query`].
+/// Filters that are left as `None` match any record.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Only include records written at or after this time
+    pub since: Option<DateTime<Local>>,
+    /// Only include records written before this time
+    pub until: Option<DateTime<Local>>,
+    /// Only include records of processes with this executable name (case-insensitive)
+    pub process: Option<String>,
+    pub action: Option<AuditAction>,
+    /// Only return the most recent `limit` records
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    #[must_use]
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|process| process.eq_ignore_ascii_case(&record.target.name))
+            && self.action.is_none_or(|action| record.action == action)
+    }
+}
+
+/// An append-only log of every action xterminate takes on a process,
+/// stored as JSON Lines in daily segment files.
+///
+/// The audit log is kept separately from the regular log files so that
+/// it can be retained for longer and read back by tools.
+///
+/// To make tampering evident, every record carries the hash of the line
+/// before it and every segment starts with a signed [`SegmentHead`] that
+/// carries the hash of the last line of the previous segment. The last line
+/// of the newest segment is covered by a signed [`ChainHead`]. Editing,
+/// inserting or removing a line breaks the chain, which [`Self::verify`] detects.
+pub struct AuditLog {
+    directory: PathBuf,
+
+    /// The key used to sign and verify segment head markers
+    key: Vec<u8>,
+
+    /// The amount of days segments are kept for, or 0 to keep them forever
+    retention_days: u64,
+
+    /// The Windows account xterminate is running as
+    user: Option<String>,
+
+    /// The end of the chain, read from the newest segment on the first append
+    /// and kept up to date afterwards, so appending does not re-read the segment
+    tip: Mutex<Option<Tip>>,
+}
+
+impl AuditLog {
+    #[must_use]
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        key: Vec<u8>,
+        retention_days: u64,
+        user: Option<String>,
+    ) -> Self {
+        Self {
+            directory: directory.into(),
+            key,
+            retention_days,
+            user,
+            tip: Mutex::new(None),
+        }
+    }
+
+    /// Appends a record of the specified action to today's segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audit log directory cannot be created or
+    /// the segment file cannot be opened or written to.
+    pub fn record(
+        &self,
+        action: AuditAction,
+        trigger: Trigger,
+        target: ProcessInfo,
+        outcome: TerminationOutcome,
+    ) -> std::io::Result<()> {
+        self.append(AuditRecord {
+            timestamp: Local::now(),
+            action,
+            trigger,
+            target,
+            outcome,
+            user: self.user.clone(),
+            previous_hash: String::new(),
+        })
+    }
+
+    /// Links a record to the end of the chain and appends it to the segment of
+    /// the day it was written on. If that segment does not exist yet, it is
+    /// started with a head marker linking it to the previous segment. Records
+    /// dated before the newest segment, e.g. after the clock was turned back,
+    /// are appended to the newest segment to keep the chain in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audit log directory cannot be created or
+    /// a segment or the chain head cannot be read, opened or written to.
+    pub fn append(&self, mut record: AuditRecord) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        let mut tip = self.tip.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if tip.is_none() {
+            *tip = self.find_tip()?;
+        }
+
+        let date = match tip.as_ref() {
+            Some(tip) if tip.date > record.timestamp.date_naive() => tip.date,
+            _ => record.timestamp.date_naive(),
+        };
+        let path = self.segment_path(date);
+
+        let mut lines = Vec::new();
+
+        record.previous_hash = match tip.as_ref() {
+            Some(tip) if tip.segment == path => tip.hash.clone(),
+            previous => {
+                let (previous_segment, previous_hash) = previous.map_or_else(
+                    || (None, GENESIS_HASH.to_owned()),
+                    |tip| (file_name(&tip.segment), tip.hash.clone()),
+                );
+
+                let head =
+                    SegmentHead::new(record.timestamp, previous_segment, previous_hash, &self.key);
+                let head_line = serde_json::to_string(&head)?;
+                let head_hash = hash(&head_line);
+
+                lines.push(head_line);
+                head_hash
+            }
+        };
+
+        let record_line = serde_json::to_string(&record)?;
+        let record_hash = hash(&record_line);
+        lines.push(record_line);
+
+        let mut segment = std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&path)?;
+
+        for line in lines {
+            writeln!(segment, "{line}")?;
+        }
+
+        let head = ChainHead::new(
+            file_name(&path).unwrap_or_default(),
+            record_hash.clone(),
+            &self.key,
+        );
+        self.write_head(&head)?;
+
+        *tip = Some(Tip {
+            date,
+            segment: path,
+            hash: record_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the last line of the newest segment that has any lines, or
+    /// `None` if the audit log does not contain any segments yet.
+    fn find_tip(&self) -> std::io::Result<Option<Tip>> {
+        for (date, path) in self.segments()?.into_iter().rev() {
+            if let Some(line) = last_line(&path)? {
+                return Ok(Some(Tip {
+                    date,
+                    segment: path,
+                    hash: hash(&line),
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Replaces the chain head file, writing to a temporary file first
+    /// so the chain head is never left partially written.
+    fn write_head(&self, head: &ChainHead) -> std::io::Result<()> {
+        let path = self.head_path();
+        let temporary = path.with_extension("tmp");
+
+        std::fs::write(&temporary, serde_json::to_string(head)?)?;
+        std::fs::rename(temporary, path)
+    }
+
+    fn head_path(&self) -> PathBuf {
+        self.directory.join(HEAD_FILENAME)
+    }
+
+    /// Walks the hash chain from the oldest remaining segment to the most
+    /// recent record and reports the first line that breaks it, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audit log directory or one of its segments cannot be read.
+    pub fn verify(&self) -> std::io::Result<Verification> {
+        let mut verification = Verification::default();
+
+        if !self.directory.exists() {
+            return Ok(verification);
+        }
+
+        // The file name and last line hash of the previous segment
+        let mut previous: Option<(String, String)> = None;
+        let mut newest: Option<(PathBuf, usize)> = None;
+
+        for (_, path) in self.segments()? {
+            let content = std::fs::read_to_string(&path)?;
+            let name = file_name(&path).unwrap_or_default();
+
+            let broken = |line: usize, reason: String| BrokenLink {
+                segment: path.clone(),
+                line,
+                reason,
+            };
+
+            verification.segments += 1;
+
+            let mut previous_hash: Option<String> = None;
+
+            for (index, line) in content.lines().enumerate() {
+                let error = if index == 0 {
+                    Self::verify_head(line, previous.as_ref(), &self.key)
+                } else {
+                    match serde_json::from_str::<AuditRecord>(line) {
+                        Ok(record) if Some(&record.previous_hash) == previous_hash.as_ref() => {
+                            verification.records += 1;
+                            None
+                        }
+
+                        Ok(_) => Some(String::from("record does not link to the previous line")),
+                        Err(_) => Some(String::from("line is not a valid audit record")),
+                    }
+                };
+
+                if let Some(reason) = error {
+                    verification.broken = Some(broken(index + 1, reason));
+                    return Ok(verification);
+                }
+
+                previous_hash = Some(hash(line));
+            }
+
+            let Some(previous_hash) = previous_hash else {
+                verification.broken = Some(broken(1, String::from("segment is empty")));
+                return Ok(verification);
+            };
+
+            previous = Some((name, previous_hash));
+            newest = Some((path.clone(), content.lines().count()));
+        }
+
+        verification.broken = self.verify_chain_head(previous.as_ref(), newest)?;
+
+        Ok(verification)
+    }
+
+    /// Checks that the chain head covers the last line of the newest segment and returns
+    /// the link that breaks the chain otherwise. `last` is the file name and last line hash
+    /// of the newest segment, `newest` its path and amount of lines.
+    fn verify_chain_head(
+        &self,
+        last: Option<&(String, String)>,
+        newest: Option<(PathBuf, usize)>,
+    ) -> std::io::Result<Option<BrokenLink>> {
+        let head_path = self.head_path();
+
+        let head = match std::fs::read_to_string(&head_path) {
+            Ok(content) => Some(serde_json::from_str::<ChainHead>(&content).ok()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+
+        let broken = |segment: PathBuf, line: usize, reason: String| {
+            Some(BrokenLink {
+                segment,
+                line,
+                reason,
+            })
+        };
+
+        let (Some((name, last_hash)), Some((newest_path, lines))) = (last, newest) else {
+            return Ok(match head {
+                None => None,
+                Some(Some(head)) => broken(
+                    head_path,
+                    1,
+                    format!("newest segment {} is missing", head.segment),
+                ),
+                Some(None) => broken(head_path, 1, String::from("chain head is not valid")),
+            });
+        };
+
+        let reason = match head {
+            None => String::from("chain head is missing"),
+            Some(None) => String::from("chain head is not valid"),
+            Some(Some(head)) if !head.verify_signature(&self.key) => {
+                String::from("chain head signature is invalid")
+            }
+            Some(Some(head)) if head.segment != *name => {
+                format!("newest segment {} is missing", head.segment)
+            }
+            Some(Some(head)) if head.hash != *last_hash => {
+                return Ok(broken(
+                    newest_path,
+                    lines,
+                    String::from("last line is not covered by the chain head, records at the end of the segment were removed or added"),
+                ));
+            }
+            Some(Some(_)) => return Ok(None),
+        };
+
+        Ok(broken(head_path, 1, reason))
+    }
+
+    /// Checks the head marker on the first line of a segment and returns
+    /// the reason it breaks the chain, or `None` if it is valid.
+    fn verify_head(line: &str, previous: Option<&(String, String)>, key: &[u8]) -> Option<String> {
+        let Ok(head) = serde_json::from_str::<SegmentHead>(line) else {
+            return Some(String::from("segment does not start with a head marker"));
+        };
+
+        if !head.verify_signature(key) {
+            return Some(String::from("head marker signature is invalid"));
+        }
+
+        // The oldest remaining segment is trusted to link to expired segments
+        // based on its signature alone, since those are no longer available.
+        let (previous_name, previous_hash) = previous?;
+
+        if head.previous_segment.as_ref() != Some(previous_name) {
+            return Some(format!(
+                "head marker does not follow the previous segment {previous_name}"
+            ));
+        }
+
+        if head.previous_hash != *previous_hash {
+            return Some(format!(
+                "head marker does not link to the last line of {previous_name}"
+            ));
+        }
+
+        None
+    }
+
+    /// Deletes segments older than the configured retention period.
+    /// Files that are not audit log segments are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audit log directory cannot be read or
+    /// an expired segment cannot be deleted.
+    pub fn prune(&self) -> std::io::Result<()> {
+        if self.retention_days == 0 || !self.directory.exists() {
+            return Ok(());
+        }
+
+        let today = Local::now().date_naive();
+        let mut tip = self.tip.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut remaining = 0;
+
+        for (date, path) in self.segments()? {
+            let expired = date
+                .checked_add_days(chrono::Days::new(self.retention_days))
+                .is_some_and(|expiry| expiry < today);
+
+            if expired {
+                std::fs::remove_file(path)?;
+            } else {
+                remaining += 1;
+            }
+        }
+
+        // A chain head without any segments would report the newest one as missing
+        if remaining == 0 {
+            match std::fs::remove_file(self.head_path()) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error),
+                _ => {}
+            }
+        }
+
+        // The tip may have been in one of the deleted segments
+        *tip = None;
+
+        Ok(())
+    }
+
+    /// Reads all records matching `query` back from the audit log, oldest first.
+    /// Head markers and lines that cannot be parsed as records are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the audit log directory or one of its segments cannot be read.
+    pub fn query(&self, query: &AuditQuery) -> std::io::Result<Vec<AuditRecord>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut records = Vec::new();
+
+        for (date, path) in self.segments()? {
+            // Skip segments that cannot contain any matching records
+            let before_since = query.since.is_some_and(|since| date < since.date_naive());
+            let after_until = query.until.is_some_and(|until| date > until.date_naive());
+
+            if before_since || after_until {
+                continue;
+            }
+
+            let file = std::io::BufReader::new(std::fs::File::open(path)?);
+
+            for line in file.lines() {
+                let Ok(record) = serde_json::from_str::<AuditRecord>(&line?) else {
+                    continue;
+                };
+
+                if query.matches(&record) {
+                    records.push(record);
+                }
+            }
+        }
+
+        if let Some(limit) = query.limit {
+            records.drain(..records.len().saturating_sub(limit));
+        }
+
+        Ok(records)
+    }
+
+    /// Returns the date and path of every segment in the audit log directory, oldest first.
+    fn segments(&self) -> std::io::Result<Vec<(NaiveDate, PathBuf)>> {
+        let mut segments = Vec::new();
+
+        for entry in std::fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+
+            if let Some(date) = segment_date(&path) {
+                segments.push((date, path));
+            }
+        }
+
+        segments.sort();
+
+        Ok(segments)
+    }
+
+    fn segment_path(&self, date: NaiveDate) -> PathBuf {
+        self.directory
+            .join(format!("{}.{SEGMENT_EXTENSION}", date.format("%Y-%m-%d")))
+    }
+}
+
+/// Reads the key used to sign segment head markers from `path`, creating
+/// a new random key if the file does not exist yet. The key file is only
+/// accessible to the local system and administrators.
+///
+/// # Errors
+///
+/// Returns an error if the key file cannot be read or created,
+/// or its access cannot be restricted.
+pub fn load_or_create_key(path: &Path) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) => {
+            // Restrict keys created by earlier versions, which used the default access
+            restrict_access(path)?;
+            Ok(key)
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            let mut key = vec![0u8; KEY_LENGTH];
+            getrandom::getrandom(&mut key)
+                .map_err(|error| std::io::Error::other(error.to_string()))?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            // Restrict the file before the key is written to it
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)?;
+
+            restrict_access(path)?;
+            file.write_all(&key)?;
+
+            Ok(key)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Replaces the access control list of a file with [`KEY_SDDL`].
+fn restrict_access(path: &Path) -> std::io::Result<()> {
+    let sddl = wide::to_wide_nul(KEY_SDDL);
+    let path = wide::to_wide_nul(path);
+
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+
+    unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            PCWSTR(sddl.as_ptr()),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            None,
+        )?;
+    }
+
+    let mut present = BOOL::default();
+    let mut defaulted = BOOL::default();
+    let mut dacl: *mut ACL = std::ptr::null_mut();
+
+    let result = unsafe {
+        GetSecurityDescriptorDacl(descriptor, &mut present, &mut dacl, &mut defaulted).and_then(
+            |()| {
+                SetNamedSecurityInfoW(
+                    PCWSTR(path.as_ptr()),
+                    SE_FILE_OBJECT,
+                    DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                    PSID::default(),
+                    PSID::default(),
+                    Some(dacl),
+                    None,
+                )
+            },
+        )
+    };
+
+    unsafe {
+        let _ = LocalFree(HLOCAL(descriptor.0));
+    }
+
+    Ok(result?)
+}
+
+/// Returns the last non-empty line of a file, or `None` if
+/// the file does not exist or contains no lines.
+fn last_line(path: &Path) -> std::io::Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content.lines().last().map(str::to_owned)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_str()?.to_owned())
+}
+
+/// Returns the hex-encoded SHA-256 hash of a line, excluding its line break.
+fn hash(line: &str) -> String {
+    to_hex(&Sha256::digest(line.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let pairs = hex.as_bytes().chunks_exact(2);
+
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+
+    pairs
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Returns the date of an audit log segment, or `None` if `path` is not a segment.
+fn segment_date(path: &Path) -> Option<NaiveDate> {
+    if path.extension()? != SEGMENT_EXTENSION {
+        return None;
+    }
+
+    NaiveDate::parse_from_str(path.file_stem()?.to_str()?, "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"audit log test key";
+
+    fn record(timestamp: &str, name: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: DateTime::parse_from_rfc3339(timestamp)
+                .unwrap()
+                .with_timezone(&Local),
+            action: AuditAction::Terminate,
+            trigger: Trigger::Hotkey,
+            target: ProcessInfo {
+                name: name.to_owned(),
+                ..ProcessInfo::default()
+            },
+            outcome: TerminationOutcome::Exited,
+            user: None,
+            previous_hash: String::new(),
+        }
+    }
+
+    /// Writes two records on two consecutive days each.
+    fn two_day_log(directory: &Path) -> AuditLog {
+        let log = AuditLog::new(directory, KEY.to_vec(), 0, None);
+
+        log.append(record("2024-05-01T10:00:00Z", "first.exe"))
+            .unwrap();
+        log.append(record("2024-05-01T11:00:00Z", "second.exe"))
+            .unwrap();
+        log.append(record("2024-05-02T10:00:00Z", "third.exe"))
+            .unwrap();
+        log.append(record("2024-05-02T11:00:00Z", "fourth.exe"))
+            .unwrap();
+
+        log
+    }
+
+    fn segment(directory: &Path, date: &str) -> PathBuf {
+        directory.join(format!("{date}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn broken(log: &AuditLog) -> BrokenLink {
+        log.verify()
+            .unwrap()
+            .broken
+            .expect("chain should be broken")
+    }
+
+    #[test]
+    fn verifies_intact_chain() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+
+        assert_eq!(
+            log.verify().unwrap(),
+            Verification {
+                segments: 2,
+                records: 4,
+                broken: None,
+            }
+        );
+    }
+
+    #[test]
+    fn continues_chain_after_reopening() {
+        let directory = tempfile::tempdir().unwrap();
+        two_day_log(directory.path());
+
+        let log = AuditLog::new(directory.path(), KEY.to_vec(), 0, None);
+        log.append(record("2024-05-02T12:00:00Z", "fifth.exe"))
+            .unwrap();
+        log.append(record("2024-05-03T10:00:00Z", "sixth.exe"))
+            .unwrap();
+
+        let verification = log.verify().unwrap();
+        assert_eq!(verification.broken, None);
+        assert_eq!(verification.records, 6);
+    }
+
+    #[test]
+    fn appends_records_from_the_past_to_newest_segment() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+
+        log.append(record("2024-04-30T10:00:00Z", "past.exe"))
+            .unwrap();
+
+        assert!(!segment(directory.path(), "2024-04-30").exists());
+        assert_eq!(log.verify().unwrap().broken, None);
+    }
+
+    #[test]
+    fn detects_edited_record() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+        let path = segment(directory.path(), "2024-05-01");
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("first.exe", "other.exe")).unwrap();
+
+        let link = broken(&log);
+        assert_eq!(link.segment, path);
+        assert_eq!(link.line, 3);
+    }
+
+    #[test]
+    fn detects_edited_last_record() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+        let path = segment(directory.path(), "2024-05-02");
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("fourth.exe", "other.exe")).unwrap();
+
+        let link = broken(&log);
+        assert_eq!(link.segment, path);
+        assert_eq!(link.line, 3);
+    }
+
+    #[test]
+    fn detects_truncated_segment() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+        let path = segment(directory.path(), "2024-05-02");
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let truncated: Vec<&str> = content.lines().take(2).collect();
+        std::fs::write(&path, truncated.join("\n") + "\n").unwrap();
+
+        let link = broken(&log);
+        assert_eq!(link.segment, path);
+        assert_eq!(link.line, 2);
+    }
+
+    #[test]
+    fn detects_missing_newest_segment() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+
+        std::fs::remove_file(segment(directory.path(), "2024-05-02")).unwrap();
+
+        let link = broken(&log);
+        assert_eq!(link.segment, directory.path().join(HEAD_FILENAME));
+        assert!(link.reason.contains("2024-05-02.jsonl is missing"));
+    }
+
+    #[test]
+    fn detects_missing_middle_segment() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+        log.append(record("2024-05-03T10:00:00Z", "fifth.exe"))
+            .unwrap();
+
+        std::fs::remove_file(segment(directory.path(), "2024-05-02")).unwrap();
+
+        let link = broken(&log);
+        assert_eq!(link.segment, segment(directory.path(), "2024-05-03"));
+        assert_eq!(link.line, 1);
+    }
+
+    #[test]
+    fn detects_missing_and_forged_chain_head() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+        let head_path = directory.path().join(HEAD_FILENAME);
+
+        let content = std::fs::read_to_string(&head_path).unwrap();
+        let head: ChainHead = serde_json::from_str(&content).unwrap();
+        let forged = ChainHead::new(head.segment, head.hash, b"another key");
+
+        std::fs::write(&head_path, serde_json::to_string(&forged).unwrap()).unwrap();
+        assert!(broken(&log).reason.contains("signature"));
+
+        std::fs::remove_file(&head_path).unwrap();
+        assert!(broken(&log).reason.contains("missing"));
+    }
+
+    #[test]
+    fn prunes_chain_head_with_last_segment() {
+        let directory = tempfile::tempdir().unwrap();
+        two_day_log(directory.path());
+
+        let log = AuditLog::new(directory.path(), KEY.to_vec(), 1, None);
+        log.prune().unwrap();
+
+        assert!(!directory.path().join(HEAD_FILENAME).exists());
+        assert_eq!(log.verify().unwrap(), Verification::default());
+    }
+
+    #[test]
+    fn queries_matching_records() {
+        let directory = tempfile::tempdir().unwrap();
+        let log = two_day_log(directory.path());
+
+        let records = log
+            .query(&AuditQuery {
+                process: Some(String::from("THIRD.EXE")),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].target.name, "third.exe");
+
+        let records = log
+            .query(&AuditQuery {
+                limit: Some(3),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].target.name, "second.exe");
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDate, TimeZone};
+
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+use crate::app::{self, App};
+use crate::audit::{AuditAction, AuditLog, AuditQuery, AuditRecord, Trigger};
+use crate::process::{self, Process, TerminationOutcome};
+use crate::{config, cursor, diagnostics, rules};
+
+const USAGE: &str = "\
+Usage: xterminate [COMMAND]
+
+Starts xterminate in the system tray if no command is given, or if the
+first argument is not one of the commands below.
+
+Commands:
+  terminate <PID>      Terminate the process with this id, unless a rule protects it
+  history [OPTIONS]    Print the termination audit log
+  verify               Check the audit log for records that have been tampered with
+  diagnostics [PATH]   Write a zip archive of logs, config and crash reports for bug reports
+
+Options for 'history':
+  --process <NAME>     Only show records of processes with this executable name
+  --action <ACTION>    Only show records of this action (terminate, restart)
+  --since <DATE>       Only show records written on or after this date (YYYY-MM-DD)
+  --until <DATE>       Only show records written before this date (YYYY-MM-DD)
+  --limit <COUNT>      Only show the most recent COUNT records
+  --json               Print records as JSON Lines instead of a table";
+
+/// Runs the command specified by the command line arguments, excluding the
+/// executable path, and returns the exit code xterminate should exit with.
+/// Returns `None` if no command was given and xterminate should start normally.
+///
+/// Arguments that are not a command, e.g. added by a shortcut or launcher, are
+/// ignored so that they do not keep xterminate from starting. Only invalid
+/// arguments of a command are rejected.
+#[must_use]
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, args) = args.split_first()?;
+
+    if !is_command(command) {
+        return None;
+    }
+
+    // xterminate is a GUI application and has no console of its own,
+    // so output is written to the console it was started from, if any.
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+
+    let result = match command.as_str() {
+        "terminate" => return Some(terminate(args)),
+        "history" => history(args),
+        "verify" => return Some(verify()),
+        "diagnostics" => return Some(diagnostics(args)),
+        // Started by xterminate itself, see `cursor::guard::spawn`
+        cursor::guard::COMMAND => return Some(cursor::guard::run(args)),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => unreachable!("'{command}' is not a command"),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            Some(2)
+        }
+    }
+}
+
+/// Returns true if `arg` is one of the commands handled by [`run`].
+fn is_command(arg: &str) -> bool {
+    matches!(
+        arg,
+        "terminate" | "history" | "verify" | "diagnostics" | "help" | "--help" | "-h"
+    ) || arg == cursor::guard::COMMAND
+}
+
+/// Terminates the process with the pid given as the only argument and records
+/// it in the audit log. Returns 0 if the process has exited, 1 if it could not
+/// be terminated or is protected by a rule and 2 if the arguments are invalid.
+fn terminate(args: &[String]) -> i32 {
+    let pid = match args {
+        [pid] => match pid.parse::<u32>() {
+            Ok(pid) => pid,
+            Err(_) => {
+                eprintln!("error: invalid process id '{pid}'\n\n{USAGE}");
+                return 2;
+            }
+        },
+        [] => {
+            eprintln!("error: missing process id\n\n{USAGE}");
+            return 2;
+        }
+        [_, arg, ..] => {
+            eprintln!("error: unexpected argument '{arg}'\n\n{USAGE}");
+            return 2;
+        }
+    };
+
+    // Protection rules must not be skipped, so a broken config is an error
+    let config = match config::reload() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("error: failed to read config: {error}");
+            return 1;
+        }
+    };
+
+    let mut target_process = match Process::try_open(pid) {
+        Ok(process) => process,
+        Err(error) => {
+            eprintln!("error: failed to open process {pid}: {error}");
+            return 1;
+        }
+    };
+
+    let info = target_process.info();
+
+    if rules::is_protected(&config.rules, &info) {
+        eprintln!("error: {} is protected by a rule", info.name);
+        return 1;
+    }
+
+    if let Err(error) = target_process.terminate() {
+        eprintln!("error: failed to terminate {}: {error}", info.name);
+        return 1;
+    }
+
+    let outcome = target_process.outcome(process::TERMINATION_TIMEOUT);
+
+    println!("{} (pid {pid}): {outcome}", info.name);
+
+    if let Some(audit) = App::open_audit_log(&config) {
+        if let Err(error) = audit.record(AuditAction::Terminate, Trigger::Cli, info, outcome) {
+            eprintln!("warning: failed to write audit record: {error}");
+        }
+    }
+
+    i32::from(outcome != TerminationOutcome::Exited)
+}
+
+/// Prints the records of the audit log that match the specified options.
+fn history(args: &[String]) -> Result<(), String> {
+    let mut query = AuditQuery::default();
+    let mut json = false;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for '{arg}'"))
+        };
+
+        match arg.as_str() {
+            "--process" => query.process = Some(value()?.clone()),
+            "--action" => {
+                query.action = Some(match value()?.as_str() {
+                    "terminate" => AuditAction::Terminate,
+                    "restart" => AuditAction::Restart,
+                    action => return Err(format!("unknown action '{action}'")),
+                });
+            }
+            "--since" => query.since = Some(parse_date(value()?)?),
+            "--until" => query.until = Some(parse_date(value()?)?),
+            "--limit" => {
+                let limit = value()?;
+                query.limit = Some(
+                    limit
+                        .parse()
+                        .map_err(|_| format!("invalid limit '{limit}'"))?,
+                );
+            }
+            "--json" => json = true,
+            _ => return Err(format!("unknown option '{arg}'")),
+        }
+    }
+
+    // The key and retention only matter when writing, so they are irrelevant here
+    let records = AuditLog::new(app::audit_path(), Vec::new(), 0, None)
+        .query(&query)
+        .map_err(|error| format!("failed to read audit log: {error}"))?;
+
+    for record in &records {
+        if json {
+            println!("{}", serde_json::to_string(record).unwrap());
+        } else {
+            println!("{}", format_record(record));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a diagnostics bundle to the specified path, or to the current directory
+/// under its default name if none is given. Returns 0 if the bundle was written,
+/// 1 if it could not be written and 2 if the arguments are invalid.
+fn diagnostics(args: &[String]) -> i32 {
+    let path = match args {
+        [] => PathBuf::from(diagnostics::default_file_name()),
+        [path] => PathBuf::from(path),
+        [_, arg, ..] => {
+            eprintln!("error: unexpected argument '{arg}'\n\n{USAGE}");
+            return 2;
+        }
+    };
+
+    // The bundle is most useful when the config is broken,
+    // so it is still written without the keybinds then
+    let keybinds = match config::reload()
+        .map_err(|error| error.to_string())
+        .and_then(|config| App::try_setup_keybinds(&config))
+    {
+        Ok(keybinds) => keybinds,
+        Err(error) => {
+            eprintln!("warning: {error}, keybinds are left out of the diagnostics");
+            HashMap::new()
+        }
+    };
+
+    if let Err(error) = diagnostics::create_bundle(&path, &keybinds) {
+        eprintln!("error: failed to create diagnostics bundle: {error}");
+        return 1;
+    }
+
+    println!("Diagnostics written to {}", path.display());
+
+    0
+}
+
+/// Verifies the audit log's hash chain. Returns 0 if it is intact,
+/// 1 if it is broken and 2 if it could not be verified.
+fn verify() -> i32 {
+    let key = match std::fs::read(app::audit_key_path()) {
+        Ok(key) => key,
+        Err(error) => {
+            eprintln!("error: failed to read audit key: {error}");
+            return 2;
+        }
+    };
+
+    let verification = match AuditLog::new(app::audit_path(), key, 0, None).verify() {
+        Ok(verification) => verification,
+        Err(error) => {
+            eprintln!("error: failed to read audit log: {error}");
+            return 2;
+        }
+    };
+
+    if let Some(broken) = verification.broken {
+        println!("Audit log is broken at {broken}");
+        return 1;
+    }
+
+    println!(
+        "Audit log is intact ({} records in {} segments)",
+        verification.records, verification.segments
+    );
+
+    0
+}
+
+fn format_record(record: &AuditRecord) -> String {
+    format!(
+        "{}  {:<9}  {:<8}  {:<13}  {} (pid {})",
+        record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        format!("{:?}", record.action).to_lowercase(),
+        format!("{:?}", record.trigger).to_lowercase(),
+        record.outcome,
+        record.target.name,
+        record.target.pid
+    )
+}
+
+/// Parses a `YYYY-MM-DD` date as local midnight.
+fn parse_date(value: &str) -> Result<chrono::DateTime<Local>, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .ok_or_else(|| format!("invalid date '{value}', expected YYYY-MM-DD"))
+}
@@ -0,0 +1,50 @@
+use windows::Win32::Foundation::{GlobalFree, HANDLE, HWND};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+
+use crate::wide;
+
+/// The clipboard format of nul-terminated UTF-16 text.
+const CF_UNICODETEXT: u32 = 13;
+
+/// Replaces the contents of the clipboard with `text`.
+///
+/// # Errors
+///
+/// Returns an error if the clipboard is in use by another
+/// application or the text cannot be copied to it.
+pub fn set_text(text: &str) -> windows::core::Result<()> {
+    let text = wide::to_wide_nul(text);
+
+    unsafe {
+        OpenClipboard(HWND(0))?;
+
+        let result = EmptyClipboard().and_then(|()| {
+            let memory = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(text.as_slice()))?;
+
+            let buffer = GlobalLock(memory).cast::<u16>();
+            if buffer.is_null() {
+                let error = windows::core::Error::from_win32();
+                let _ = GlobalFree(memory);
+                return Err(error);
+            }
+
+            std::ptr::copy_nonoverlapping(text.as_ptr(), buffer, text.len());
+            let _ = GlobalUnlock(memory);
+
+            // The clipboard takes ownership of the memory only if setting its data succeeds
+            if let Err(error) = SetClipboardData(CF_UNICODETEXT, HANDLE(memory.0 as isize)) {
+                let _ = GlobalFree(memory);
+                return Err(error);
+            }
+
+            Ok(())
+        });
+
+        let _ = CloseClipboard();
+
+        result
+    }
+}
@@ -1,14 +1,219 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::app;
 use crate::logf;
+use crate::logger::LogFormat;
+use crate::notification::Verbosity;
+use crate::rules::Rule;
 
 const DEFAULT_CONFIG_BYTES: &[u8] = include_bytes!("..\\res\\config.toml");
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Config {
     pub keybinds: Keybinds,
+    #[serde(default)]
+    pub watchdog: Watchdog,
+    #[serde(default)]
+    pub audit: Audit,
+    #[serde(default)]
+    pub logging: Logging,
+    #[serde(default)]
+    pub cursor: Cursor,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default)]
+    pub pause: Pause,
+    #[serde(default)]
+    pub confirm: Confirm,
+    #[serde(default)]
+    pub locale: Locale,
     pub compatibility: Compatibility,
+    #[serde(
+        default,
+        deserialize_with = "crate::rules::deserialize",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct Watchdog {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    pub threshold_seconds: u64,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 5,
+            threshold_seconds: 15,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct Audit {
+    pub enabled: bool,
+    pub retention_days: u64,
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 365,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct Logging {
+    /// The default log level, or a comma-separated list of directives
+    /// in the same format as the `XTERMINATE_LOG` environment variable.
+    pub level: String,
+
+    /// Whether log files are written as plain text or as JSON Lines
+    pub format: LogFormat,
+
+    /// Size in megabytes after which a new log file is started, 0 for no limit
+    pub max_file_size_mb: u64,
+
+    /// Retention limits applied on startup, 0 disables a limit
+    pub retention_days: u64,
+    pub max_files: usize,
+    pub max_total_size_mb: u64,
+
+    /// Log levels of specific modules, e.g. `"reqwest" = "debug"`.
+    /// Declared last, as TOML tables have to follow all plain values.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Self {
+            level: String::from("info"),
+            format: LogFormat::Text,
+            max_file_size_mb: 10,
+            retention_days: 7,
+            max_files: 50,
+            max_total_size_mb: 100,
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct Cursor {
+    /// Starts a helper process that restores the system cursors
+    /// if xterminate is terminated while in termination mode.
+    pub guard: bool,
+
+    /// Replaces the cursor bundled with xterminate in termination mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminate: Option<CursorImage>,
+
+    /// Replaces the termination mode cursor in suspend mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<CursorImage>,
+
+    /// Replaces the termination mode cursor in info mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<CursorImage>,
+
+    /// Shown in termination and suspend mode while hovering
+    /// a window whose process is protected by a rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forbidden: Option<CursorImage>,
+
+    /// The PNG image of the termination mode cursor in config files
+    /// written before `[cursor.terminate]` existed, see [`Self::migrate`]
+    #[serde(skip_serializing)]
+    pub image: Option<String>,
+
+    /// The hotspot of the legacy `image`
+    #[serde(skip_serializing)]
+    pub hotspot: Option<[u32; 2]>,
+}
+
+impl Cursor {
+    /// Moves the `image` and `hotspot` keys of config files written before
+    /// `[cursor.terminate]` existed into it, unless it is set already. Returns
+    /// a warning describing the migration, if any. The keys are written in
+    /// their new form the next time the config is saved.
+    pub fn migrate(&mut self) -> Option<String> {
+        let hotspot = self.hotspot.take().unwrap_or_default();
+        let image = self.image.take()?;
+
+        if self.terminate.is_some() {
+            return Some(String::from(
+                "cursor.image: ignored in favour of [cursor.terminate], remove it from the config",
+            ));
+        }
+
+        self.terminate = Some(CursorImage { image, hotspot });
+
+        Some(String::from(
+            "cursor.image: deprecated, moved to [cursor.terminate] along with cursor.hotspot",
+        ))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CursorImage {
+    /// A `.png`, `.cur` or `.ani` file, relative to the config directory
+    pub image: String,
+
+    /// The pixel of a PNG `image` that clicks, counted from its top left
+    /// corner. Cursor files specify their own hotspot.
+    #[serde(default)]
+    pub hotspot: [u32; 2],
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct Notifications {
+    /// Which tray notifications are shown
+    pub verbosity: Verbosity,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct Pause {
+    /// Minutes after which paused hotkeys are resumed automatically, 0 to never resume
+    pub resume_after_minutes: u64,
+}
+
+/// Which keybinds ask for confirmation before acting on a process.
+/// Rules can force or skip the confirmation for specific applications.
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct Confirm {
+    pub terminate_immediate: bool,
+    pub terminate_click: bool,
+    pub restart_immediate: bool,
+
+    /// Seconds after which the confirmation is accepted automatically, 0 to wait
+    pub countdown_seconds: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct Locale {
+    /// The language of dialogs, notifications and the tray menu, e.g. `"de"`.
+    /// Defaults to the language of the Windows user interface.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -24,6 +229,50 @@ pub struct Keybinds {
     pub terminate_click: Vec<String>,
     pub terminate_click_confirm: Vec<String>,
     pub terminate_abort: Vec<String>,
+    #[serde(default = "default_restart_immediate")]
+    pub restart_immediate: Vec<String>,
+    #[serde(default = "default_pause_toggle")]
+    pub pause_toggle: Vec<String>,
+    #[serde(default = "default_suspend_click")]
+    pub suspend_click: Vec<String>,
+    #[serde(default = "default_info_click")]
+    pub info_click: Vec<String>,
+}
+
+/// Used for config files created before the restart keybind was introduced.
+fn default_restart_immediate() -> Vec<String> {
+    vec![
+        String::from("VK_LCONTROL"),
+        String::from("VK_LMENU"),
+        String::from("VK_F5"),
+    ]
+}
+
+/// Used for config files created before hotkeys could be paused.
+fn default_pause_toggle() -> Vec<String> {
+    vec![
+        String::from("VK_LCONTROL"),
+        String::from("VK_LMENU"),
+        String::from("VK_PAUSE"),
+    ]
+}
+
+/// Used for config files created before suspend mode was introduced.
+fn default_suspend_click() -> Vec<String> {
+    vec![
+        String::from("VK_LCONTROL"),
+        String::from("VK_LMENU"),
+        String::from("VK_HOME"),
+    ]
+}
+
+/// Used for config files created before info mode was introduced.
+fn default_info_click() -> Vec<String> {
+    vec![
+        String::from("VK_LCONTROL"),
+        String::from("VK_LMENU"),
+        String::from("VK_INSERT"),
+    ]
 }
 
 /// Reads the [`Config`] structure from the `config.toml` file located in `&ProgramData%/xterminate/config.toml`.
@@ -34,7 +283,7 @@ pub struct Keybinds {
 /// cannot be written to.
 #[must_use]
 pub fn load() -> Config {
-    let default_config = toml::from_slice::<Config>(DEFAULT_CONFIG_BYTES).unwrap();
+    let default_config = defaults();
 
     let path = app::config_path();
 
@@ -84,26 +333,144 @@ pub fn load() -> Config {
         logf!("Config replaced");
     }
 
+    if let Some(warning) = config.cursor.migrate() {
+        logf!("WARNING: {warning}");
+    }
+
     logf!("Configuration loaded");
-    logf!("Config:\n{config:#?}");
+    logf!(Debug: "Config:\n{config:#?}");
 
     config
 }
 
-/// Saves the specified [`Config`] to disk.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read config file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to parse config file: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Reads the [`Config`] structure from the config file again after it has
+/// been edited. Unlike [`load`], the file is neither created nor replaced.
+///
+/// # Errors
+///
+/// Returns an error if the config file cannot be read or parsed.
+pub fn reload() -> Result<Config, ConfigError> {
+    let content = std::fs::read(app::config_path())?;
+    let mut config = toml::from_slice::<Config>(&content)?;
+
+    if let Some(warning) = config.cursor.migrate() {
+        logf!("WARNING: {warning}");
+    }
+
+    logf!("Configuration reloaded");
+    logf!(Debug: "Config:\n{config:#?}");
+
+    Ok(config)
+}
+
+/// Returns the default [`Config`], as written to disk if no config file exists.
+///
+/// # Panics
+///
+/// Panics if the bundled default config cannot be parsed.
+#[must_use]
+pub fn defaults() -> Config {
+    toml::from_slice::<Config>(DEFAULT_CONFIG_BYTES).expect("failed to parse default config")
+}
+
+/// Saves the specified [`Config`] to the config file at `path`.
 ///
 /// # Panics
 ///
 /// This method will panic if Serde fails to serialize the [`Config`] structure
 /// or if the config file cannot be written to for any reason.
-pub fn save(config: &Config) {
+pub fn save(config: &Config, path: &Path) {
     logf!("Writing configuration to disk");
 
-    let path = app::config_path();
-
     let content = toml::to_string_pretty::<Config>(config).expect("failed to serialize config");
 
     std::fs::write(path, content).expect("failed to write to config file");
 
     logf!("Configuration successfully written to disk");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_legacy_cursor_image() {
+        let mut cursor = toml::from_str::<Cursor>(
+            r#"
+            image = "C:\\cursor.png"
+            hotspot = [3, 4]
+            guard = true
+            "#,
+        )
+        .unwrap();
+
+        assert!(cursor.migrate().is_some());
+
+        let terminate = cursor.terminate.as_ref().unwrap();
+        assert_eq!(terminate.image, "C:\\cursor.png");
+        assert_eq!(terminate.hotspot, [3, 4]);
+        assert!(cursor.image.is_none());
+
+        // The legacy keys are not written back
+        let saved = toml::from_str::<Cursor>(&toml::to_string(&cursor).unwrap()).unwrap();
+        assert!(saved.image.is_none());
+        assert!(saved.hotspot.is_none());
+        assert_eq!(saved.terminate.unwrap().hotspot, [3, 4]);
+    }
+
+    #[test]
+    fn keeps_configured_terminate_cursor() {
+        let mut cursor = toml::from_str::<Cursor>(
+            r#"
+            image = "old.png"
+
+            [terminate]
+            image = "new.png"
+            "#,
+        )
+        .unwrap();
+
+        assert!(cursor.migrate().is_some());
+        assert_eq!(cursor.terminate.unwrap().image, "new.png");
+    }
+
+    #[test]
+    fn drops_legacy_hotspot_without_image() {
+        let mut cursor = toml::from_str::<Cursor>("hotspot = [0, 0]").unwrap();
+
+        assert!(cursor.migrate().is_none());
+        assert!(cursor.terminate.is_none());
+        assert!(cursor.hotspot.is_none());
+    }
+
+    #[test]
+    fn saves_logging_format_and_modules() {
+        let mut config = defaults();
+        config
+            .logging
+            .modules
+            .insert(String::from("xterminate::input"), String::from("trace"));
+        config.logging.format = LogFormat::Json;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        save(&config, &path);
+
+        let saved = toml::from_str::<Config>(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.logging.modules, config.logging.modules);
+        assert_eq!(saved.logging.format, LogFormat::Json);
+        assert_eq!(
+            saved.logging.max_file_size_mb,
+            config.logging.max_file_size_mb
+        );
+    }
+}
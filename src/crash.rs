@@ -0,0 +1,208 @@
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::Local;
+
+use crate::{app, logger};
+
+/// The amount of log lines included at the end of a crash report.
+const LOG_LINE_COUNT: usize = 100;
+
+/// The file in the crash report directory that holds the path of the most
+/// recent crash report until the user has been told about it.
+const PENDING_FILENAME: &str = "pending";
+
+/// Writes a crash report for a panic into the crash report directory and marks it
+/// as pending, so the user is told about it the next time xterminate starts.
+///
+/// The report holds the panic message and location, the name of the panicking
+/// thread, a symbolised backtrace, environment information, the sanitised
+/// config file and the last lines of today's log file.
+///
+/// # Errors
+///
+/// Returns an error if the crash report directory cannot be created or
+/// the report cannot be written.
+pub fn write_report(info: &std::panic::PanicHookInfo) -> std::io::Result<PathBuf> {
+    let directory = app::crash_reports_path();
+    std::fs::create_dir_all(&directory)?;
+
+    let now = Local::now();
+    let path = directory.join(format!("{}.crash", now.format("%Y-%m-%d-%H%M%S")));
+
+    let thread = std::thread::current();
+
+    let mut report = std::fs::File::create(&path)?;
+
+    writeln!(report, "xterminate crash report")?;
+    writeln!(report, "Time: {}", now.to_rfc3339())?;
+    writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(report, "OS: {}", os_info::get())?;
+    writeln!(report, "Thread: {}", thread.name().unwrap_or("<unnamed>"))?;
+    writeln!(report, "Message: {}", panic_message(info))?;
+
+    if let Some(location) = info.location() {
+        writeln!(report, "Location: {location}")?;
+    }
+
+    writeln!(report, "\n--- Backtrace ---\n")?;
+    writeln!(report, "{:?}", backtrace::Backtrace::new())?;
+
+    writeln!(report, "\n--- Config ---\n")?;
+    match std::fs::read_to_string(app::config_path()) {
+        Ok(config) => writeln!(report, "{}", sanitize(&config))?,
+        Err(error) => writeln!(report, "Unable to read config file: {error}")?,
+    }
+
+    writeln!(report, "\n--- Log (last {LOG_LINE_COUNT} lines) ---\n")?;
+    match last_log_lines(LOG_LINE_COUNT) {
+        Ok(lines) => writeln!(report, "{}", sanitize(&lines.join("\n")))?,
+        Err(error) => writeln!(report, "Unable to read log file: {error}")?,
+    }
+
+    std::fs::write(directory.join(PENDING_FILENAME), path.display().to_string())?;
+
+    Ok(path)
+}
+
+/// Returns the path of a crash report that the user has not been told about
+/// yet and marks it as no longer pending, or `None` if there is none.
+#[must_use]
+pub fn take_pending() -> Option<PathBuf> {
+    let pending = app::crash_reports_path().join(PENDING_FILENAME);
+
+    let path = std::fs::read_to_string(&pending).ok()?;
+    let _ = std::fs::remove_file(&pending);
+
+    let path = PathBuf::from(path.trim());
+    path.exists().then_some(path)
+}
+
+/// Returns the message a panic was raised with.
+#[must_use]
+pub fn panic_message<'a>(info: &'a std::panic::PanicHookInfo) -> &'a str {
+    if let Some(message) = info.payload().downcast_ref::<String>() {
+        message
+    } else if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message
+    } else {
+        "no additional error information was supplied"
+    }
+}
+
+/// Removes personal information from text that is meant to be shared,
+/// replacing the user's profile directory and account name with placeholders.
+#[must_use]
+pub fn sanitize(text: &str) -> String {
+    let profile = std::env::var_os("USERPROFILE");
+    let username = std::env::var_os("USERNAME");
+
+    sanitize_with(
+        text,
+        profile.as_deref().map(OsStr::to_string_lossy).as_deref(),
+        username.as_deref().map(OsStr::to_string_lossy).as_deref(),
+    )
+}
+
+/// Replaces the profile directory with `%USERPROFILE%` and then the account name with
+/// `<user>`. The account name is only replaced where it is not part of a longer word,
+/// so a short name like `al` does not mangle every `local` or `final` in the text.
+fn sanitize_with(text: &str, profile: Option<&str>, username: Option<&str>) -> String {
+    let mut sanitized = text.to_owned();
+
+    if let Some(profile) = profile.filter(|profile| !profile.is_empty()) {
+        // Paths in TOML and JSON strings have their backslashes escaped
+        let escaped = profile.replace('\\', "\\\\");
+
+        sanitized = replace_ignore_case(&sanitized, &escaped, "%USERPROFILE%", false);
+        sanitized = replace_ignore_case(&sanitized, profile, "%USERPROFILE%", false);
+    }
+
+    if let Some(username) = username.filter(|username| !username.is_empty()) {
+        sanitized = replace_ignore_case(&sanitized, username, "<user>", true);
+    }
+
+    sanitized
+}
+
+/// Replaces all occurrences of `from`, ignoring ASCII case, since Windows
+/// paths and account names are case-insensitive. If `whole_words` is set,
+/// occurrences directly preceded or followed by a letter, digit or
+/// underscore are left as they are.
+fn replace_ignore_case(text: &str, from: &str, to: &str, whole_words: bool) -> String {
+    let haystack = text.to_ascii_lowercase();
+    let needle = from.to_ascii_lowercase();
+
+    let is_word = |character: Option<char>| {
+        character.is_some_and(|character| character.is_alphanumeric() || character == '_')
+    };
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    // ASCII lowercasing preserves byte offsets, so matches
+    // in `haystack` can be used to slice `text`
+    for (index, _) in haystack.match_indices(&needle) {
+        let end = index + needle.len();
+
+        if whole_words
+            && (is_word(text[..index].chars().next_back()) || is_word(text[end..].chars().next()))
+        {
+            continue;
+        }
+
+        result.push_str(&text[last..index]);
+        result.push_str(to);
+        last = end;
+    }
+
+    result.push_str(&text[last..]);
+    result
+}
+
+/// Returns the last `count` lines of the most recent log file.
+fn last_log_lines(count: usize) -> std::io::Result<Vec<String>> {
+    let path = logger::latest_log_file()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no log file found"))?;
+
+    let content = std::fs::read_to_string(path)?;
+    let lines = content.lines().collect::<Vec<&str>>();
+
+    Ok(lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| (*line).to_owned())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r"C:\Users\al";
+
+    #[test]
+    fn replaces_profile_directory() {
+        let text = r#"path = "C:\\Users\\AL\\config.toml", log = C:\Users\al\log.txt"#;
+
+        assert_eq!(
+            sanitize_with(text, Some(PROFILE), None),
+            r#"path = "%USERPROFILE%\\config.toml", log = %USERPROFILE%\log.txt"#
+        );
+    }
+
+    #[test]
+    fn replaces_username_only_as_whole_word() {
+        let text = r"Running as DESKTOP\al, loaded local config from C:\Users\al\x and D:\al\final";
+
+        assert_eq!(
+            sanitize_with(text, Some(PROFILE), Some("al")),
+            r"Running as DESKTOP\<user>, loaded local config from %USERPROFILE%\x and D:\<user>\final"
+        );
+    }
+
+    #[test]
+    fn ignores_empty_values() {
+        assert_eq!(sanitize_with("text", Some(""), Some("")), "text");
+    }
+}
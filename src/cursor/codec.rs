@@ -0,0 +1,692 @@
+//! Reading and writing of the `.ico` and `.cur` container formats.
+//!
+//! Both formats start with a directory of images, each stored either as a
+//! PNG file or as a headerless BMP (a `BITMAPINFOHEADER` followed by the
+//! colour pixels and a 1-bit transparency mask, with the height doubled to
+//! account for the mask). The only difference between the two is that the
+//! directory of a cursor holds the hotspot of each image where that of an
+//! icon holds its colour planes and bit depth.
+//!
+//! Nothing in this module depends on Windows.
+
+use thiserror::Error;
+
+/// The sizes generated by [`cursor_from_png`] when none are specified,
+/// covering the standard cursor size at display scales from 100% to 400%.
+pub const DEFAULT_CURSOR_SIZES: &[u32] = &[32, 48, 64, 96, 128];
+
+/// The largest width and height an image in a container can have.
+pub const MAX_IMAGE_SIZE: u32 = 256;
+
+const HEADER_SIZE: usize = 6;
+const DIRECTORY_ENTRY_SIZE: usize = 16;
+const BITMAP_HEADER_SIZE: u32 = 40;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error("unexpected end of data")]
+    Truncated,
+
+    #[error("not an icon or cursor file")]
+    InvalidHeader,
+
+    #[error("image {0} is neither a PNG nor a bitmap")]
+    InvalidImage(usize),
+
+    #[error("image size {0}x{1} is not supported, images can be at most 256x256")]
+    UnsupportedSize(u32, u32),
+
+    #[error("hotspot ({0}, {1}) lies outside of the image")]
+    HotspotOutOfBounds(u32, u32),
+
+    #[error("failed to decode PNG: {0}")]
+    PngDecoding(#[from] png::DecodingError),
+
+    #[error("failed to encode PNG: {0}")]
+    PngEncoding(#[from] png::EncodingError),
+}
+
+/// The kind of container, as stored in its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerType {
+    Icon = 1,
+    Cursor = 2,
+}
+
+/// The encoded pixels of an image in a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    /// A complete PNG file
+    Png(Vec<u8>),
+    /// A `BITMAPINFOHEADER` followed by the colour pixels and the transparency mask
+    Bitmap(Vec<u8>),
+}
+
+impl Payload {
+    /// Returns the width and height of the image as stored in the payload's own header.
+    fn dimensions(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Png(data) => Some((read_u32_be(data, 16)?, read_u32_be(data, 20)?)),
+            Self::Bitmap(data) => {
+                let width = read_i32(data, 4)?.unsigned_abs();
+                // The height includes the transparency mask
+                let height = read_i32(data, 8)?.unsigned_abs() / 2;
+                Some((width, height))
+            }
+        }
+    }
+
+    /// Returns the colour planes and bit depth of the image.
+    fn format(&self) -> (u16, u16) {
+        match self {
+            Self::Png(_) => (1, 32),
+            Self::Bitmap(data) => (
+                read_u16(data, 12).unwrap_or(1),
+                read_u16(data, 14).unwrap_or(32),
+            ),
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Png(data) | Self::Bitmap(data) => data,
+        }
+    }
+}
+
+/// A single image in a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+
+    /// The amount of colours in the palette, or 0 if the image has no palette
+    pub color_count: u8,
+
+    /// The point of a cursor image that clicks, relative to its top left
+    /// corner. Always `(0, 0)` for images of icons.
+    pub hotspot: (u16, u16),
+
+    pub payload: Payload,
+}
+
+impl Image {
+    /// Creates a 32-bit bitmap image from non-premultiplied RGBA pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pixels` does not contain exactly `width * height` pixels.
+    #[must_use]
+    pub fn from_rgba(pixels: &RgbaImage, hotspot: (u16, u16)) -> Self {
+        Self {
+            width: pixels.width,
+            height: pixels.height,
+            color_count: 0,
+            hotspot,
+            payload: Payload::Bitmap(encode_bitmap(pixels)),
+        }
+    }
+
+    /// Creates an image that stores the RGBA pixels as a PNG, which
+    /// is far smaller than a bitmap for large images.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pixels cannot be encoded.
+    pub fn from_rgba_png(pixels: &RgbaImage, hotspot: (u16, u16)) -> Result<Self, CodecError> {
+        Ok(Self {
+            width: pixels.width,
+            height: pixels.height,
+            color_count: 0,
+            hotspot,
+            payload: Payload::Png(encode_png(pixels)?),
+        })
+    }
+}
+
+/// An `.ico` or `.cur` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    pub container_type: ContainerType,
+    pub images: Vec<Image>,
+}
+
+impl Container {
+    #[must_use]
+    pub fn new(container_type: ContainerType) -> Self {
+        Self {
+            container_type,
+            images: Vec::new(),
+        }
+    }
+
+    /// Parses an `.ico` or `.cur` file.
+    ///
+    /// The size of each image is taken from its payload rather than the
+    /// directory, since the directory cannot express sizes above 255 and
+    /// some editors write incorrect values to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is not a well-formed icon or cursor file.
+    pub fn parse(data: &[u8]) -> Result<Self, CodecError> {
+        if read_u16(data, 0).ok_or(CodecError::Truncated)? != 0 {
+            return Err(CodecError::InvalidHeader);
+        }
+
+        let container_type = match read_u16(data, 2).ok_or(CodecError::Truncated)? {
+            1 => ContainerType::Icon,
+            2 => ContainerType::Cursor,
+            _ => return Err(CodecError::InvalidHeader),
+        };
+
+        let count = usize::from(read_u16(data, 4).ok_or(CodecError::Truncated)?);
+
+        let mut images = Vec::with_capacity(count);
+
+        for index in 0..count {
+            let entry = HEADER_SIZE + index * DIRECTORY_ENTRY_SIZE;
+            let directory = data
+                .get(entry..entry + DIRECTORY_ENTRY_SIZE)
+                .ok_or(CodecError::Truncated)?;
+
+            let size = read_u32(directory, 8).ok_or(CodecError::Truncated)? as usize;
+            let offset = read_u32(directory, 12).ok_or(CodecError::Truncated)? as usize;
+
+            let bytes = data
+                .get(offset..offset.checked_add(size).ok_or(CodecError::Truncated)?)
+                .ok_or(CodecError::Truncated)?
+                .to_vec();
+
+            let payload = if bytes.starts_with(PNG_SIGNATURE) {
+                Payload::Png(bytes)
+            } else if read_u32(&bytes, 0) == Some(BITMAP_HEADER_SIZE) {
+                Payload::Bitmap(bytes)
+            } else {
+                return Err(CodecError::InvalidImage(index));
+            };
+
+            // A size of 0 in the directory means 256
+            let directory_size = |value: u8| if value == 0 { 256 } else { u32::from(value) };
+
+            let (width, height) = payload
+                .dimensions()
+                .unwrap_or((directory_size(directory[0]), directory_size(directory[1])));
+
+            let hotspot = match container_type {
+                ContainerType::Cursor => (
+                    read_u16(directory, 4).ok_or(CodecError::Truncated)?,
+                    read_u16(directory, 6).ok_or(CodecError::Truncated)?,
+                ),
+                ContainerType::Icon => (0, 0),
+            };
+
+            images.push(Image {
+                width,
+                height,
+                color_count: directory[2],
+                hotspot,
+                payload,
+            });
+        }
+
+        Ok(Self {
+            container_type,
+            images,
+        })
+    }
+
+    /// Encodes the container as an `.ico` or `.cur` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an image is larger than [`MAX_IMAGE_SIZE`].
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CodecError> {
+        let mut data = Vec::new();
+
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&(self.container_type as u16).to_le_bytes());
+        data.extend_from_slice(
+            &u16::try_from(self.images.len())
+                .unwrap_or(u16::MAX)
+                .to_le_bytes(),
+        );
+
+        let mut offset = HEADER_SIZE + self.images.len() * DIRECTORY_ENTRY_SIZE;
+
+        for image in &self.images {
+            if image.width > MAX_IMAGE_SIZE || image.height > MAX_IMAGE_SIZE {
+                return Err(CodecError::UnsupportedSize(image.width, image.height));
+            }
+
+            // 256 does not fit into a byte and is stored as 0
+            let directory_size = |value: u32| u8::try_from(value % MAX_IMAGE_SIZE).unwrap();
+
+            let (x, y) = match self.container_type {
+                ContainerType::Cursor => image.hotspot,
+                ContainerType::Icon => image.payload.format(),
+            };
+
+            let size = image.payload.bytes().len();
+
+            data.push(directory_size(image.width));
+            data.push(directory_size(image.height));
+            data.push(image.color_count);
+            data.push(0);
+            data.extend_from_slice(&x.to_le_bytes());
+            data.extend_from_slice(&y.to_le_bytes());
+            data.extend_from_slice(&u32::try_from(size).unwrap().to_le_bytes());
+            data.extend_from_slice(&u32::try_from(offset).unwrap().to_le_bytes());
+
+            offset += size;
+        }
+
+        for image in &self.images {
+            data.extend_from_slice(image.payload.bytes());
+        }
+
+        Ok(data)
+    }
+}
+
+/// An image of non-premultiplied 8-bit RGBA pixels, stored row by row from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Decodes a PNG file of any colour type and bit depth.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is not a valid PNG file.
+    pub fn from_png(data: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = png::Decoder::new(data);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buffer)?;
+        buffer.truncate(frame.buffer_size());
+
+        let pixels = match frame.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            // Palettes are expanded by the transformations above
+            png::ColorType::Grayscale | png::ColorType::Indexed => buffer
+                .iter()
+                .flat_map(|gray| [*gray, *gray, *gray, u8::MAX])
+                .collect(),
+        };
+
+        Ok(Self {
+            width: frame.width,
+            height: frame.height,
+            pixels,
+        })
+    }
+
+    /// Scales the image to fit into a `size` by `size` square, keeping its aspect
+    /// ratio and placing it in the top left corner so that positions such as the
+    /// hotspot scale by the same factor in both directions. Every target pixel is
+    /// the average of the source pixels it covers, weighted by alpha.
+    #[must_use]
+    pub fn fit_to(&self, size: u32) -> Self {
+        let scale = f64::from(size) / f64::from(self.width.max(self.height).max(1));
+
+        let scaled_width = scaled(self.width, scale).min(size);
+        let scaled_height = scaled(self.height, scale).min(size);
+
+        let mut pixels = vec![0u8; (size * size * 4) as usize];
+
+        for y in 0..scaled_height {
+            let top = f64::from(y) / scale;
+            let bottom = (f64::from(y + 1) / scale).min(f64::from(self.height));
+
+            for x in 0..scaled_width {
+                let left = f64::from(x) / scale;
+                let right = (f64::from(x + 1) / scale).min(f64::from(self.width));
+
+                let pixel = self.average(left, top, right, bottom);
+                let index = ((y * size + x) * 4) as usize;
+                pixels[index..index + 4].copy_from_slice(&pixel);
+            }
+        }
+
+        Self {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    /// Returns the average colour of the area between the source coordinates.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::many_single_char_names
+    )]
+    fn average(&self, left: f64, top: f64, right: f64, bottom: f64) -> [u8; 4] {
+        let mut sum = [0.0f64; 4];
+        let mut area = 0.0;
+
+        let mut y = top.floor();
+        while y < bottom {
+            let height = (y + 1.0).min(bottom) - y.max(top);
+
+            let mut x = left.floor();
+            while x < right {
+                let width = (x + 1.0).min(right) - x.max(left);
+                let weight = width * height;
+
+                let index = ((y as u32 * self.width + x as u32) * 4) as usize;
+                let pixel = &self.pixels[index..index + 4];
+                let alpha = f64::from(pixel[3]);
+
+                // Colours are weighted by alpha so transparent pixels do not darken edges
+                for channel in 0..3 {
+                    sum[channel] += f64::from(pixel[channel]) * alpha * weight;
+                }
+                sum[3] += alpha * weight;
+                area += weight;
+
+                x += 1.0;
+            }
+
+            y += 1.0;
+        }
+
+        if area <= 0.0 || sum[3] <= 0.0 {
+            return [0; 4];
+        }
+
+        [
+            (sum[0] / sum[3]).round() as u8,
+            (sum[1] / sum[3]).round() as u8,
+            (sum[2] / sum[3]).round() as u8,
+            (sum[3] / area).round() as u8,
+        ]
+    }
+}
+
+/// Generates a cursor with an image of each of the specified sizes from a PNG file.
+///
+/// The hotspot is given in pixels of the PNG and scaled along with the image.
+/// Images up to 128 pixels are stored as bitmaps, which every version of Windows
+/// can load, and larger ones as PNGs to keep the file small.
+///
+/// # Errors
+///
+/// Returns an error if the PNG cannot be decoded, a size is 0 or exceeds
+/// [`MAX_IMAGE_SIZE`], or the hotspot lies outside of the PNG.
+pub fn cursor_from_png(
+    png: &[u8],
+    hotspot: (u32, u32),
+    sizes: &[u32],
+) -> Result<Container, CodecError> {
+    let source = RgbaImage::from_png(png)?;
+
+    if hotspot.0 >= source.width || hotspot.1 >= source.height {
+        return Err(CodecError::HotspotOutOfBounds(hotspot.0, hotspot.1));
+    }
+
+    let mut cursor = Container::new(ContainerType::Cursor);
+
+    for &size in sizes {
+        if size == 0 || size > MAX_IMAGE_SIZE {
+            return Err(CodecError::UnsupportedSize(size, size));
+        }
+
+        let scale = f64::from(size) / f64::from(source.width.max(source.height));
+        let hotspot = (
+            u16::try_from(scaled(hotspot.0, scale).min(size - 1)).unwrap(),
+            u16::try_from(scaled(hotspot.1, scale).min(size - 1)).unwrap(),
+        );
+
+        let pixels = source.fit_to(size);
+
+        cursor.images.push(if size > 128 {
+            Image::from_rgba_png(&pixels, hotspot)?
+        } else {
+            Image::from_rgba(&pixels, hotspot)
+        });
+    }
+
+    Ok(cursor)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn scaled(value: u32, scale: f64) -> u32 {
+    (f64::from(value) * scale).floor() as u32
+}
+
+/// Encodes pixels as a 32-bit bottom-up bitmap followed by its transparency mask.
+fn encode_bitmap(image: &RgbaImage) -> Vec<u8> {
+    assert_eq!(
+        image.pixels.len(),
+        (image.width * image.height * 4) as usize,
+        "pixel buffer does not match the image size"
+    );
+
+    // Rows of the 1-bit mask are padded to a multiple of 4 bytes
+    let mask_stride = image.width.div_ceil(32) * 4;
+    let color_size = image.width * image.height * 4;
+    let mask_size = mask_stride * image.height;
+
+    let mut data = Vec::with_capacity((BITMAP_HEADER_SIZE + color_size + mask_size) as usize);
+
+    data.extend_from_slice(&BITMAP_HEADER_SIZE.to_le_bytes());
+    data.extend_from_slice(&i32::try_from(image.width).unwrap().to_le_bytes());
+    data.extend_from_slice(&i32::try_from(image.height * 2).unwrap().to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes()); // Planes
+    data.extend_from_slice(&32u16.to_le_bytes()); // Bit depth
+    data.extend_from_slice(&0u32.to_le_bytes()); // BI_RGB
+    data.extend_from_slice(&(color_size + mask_size).to_le_bytes());
+    data.extend_from_slice(&[0; 16]); // Resolution and palette
+
+    let rows = || image.pixels.chunks_exact((image.width * 4) as usize).rev();
+
+    for row in rows() {
+        for pixel in row.chunks_exact(4) {
+            data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+
+    // Fully transparent pixels are masked out for the benefit of
+    // renderers that ignore the alpha channel
+    for row in rows() {
+        let mut mask = vec![0u8; mask_stride as usize];
+
+        for (x, pixel) in row.chunks_exact(4).enumerate() {
+            if pixel[3] == 0 {
+                mask[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+
+        data.extend_from_slice(&mask);
+    }
+
+    data
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, CodecError> {
+    let mut data = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut data, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.pixels)?;
+    writer.finish()?;
+
+    Ok(data)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURSOR: &[u8] = include_bytes!("..\\..\\res\\cursor.cur");
+
+    /// Returns a cursor header with a single directory entry pointing at `size` bytes at `offset`.
+    fn header(count: u16, size: u32, offset: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend_from_slice(&[32, 32, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&size.to_le_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn round_trips_bundled_cursor() {
+        let cursor = Container::parse(CURSOR).unwrap();
+
+        assert_eq!(cursor.container_type, ContainerType::Cursor);
+        assert!(!cursor.images.is_empty());
+
+        let bytes = cursor.to_bytes().unwrap();
+        assert_eq!(bytes, CURSOR);
+        assert_eq!(Container::parse(&bytes).unwrap(), cursor);
+    }
+
+    #[test]
+    fn round_trips_generated_cursor() {
+        let png = encode_png(&RgbaImage {
+            width: 2,
+            height: 2,
+            pixels: vec![255; 16],
+        })
+        .unwrap();
+
+        let cursor = cursor_from_png(&png, (1, 0), &[32, 256]).unwrap();
+        let parsed = Container::parse(&cursor.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(parsed, cursor);
+        assert_eq!(parsed.images[0].hotspot, (16, 0));
+        assert_eq!(
+            (parsed.images[1].width, parsed.images[1].height),
+            (256, 256)
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        for length in 0..CURSOR.len() {
+            assert!(
+                Container::parse(&CURSOR[..length]).is_err(),
+                "parsed cursor truncated to {length} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_header() {
+        assert!(matches!(
+            Container::parse(&[1, 0, 2, 0, 0, 0]),
+            Err(CodecError::InvalidHeader)
+        ));
+        assert!(matches!(
+            Container::parse(&[0, 0, 3, 0, 0, 0]),
+            Err(CodecError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_image_count() {
+        // A valid first image, but a count claiming more directory entries than there are
+        let bitmap = encode_bitmap(&RgbaImage {
+            width: 1,
+            height: 1,
+            pixels: vec![0; 4],
+        });
+        let mut data = header(u16::MAX, u32::try_from(bitmap.len()).unwrap(), 22);
+        data.extend_from_slice(&bitmap);
+
+        assert!(matches!(
+            Container::parse(&data),
+            Err(CodecError::Truncated | CodecError::InvalidImage(_))
+        ));
+
+        data[4..6].copy_from_slice(&1u16.to_le_bytes());
+        assert!(Container::parse(&data).is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_offsets() {
+        for (size, offset) in [
+            (16, 1000),
+            (u32::MAX, 22),
+            (16, u32::MAX),
+            (u32::MAX, u32::MAX),
+        ] {
+            let mut data = header(1, size, offset);
+            data.extend_from_slice(&[0; 16]);
+
+            assert!(
+                matches!(Container::parse(&data), Err(CodecError::Truncated)),
+                "parsed image of {size} bytes at offset {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_payload() {
+        let mut data = header(1, 16, 22);
+        data.extend_from_slice(&[0xff; 16]);
+
+        assert!(matches!(
+            Container::parse(&data),
+            Err(CodecError::InvalidImage(0))
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_images() {
+        let mut cursor = Container::parse(CURSOR).unwrap();
+        cursor.images[0].width = MAX_IMAGE_SIZE + 1;
+
+        assert!(matches!(
+            cursor.to_bytes(),
+            Err(CodecError::UnsupportedSize(257, _))
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+//! A helper process that restores the system cursors if xterminate exits
+//! while they are swapped, which the panic hook cannot do when xterminate
+//! is terminated or crashes outside of Rust code.
+
+use std::time::Duration;
+
+use crate::process::Process;
+use crate::{app, logf};
+
+/// The command line command that starts the guard.
+pub const COMMAND: &str = "cursor-guard";
+
+/// Starts the guard as a separate xterminate process watching this one.
+///
+/// # Errors
+///
+/// Returns an error if the guard process cannot be started.
+///
+/// # Panics
+///
+/// Panics if the path of the xterminate executable cannot be determined.
+pub fn spawn() -> Result<(), windows::core::Error> {
+    let executable = std::env::current_exe().expect("failed to get path to executable");
+
+    // The path is quoted since it may contain spaces, e.g. in `Program Files`
+    let command_line = format!(
+        "\"{}\" {COMMAND} {}",
+        executable.display(),
+        std::process::id()
+    );
+
+    logf!("Starting cursor guard");
+
+    app::run_executable_in(&executable, std::ffi::OsStr::new(&command_line), None, None)
+}
+
+/// Waits for the xterminate process with the specified id to exit and
+/// restores the system cursors if it left them swapped. Returns the
+/// exit code of the guard process.
+#[must_use]
+pub fn run(args: &[String]) -> i32 {
+    let Some(pid) = args.first().and_then(|pid| pid.parse::<u32>().ok()) else {
+        eprintln!("error: '{COMMAND}' expects the id of the process to watch");
+        return 2;
+    };
+
+    let process = match Process::try_open(pid) {
+        Ok(process) => process,
+        Err(error) => {
+            logf!("ERROR: cursor guard failed to open process (pid: {pid}): {error}");
+            return 1;
+        }
+    };
+
+    logf!("Cursor guard watching process (pid: {pid})");
+
+    // The timeout saturates to INFINITE
+    while !process.wait_for_exit(Duration::MAX) {}
+
+    if super::restore_if_swapped() {
+        logf!("Cursor guard restored the system cursors after xterminate exited");
+    }
+
+    0
+}
@@ -1,18 +1,24 @@
+pub mod codec;
+pub mod guard;
+pub mod theme;
+
 use windows::core::{PCSTR, PCWSTR};
 use windows::Win32::Foundation::{GetLastError, HANDLE, HMODULE, POINT};
 
 use windows::Win32::UI::WindowsAndMessaging::{
-    CopyImage, GetCursorPos, LoadImageA, SetSystemCursor, SystemParametersInfoA, HCURSOR,
-    IDC_APPSTARTING, IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_HELP, IDC_IBEAM, IDC_NO, IDC_SIZEALL,
-    IDC_SIZENESW, IDC_SIZENS, IDC_SIZENWSE, IDC_SIZEWE, IDC_UPARROW, IDC_WAIT, IMAGE_CURSOR,
-    IMAGE_FLAGS, LR_LOADFROMFILE, LR_SHARED, OCR_APPSTARTING, OCR_CROSS, OCR_HAND, OCR_HELP,
-    OCR_IBEAM, OCR_NO, OCR_NORMAL, OCR_SIZEALL, OCR_SIZENESW, OCR_SIZENS, OCR_SIZENWSE, OCR_SIZEWE,
-    OCR_UP, OCR_WAIT, SPI_SETCURSORS, SYSTEM_CURSOR_ID, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    CopyImage, GetCursorPos, LoadImageA, LoadImageW, SetSystemCursor, SystemParametersInfoA,
+    HCURSOR, IDC_APPSTARTING, IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_HELP, IDC_IBEAM, IDC_NO,
+    IDC_SIZEALL, IDC_SIZENESW, IDC_SIZENS, IDC_SIZENWSE, IDC_SIZEWE, IDC_UPARROW, IDC_WAIT,
+    IMAGE_CURSOR, IMAGE_FLAGS, LR_LOADFROMFILE, LR_SHARED, OCR_APPSTARTING, OCR_CROSS, OCR_HAND,
+    OCR_HELP, OCR_IBEAM, OCR_NO, OCR_NORMAL, OCR_SIZEALL, OCR_SIZENESW, OCR_SIZENS, OCR_SIZENWSE,
+    OCR_SIZEWE, OCR_UP, OCR_WAIT, SPI_SETCURSORS, SYSTEM_CURSOR_ID,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
 };
 
 use crate::error::{AppError, AppResult};
-use crate::logf;
+use crate::{app, logf, wide};
 
+#[derive(Clone, Copy)]
 pub enum CursorType {
     AppStarting,
     Normal,
@@ -30,6 +36,26 @@ pub enum CursorType {
     Wait,
 }
 
+impl CursorType {
+    /// Every system cursor type, in the order they are replaced in.
+    pub const ALL: [Self; 14] = [
+        Self::AppStarting,
+        Self::Normal,
+        Self::Cross,
+        Self::Hand,
+        Self::Help,
+        Self::IBeam,
+        Self::No,
+        Self::SizeAll,
+        Self::SizeNESW,
+        Self::SizeNS,
+        Self::SizeNWSE,
+        Self::SizeWE,
+        Self::Up,
+        Self::Wait,
+    ];
+}
+
 /// Returns the position of the cursor.
 ///
 /// # Panics
@@ -78,28 +104,21 @@ impl Cursor {
         Self { handle: hcursor.0 }
     }
 
-    /// Loads a cursor from the specified file. If the file does not exist
-    /// or is not a valid cursor file, [`AppError`] is returned.
+    /// Loads a cursor from the specified `.cur` or `.ani` file. If the file does
+    /// not exist or is not a valid cursor file, [`AppError`] is returned.
     ///
     /// # Errors
     ///
-    /// This method returns an error if the underlaying call to [`windows::Windows::Win32::UI::WindowsAndMessaging::LoadImageA`] fails.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `filename` can not be turned into a valid [`CString`].
+    /// This method returns an error if the underlaying call to [`windows::Windows::Win32::UI::WindowsAndMessaging::LoadImageW`] fails.
     pub fn load_from_file(filename: &str) -> AppResult<Self> {
-        logf!("Loading cursor image from file (path: {})", filename);
+        logf!(Debug: "Loading cursor image from file (path: {})", filename);
+
+        let filename = wide::to_wide_nul(filename);
 
         let hcursor = unsafe {
-            LoadImageA(
+            LoadImageW(
                 HMODULE(0),
-                PCSTR(
-                    std::ffi::CString::new(filename)
-                        .unwrap()
-                        .as_bytes()
-                        .as_ptr(),
-                ),
+                PCWSTR(filename.as_ptr()),
                 IMAGE_CURSOR,
                 0,
                 0,
@@ -141,7 +160,7 @@ impl Cursor {
 ///
 /// This function panics if the internal call to [`SetSystemCursor()`] returns `false`.
 pub fn set(cursor_type: &CursorType, cursor: &Cursor) {
-    logf!("Setting cursor (idc: {})", idc(cursor_type).0 as isize);
+    logf!(Debug: "Setting cursor (idc: {})", idc(cursor_type).0 as isize);
 
     let success = unsafe { SetSystemCursor(HCURSOR(cursor.handle), ocr(cursor_type)).is_ok() };
 
@@ -155,25 +174,48 @@ pub fn set(cursor_type: &CursorType, cursor: &Cursor) {
     );
 }
 
-/// Sets all the system cursor types to the specified cursor
+/// Sets all the system cursor types to the specified cursor. A marker file is
+/// written beforehand so the cursors can be restored by [`restore_if_swapped`]
+/// if xterminate exits without calling [`reset`].
 pub fn set_all(cursor: &Cursor) {
     logf!("Setting all cursors");
 
-    // This is terrible but it works
-    set(&CursorType::AppStarting, &cursor.copy());
-    set(&CursorType::Normal, &cursor.copy());
-    set(&CursorType::Cross, &cursor.copy());
-    set(&CursorType::Hand, &cursor.copy());
-    set(&CursorType::Help, &cursor.copy());
-    set(&CursorType::IBeam, &cursor.copy());
-    set(&CursorType::No, &cursor.copy());
-    set(&CursorType::SizeAll, &cursor.copy());
-    set(&CursorType::SizeNESW, &cursor.copy());
-    set(&CursorType::SizeNS, &cursor.copy());
-    set(&CursorType::SizeNWSE, &cursor.copy());
-    set(&CursorType::SizeWE, &cursor.copy());
-    set(&CursorType::Up, &cursor.copy());
-    set(&CursorType::Wait, &cursor.copy());
+    mark_swapped();
+
+    for cursor_type in &CursorType::ALL {
+        set(cursor_type, &cursor.copy());
+    }
+}
+
+/// Sets all the system cursor types to the cursor in the specified `.cur` or
+/// `.ani` file. Unlike [`set_all`], the file is loaded once for every cursor type
+/// instead of copying a single cursor, which would lose the animation of `.ani` files.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be loaded, in which case no cursor is changed.
+pub fn set_all_from_file(filename: &str) -> AppResult<()> {
+    logf!("Setting all cursors (path: {filename})");
+
+    let cursors = CursorType::ALL
+        .iter()
+        .map(|_| Cursor::load_from_file(filename))
+        .collect::<AppResult<Vec<Cursor>>>()?;
+
+    mark_swapped();
+
+    for (cursor_type, cursor) in CursorType::ALL.iter().zip(&cursors) {
+        set(cursor_type, cursor);
+    }
+
+    Ok(())
+}
+
+/// Writes the marker checked by [`restore_if_swapped`].
+fn mark_swapped() {
+    if let Err(error) = std::fs::write(app::cursor_marker_path(), std::process::id().to_string()) {
+        logf!("WARNING: failed to write cursor marker, cursors will not be restored after a crash: {error}");
+    }
 }
 
 /// Resets system cursors to Windows the user-defined cursors
@@ -184,6 +226,44 @@ pub fn set_all(cursor: &Cursor) {
 pub fn reset() {
     logf!("Resetting cursors");
 
+    assert!(
+        reload_system_cursors(),
+        "failed to reset system cursor: SystemPerametersInfoA returned 0 [{}]",
+        unsafe { GetLastError().unwrap_err() }
+    );
+}
+
+/// Resets the system cursors if they were left swapped by a previous run or by
+/// this one, e.g. because xterminate was terminated or crashed in termination mode.
+/// Unlike [`reset`], this never panics, so it is safe to call from the panic hook.
+///
+/// Returns true if the cursors were swapped and have been restored.
+pub fn restore_if_swapped() -> bool {
+    let marker = app::cursor_marker_path();
+
+    let Ok(pid) = std::fs::read_to_string(&marker) else {
+        return false;
+    };
+
+    logf!(
+        "WARNING: system cursors were left swapped by xterminate (pid: {}), restoring them",
+        pid.trim()
+    );
+
+    if !reload_system_cursors() {
+        logf!("ERROR: failed to restore system cursors [{}]", unsafe {
+            GetLastError().unwrap_err()
+        });
+
+        return false;
+    }
+
+    true
+}
+
+/// Reloads the user's cursor scheme, replacing any cursors set by
+/// [`set`], and removes the marker written by [`set_all`].
+fn reload_system_cursors() -> bool {
     let success = unsafe {
         SystemParametersInfoA(
             SPI_SETCURSORS,
@@ -194,11 +274,11 @@ pub fn reset() {
         .is_ok()
     };
 
-    assert!(
-        success,
-        "failed to reset system cursor: SystemPerametersInfoA returned 0 [{}]",
-        unsafe { GetLastError().unwrap_err() }
-    );
+    if success {
+        let _ = std::fs::remove_file(app::cursor_marker_path());
+    }
+
+    success
 }
 
 /// Converts a [`CursorType`] to a Windows `OCR_` value.
@@ -0,0 +1,160 @@
+//! Resolves the cursors configured in the `[cursor]` section of the config
+//! file into files that can be loaded by [`super::set_all_from_file`].
+
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use super::codec;
+use crate::config::{self, CursorImage};
+
+#[derive(Error, Debug)]
+pub enum AssetError {
+    #[error("'{0}' does not exist")]
+    NotFound(PathBuf),
+
+    #[error("'{0}' is not a .png, .cur or .ani file")]
+    UnsupportedFormat(PathBuf),
+
+    #[error("'{path}' is not a valid image or cursor: {source}")]
+    Invalid {
+        path: PathBuf,
+        source: codec::CodecError,
+    },
+
+    #[error("'{0}' is not a valid animated cursor")]
+    InvalidAnimated(PathBuf),
+
+    #[error("failed to access '{path}': {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// The cursors shown in termination, suspend and info mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorTheme {
+    /// Shown while termination mode is active
+    pub terminate: PathBuf,
+
+    /// Shown while suspend mode is active, `terminate` unless configured
+    pub suspend: PathBuf,
+
+    /// Shown while info mode is active, `terminate` unless configured
+    pub info: PathBuf,
+
+    /// Shown instead of `terminate` or `suspend` while hovering a window
+    /// whose process is protected by a rule, if configured
+    pub forbidden: Option<PathBuf>,
+}
+
+impl CursorTheme {
+    /// Resolves the configured cursors. Relative paths are resolved against
+    /// `config_directory`, and cursors generated from PNG images are written to
+    /// `output_directory`. Cursors that fail to resolve are replaced by
+    /// `default` or left out, and the problems are returned alongside the
+    /// theme, each prefixed by the config key they were found in.
+    #[must_use]
+    pub fn load(
+        config: &config::Cursor,
+        config_directory: &Path,
+        output_directory: &Path,
+        default: PathBuf,
+    ) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+
+        let mut resolve = |key: &str, image: Option<&CursorImage>| {
+            let image = image?;
+
+            resolve(image, key, config_directory, output_directory)
+                .map_err(|error| errors.push(format!("cursor.{key}: {error}")))
+                .ok()
+        };
+
+        let terminate = resolve("terminate", config.terminate.as_ref()).unwrap_or(default);
+        let suspend =
+            resolve("suspend", config.suspend.as_ref()).unwrap_or_else(|| terminate.clone());
+        let info = resolve("info", config.info.as_ref()).unwrap_or_else(|| terminate.clone());
+        let forbidden = resolve("forbidden", config.forbidden.as_ref());
+
+        (
+            Self {
+                terminate,
+                suspend,
+                info,
+                forbidden,
+            },
+            errors,
+        )
+    }
+}
+
+/// Validates a configured cursor and returns the path of a `.cur`
+/// or `.ani` file, generating one first if `image` is a PNG.
+fn resolve(
+    image: &CursorImage,
+    name: &str,
+    config_directory: &Path,
+    output_directory: &Path,
+) -> Result<PathBuf, AssetError> {
+    let path = config_directory.join(&image.image);
+
+    let data = std::fs::read(&path).map_err(|error| match error.kind() {
+        std::io::ErrorKind::NotFound => AssetError::NotFound(path.clone()),
+        _ => AssetError::Io {
+            path: path.clone(),
+            source: error,
+        },
+    })?;
+
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("png") => {
+            let cursor = codec::cursor_from_png(
+                &data,
+                (image.hotspot[0], image.hotspot[1]),
+                codec::DEFAULT_CURSOR_SIZES,
+            )
+            .and_then(|cursor| cursor.to_bytes())
+            .map_err(|source| AssetError::Invalid {
+                path: path.clone(),
+                source,
+            })?;
+
+            let output = output_directory.join(format!("{name}.cur"));
+
+            std::fs::create_dir_all(output_directory)
+                .and_then(|()| std::fs::write(&output, cursor))
+                .map_err(|source| AssetError::Io {
+                    path: output.clone(),
+                    source,
+                })?;
+
+            Ok(output)
+        }
+
+        Some("cur") => {
+            codec::Container::parse(&data).map_err(|source| AssetError::Invalid {
+                path: path.clone(),
+                source,
+            })?;
+
+            Ok(path)
+        }
+
+        // Animated cursors are RIFF files of the ACON form type
+        Some("ani") => {
+            if data.get(0..4) == Some(b"RIFF") && data.get(8..12) == Some(b"ACON") {
+                Ok(path)
+            } else {
+                Err(AssetError::InvalidAnimated(path))
+            }
+        }
+
+        _ => Err(AssetError::UnsupportedFormat(path)),
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::app::{self, App};
+use crate::crash::sanitize;
+use crate::input::Keybind;
+use crate::logger::{self, LogRecord};
+
+/// The amount of most recent log files included in a bundle.
+const LOG_FILE_COUNT: usize = 5;
+
+#[derive(Error, Debug)]
+pub enum DiagnosticsError {
+    #[error("failed to read or write file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to write zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+/// Returns the default file name of a diagnostics bundle created now.
+#[must_use]
+pub fn default_file_name() -> String {
+    format!(
+        "xterminate-diagnostics-{}.zip",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    )
+}
+
+/// Writes a zip archive to `destination` that holds everything needed to
+/// investigate a problem report: environment information, registry settings
+/// and the keybind table in `info.txt`, the config file, the most recent log
+/// files and all crash reports. Usernames and home paths are redacted from
+/// every file using [`sanitize`].
+///
+/// Files that do not exist, such as a missing config file, are left out.
+///
+/// # Errors
+///
+/// Returns an error if the archive cannot be written or a file
+/// that should be included exists but cannot be read.
+pub fn create_bundle(
+    destination: &Path,
+    keybinds: &HashMap<String, Keybind>,
+) -> Result<(), DiagnosticsError> {
+    let mut zip = ZipWriter::new(std::fs::File::create(destination)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("info.txt", options)?;
+    zip.write_all(sanitize(&info(keybinds)).as_bytes())?;
+
+    if let Some(config) = read_if_exists(Path::new(&app::config_path()))? {
+        zip.start_file("config.toml", options)?;
+        zip.write_all(sanitize(&config).as_bytes())?;
+    }
+
+    for path in logger::recent_log_files(LOG_FILE_COUNT).unwrap_or_default() {
+        let Some(content) = read_if_exists(&path)? else {
+            continue;
+        };
+
+        zip.start_file(format!("logs/{}", file_name(&path)), options)?;
+        zip.write_all(sanitize_log(&content).as_bytes())?;
+    }
+
+    for path in crash_reports() {
+        let Some(content) = read_if_exists(&path)? else {
+            continue;
+        };
+
+        zip.start_file(format!("crashes/{}", file_name(&path)), options)?;
+        zip.write_all(sanitize(&content).as_bytes())?;
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Describes the environment xterminate is running in.
+fn info(keybinds: &HashMap<String, Keybind>) -> String {
+    let mut info = String::new();
+
+    let _ = writeln!(info, "xterminate diagnostics");
+    let _ = writeln!(info, "Created: {}", Local::now().to_rfc3339());
+    let _ = writeln!(info, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(info, "OS: {}", os_info::get());
+    let _ = writeln!(info, "Session: {}", logger::session_id());
+    let _ = writeln!(info, "Executable: {}", app::executable_path());
+
+    let _ = writeln!(info, "\n--- Registry ---\n");
+    let _ = writeln!(info, "Start with Windows: {}", App::autostart());
+    let _ = writeln!(info, "Check for updates on startup: {}", App::autoupdate());
+
+    let _ = writeln!(info, "\n--- Keybinds ---\n");
+
+    let mut keybinds = keybinds.iter().collect::<Vec<_>>();
+    keybinds.sort_by_key(|(name, _)| name.as_str());
+
+    let width = keybinds
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    for (name, keybind) in keybinds {
+        let _ = writeln!(info, "{name:<width$}  {keybind}");
+    }
+
+    info
+}
+
+/// Sanitizes a log file. Lines written in the JSON format are parsed and
+/// written back so that escaped paths in messages are redacted as well.
+fn sanitize_log(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| match serde_json::from_str::<LogRecord>(line) {
+            Ok(mut record) => {
+                record.message = sanitize(&record.message);
+                serde_json::to_string(&record).unwrap_or_else(|_| sanitize(line))
+            }
+            Err(_) => sanitize(line),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Returns all crash reports in the crash report directory.
+fn crash_reports() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(app::crash_reports_path()) else {
+        return Vec::new();
+    };
+
+    let mut reports = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "crash")
+        })
+        .collect::<Vec<PathBuf>>();
+
+    reports.sort();
+    reports
+}
+
+/// Reads a file, returning `None` instead of an error if it does not exist.
+fn read_if_exists(path: &Path) -> std::io::Result<Option<String>> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(String::from_utf8_lossy(&content).into_owned())),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -1,7 +1,9 @@
 pub use std::error::Error;
 
-use crate::ui::taskdialog::{self, TaskDialog};
-use crate::{app, logf};
+use crate::notification::Severity;
+use crate::ui::dialogs::{Dialog, Dialogs};
+use crate::ui::taskdialog::TaskDialogs;
+use crate::{crash, cursor, logf, logger, tr};
 
 /// Represents a human-readable error including an optional error code (e.g. system error code)
 #[derive(Debug)]
@@ -40,7 +42,6 @@ pub type AppResult<T> = Result<T, AppError>;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::UI::WindowsAndMessaging::{MessageBoxA, MB_ICONERROR, MB_OK};
 
-use chrono::Local;
 use windows::core::PCSTR;
 
 /// # Panics
@@ -66,41 +67,37 @@ pub fn set_panic_hook() {
     std::panic::set_hook(Box::new(on_panic));
 }
 
-fn on_panic(info: &std::panic::PanicInfo) {
-    let message: String;
-
-    let date = Local::now().format("%Y-%m-%d");
-    let timestamp = Local::now().format("%H:%M:%S.%f");
-
-    let backtrace_directory = crate::app::make_rel_path_abs("logs");
-    let backtrace_filepath =
-        app::make_rel_path_abs(format!("logs/{date}-{timestamp}.backtrace").as_str());
-
-    std::fs::create_dir_all(backtrace_directory).unwrap();
-
-    match info.payload().downcast_ref::<String>() {
-        Some(panic_string) => {
-            message = format!("Unexpected error: {panic_string}.\n\nPanic Information:\n\n{info:#?}\n\nBacktrace saved to '{backtrace_filepath}'.\n\nSorry. :(");
-        }
-
-        None => match info.payload().downcast_ref::<&str>() {
-            Some(panic_str) => {
-                message = format!("Unexpected error: {panic_str}.\n\nPanic Information:\n\n{info:#?}\n\nBacktrace saved to '{backtrace_filepath}'.\n\nSorry. :(");
-            }
-
-            None => {
-                message = format!("An unexpected error ocurred but no additional error information was supplied.\n\nPanic Information:\n\n{info:#?}\n\nBacktrace saved to '{backtrace_filepath}'.\n\nSorry. :(");
-            }
-        },
-    }
-
-    logf!("PANIC: {}", message);
-
-    TaskDialog::new()
-        .set_title("Panic! in xterminate")
-        .set_heading("An unexpected error occurred in xterminate")
-        .set_content(message)
-        .set_icon(taskdialog::TaskDialogIcon::ErrorIcon)
-        .display()
-        .result();
+fn on_panic(info: &std::panic::PanicHookInfo) {
+    // Don't leave the user stuck with the termination mode cursor
+    cursor::restore_if_swapped();
+
+    // Make sure the crash report includes everything logged before the panic
+    logger::flush();
+
+    let report = match crash::write_report(info) {
+        Ok(path) => tr!("dialog.panic.report_saved", path = path.display()),
+        Err(error) => tr!("dialog.panic.report_failed", error = error),
+    };
+
+    let message = tr!(
+        "dialog.panic.content",
+        error = crash::panic_message(info),
+        report = report
+    );
+    let details = tr!("dialog.panic.details", info = format!("{info:#?}"));
+
+    logf!("PANIC: {}\n\n{}", message, details);
+    logger::flush();
+
+    // The panic hook cannot be handed the dialogs of the app, so task dialogs are used directly
+    TaskDialogs.message(
+        &Dialog::new(
+            Severity::Error,
+            tr!("dialog.panic.title"),
+            tr!("dialog.panic.heading"),
+            message,
+        )
+        .with_details(details),
+        true,
+    );
 }
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+
+use crate::process::{LaunchSpec, TerminationOutcome};
+
+/// The amount of terminated processes remembered by [`History`].
+pub const HISTORY_CAPACITY: usize = 10;
+
+/// Entry ids are reused after this many entries, which keeps them small
+/// enough to be encoded into tray menu command ids.
+pub const HISTORY_ID_LIMIT: u16 = 4096;
+
+/// A single process terminated by xterminate.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// Identifies the entry for as long as it is in the [`History`]
+    pub id: u16,
+    pub time: DateTime<Local>,
+    pub name: String,
+    pub pid: u32,
+
+    /// The path of the executable, or an empty string if it is unknown
+    pub path: String,
+    pub outcome: TerminationOutcome,
+
+    /// Information required to relaunch the process, or `None` if
+    /// it could not be captured before the process was terminated.
+    pub launch: Option<LaunchSpec>,
+}
+
+impl HistoryEntry {
+    #[must_use]
+    pub fn new(
+        name: String,
+        pid: u32,
+        path: String,
+        outcome: TerminationOutcome,
+        launch: Option<LaunchSpec>,
+    ) -> Self {
+        Self {
+            id: 0,
+            time: Local::now(),
+            name,
+            pid,
+            path,
+            outcome,
+            launch,
+        }
+    }
+}
+
+/// In-memory record of the most recently terminated processes.
+/// Once the capacity is reached, the oldest entries are discarded.
+pub struct History {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+    next_id: u16,
+}
+
+impl History {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            next_id: 0,
+        }
+    }
+
+    /// Adds a terminated process to the history, discarding the oldest
+    /// entry if the history is full, and assigns the entry its id.
+    pub fn push(&mut self, mut entry: HistoryEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        entry.id = self.next_id;
+        self.next_id = (self.next_id + 1) % HISTORY_ID_LIMIT;
+
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_back();
+        }
+
+        self.entries.push_front(entry);
+    }
+
+    /// Returns an iterator over the history, starting with the most recent entry.
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the entry with the specified id, if it is still in the history.
+    #[must_use]
+    pub fn get(&self, id: u16) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Returns the most recent entry that can be relaunched, if any.
+    #[must_use]
+    pub fn last_relaunchable(&self) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|entry| entry.launch.is_some())
+    }
+}
@@ -216,7 +216,7 @@ unsafe extern "system" fn raw_input_callback(
 ) -> LRESULT {
     match msg {
         WM_CREATE => {
-            logf!("Recieved WM_CREATE message");
+            logf!(Debug: "Recieved WM_CREATE message");
 
             register_raw_input_devices(hwnd);
 
@@ -224,7 +224,7 @@ unsafe extern "system" fn raw_input_callback(
         }
 
         WM_DESTROY => {
-            logf!("Recieved WM_DESTROY message");
+            logf!(Debug: "Recieved WM_DESTROY message");
 
             unregister_raw_input_devices();
 
@@ -370,7 +370,7 @@ fn process_raw_input_event(hwnd: HWND, msg: u32, lparam: LPARAM, wparam: WPARAM)
             );
             if processed {
                 logf!(
-                    "Processed and consumed relevant input: ({}, {})",
+                    Trace: "Processed and consumed relevant input: ({}, {})",
                     keycode,
                     keystatus
                 );
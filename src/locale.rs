@@ -0,0 +1,427 @@
+//! Translations of the text xterminate shows to the user. Messages are looked
+//! up by key in the per-language files bundled from `res/locales`, falling
+//! back to English for keys a translation is missing. Use [`tr!`](crate::tr)
+//! for plain messages and [`trn!`](crate::trn) for messages with a count.
+//!
+//! Messages may contain `{name}` placeholders, which are replaced by the
+//! arguments of the same name. A message with a count is a table of plural
+//! forms, e.g. `{ one = "{count} process", other = "{count} processes" }`.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{PoisonError, RwLock};
+
+use crate::config::Locale;
+use crate::logf;
+
+/// The language used for missing translations, which must contain every key.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// The bundled locale files and the language codes they are used for.
+pub const LOCALES: [(&str, &str); 2] = [
+    ("en", include_str!("..\\res\\locales\\en.toml")),
+    ("de", include_str!("..\\res\\locales\\de.toml")),
+];
+
+/// The plural categories a message with a count may have. Only the forms
+/// needed by the language of a locale file have to be present.
+const PLURAL_CATEGORIES: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// The messages of the current language, or `None` until the first message
+/// is looked up or [`configure`] is called.
+static CATALOG: RwLock<Option<Catalog>> = RwLock::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+
+    /// Forms keyed by plural category, which always include `other`
+    Plural(HashMap<String, String>),
+}
+
+/// The messages of one language, keyed by their dotted path in the locale
+/// file, e.g. `tray.menu.exit` for the `exit` key of the `[tray.menu]` table.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    language: String,
+    messages: HashMap<String, Message>,
+}
+
+impl Catalog {
+    /// Loads the bundled messages of `language`, with any missing ones
+    /// taken from [`FALLBACK_LANGUAGE`]. Returns `None` if no locale file
+    /// is bundled for `language`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bundled locale file cannot be parsed.
+    #[must_use]
+    pub fn load(language: &str) -> Option<Self> {
+        let source = bundled(language)?;
+
+        let mut messages = parse(bundled(FALLBACK_LANGUAGE).unwrap())
+            .expect("failed to parse fallback locale file");
+
+        if language != FALLBACK_LANGUAGE {
+            messages.extend(parse(source).expect("failed to parse bundled locale file"));
+        }
+
+        Some(Self {
+            language: language.to_owned(),
+            messages,
+        })
+    }
+
+    #[must_use]
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the message with the specified key, with `{name}` placeholders
+    /// replaced by `args`. Unknown keys are returned as they are.
+    #[must_use]
+    pub fn text(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        match self.messages.get(key) {
+            Some(Message::Text(text)) => interpolate(text, args),
+            Some(Message::Plural(forms)) => interpolate(&forms["other"], args),
+            None => {
+                logf!("WARNING: missing message '{key}'");
+                key.to_owned()
+            }
+        }
+    }
+
+    /// Like [`Catalog::text`], but picks the plural form for `count`,
+    /// which also replaces the `{count}` placeholder.
+    #[must_use]
+    pub fn plural(&self, key: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+        let mut args = args.to_vec();
+        args.push(("count", &count));
+
+        match self.messages.get(key) {
+            Some(Message::Plural(forms)) => {
+                let category = plural_category(&self.language, count);
+                let form = forms.get(category).unwrap_or(&forms["other"]);
+
+                interpolate(form, &args)
+            }
+            _ => self.text(key, &args),
+        }
+    }
+}
+
+/// Returns the contents of the bundled locale file for `language`.
+#[must_use]
+pub fn bundled(language: &str) -> Option<&'static str> {
+    LOCALES
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map(|(_, source)| *source)
+}
+
+/// Parses a locale file into its messages, keyed by their dotted path.
+///
+/// # Errors
+///
+/// Returns an error if the file is not valid TOML or contains a value that is
+/// neither a string nor a table.
+pub fn parse(source: &str) -> Result<HashMap<String, Message>, String> {
+    let table = toml::from_str::<toml::value::Table>(source).map_err(|error| error.to_string())?;
+
+    let mut messages = HashMap::new();
+    flatten("", &table, &mut messages)?;
+
+    Ok(messages)
+}
+
+fn flatten(
+    prefix: &str,
+    table: &toml::value::Table,
+    messages: &mut HashMap<String, Message>,
+) -> Result<(), String> {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match value {
+            toml::Value::String(text) => {
+                messages.insert(key, Message::Text(text.clone()));
+            }
+
+            toml::Value::Table(table) if table.contains_key("other") => {
+                let mut forms = HashMap::new();
+
+                for (category, form) in table {
+                    let form = form
+                        .as_str()
+                        .filter(|_| PLURAL_CATEGORIES.contains(&category.as_str()))
+                        .ok_or_else(|| format!("{key}.{category}: invalid plural form"))?;
+
+                    forms.insert(category.clone(), form.to_owned());
+                }
+
+                messages.insert(key, Message::Plural(forms));
+            }
+
+            toml::Value::Table(table) => flatten(&key, table, messages)?,
+
+            _ => return Err(format!("{key}: messages must be strings or tables")),
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the `{name}` placeholders in `text` by the values in `args`.
+/// Placeholders without an argument are left as they are.
+fn interpolate(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = text.to_owned();
+
+    for (name, value) in args {
+        result = result.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+
+    result
+}
+
+/// Returns the plural category of `count` in `language`, see the
+/// [CLDR plural rules](https://cldr.unicode.org/index/cldr-spec/plural-rules).
+fn plural_category(language: &str, count: usize) -> &'static str {
+    match (language, count) {
+        // French uses the singular for 0 as well. Most other languages,
+        // including English and German, only use it for 1.
+        ("fr", 0) | (_, 1) => "one",
+        _ => "other",
+    }
+}
+
+/// Picks the language from the `[locale]` section of the config, or the
+/// language of the Windows user interface if none is configured, and uses
+/// it for all messages from now on. Languages without a bundled locale file
+/// fall back to English.
+///
+/// Problems with the config are logged as warnings and returned.
+#[must_use]
+pub fn configure(locale: &Locale) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let catalog = match locale.language.as_deref() {
+        Some(language) => load(language).unwrap_or_else(|| {
+            warnings.push(format!(
+                "locale.language: no translation available for '{language}'"
+            ));
+
+            fallback()
+        }),
+        None => system_catalog(),
+    };
+
+    logf!("Using language '{}'", catalog.language());
+
+    for warning in &warnings {
+        logf!("WARNING: {warning}");
+    }
+
+    *CATALOG.write().unwrap_or_else(PoisonError::into_inner) = Some(catalog);
+
+    warnings
+}
+
+/// Loads the catalog for a language tag such as `de-AT` or `de`.
+fn load(tag: &str) -> Option<Catalog> {
+    let language = tag
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    Catalog::load(&language)
+}
+
+fn fallback() -> Catalog {
+    Catalog::load(FALLBACK_LANGUAGE).unwrap()
+}
+
+fn system_catalog() -> Catalog {
+    system_language()
+        .and_then(|tag| load(&tag))
+        .unwrap_or_else(fallback)
+}
+
+/// Returns the language tag of the Windows user interface, e.g. `en-US`.
+fn system_language() -> Option<String> {
+    use windows::Win32::Globalization::{GetUserDefaultUILanguage, LCIDToLocaleName};
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut name = [0u16; 85];
+
+    let length =
+        unsafe { LCIDToLocaleName(u32::from(GetUserDefaultUILanguage()), Some(&mut name), 0) };
+
+    // The length includes the terminating nul
+    let length = usize::try_from(length).ok().filter(|length| *length > 1)?;
+
+    Some(String::from_utf16_lossy(&name[..length - 1]))
+}
+
+/// Calls `f` with the current catalog, picking the language of the Windows
+/// user interface if [`configure`] has not been called yet.
+fn with_catalog<T>(f: impl FnOnce(&Catalog) -> T) -> T {
+    if let Some(catalog) = CATALOG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        return f(catalog);
+    }
+
+    // Loaded before locking, so that a panic while loading can still show translated messages
+    let catalog = system_catalog();
+
+    f(CATALOG
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert(catalog))
+}
+
+/// Returns the message with the specified key in the current language.
+/// Prefer the [`tr!`](crate::tr) macro.
+#[must_use]
+pub fn text(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    with_catalog(|catalog| catalog.text(key, args))
+}
+
+/// Returns the plural form of the message with the specified key for `count`
+/// in the current language. Prefer the [`trn!`](crate::trn) macro.
+#[must_use]
+pub fn plural(key: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+    with_catalog(|catalog| catalog.plural(key, count, args))
+}
+
+/// Translated message macro
+///
+/// Looks up a message in the current language, replacing its placeholders
+/// by the named arguments, e.g. `tr!("notification.protected", name = name)`.
+#[macro_export]
+macro_rules! tr {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::locale::text(
+            $key,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+/// Translated plural message macro
+///
+/// Like [`tr!`], but picks the plural form for the count given after the key,
+/// which also replaces `{count}`, e.g. `trn!("action.terminated_tree", count, name = name)`.
+#[macro_export]
+macro_rules! trn {
+    ($key:expr, $count:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::locale::plural(
+            $key,
+            $count,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Returns the names of the `{name}` placeholders in `text`.
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}'))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn every_locale_has_the_keys_of_the_fallback() {
+        let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("res")
+            .join("locales");
+
+        let fallback = parse(bundled(FALLBACK_LANGUAGE).unwrap()).unwrap();
+        let expected: BTreeSet<&String> = fallback.keys().collect();
+
+        for file in std::fs::read_dir(directory).unwrap() {
+            let path = file.unwrap().path();
+            let language = path.file_stem().unwrap().to_str().unwrap().to_owned();
+
+            let source = std::fs::read_to_string(&path).unwrap();
+            let messages = parse(&source).unwrap_or_else(|error| panic!("{language}: {error}"));
+
+            assert!(
+                bundled(&language).is_some(),
+                "{language} is not listed in LOCALES"
+            );
+
+            let keys: BTreeSet<&String> = messages.keys().collect();
+
+            assert_eq!(
+                keys.difference(&expected).collect::<Vec<_>>(),
+                Vec::<&&String>::new(),
+                "{language} has keys that {FALLBACK_LANGUAGE} does not have"
+            );
+            assert_eq!(
+                expected.difference(&keys).collect::<Vec<_>>(),
+                Vec::<&&String>::new(),
+                "{language} is missing keys"
+            );
+
+            for (key, message) in &messages {
+                let (Message::Text(text), Message::Text(fallback_text)) = (message, &fallback[key])
+                else {
+                    assert!(
+                        matches!(
+                            (message, &fallback[key]),
+                            (Message::Plural(_), Message::Plural(_))
+                        ),
+                        "{language}: {key} must be plural in every locale or in none"
+                    );
+                    continue;
+                };
+
+                assert_eq!(
+                    placeholders(text),
+                    placeholders(fallback_text),
+                    "{language}: {key} has different placeholders"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn interpolates_placeholders() {
+        assert_eq!(
+            interpolate(
+                "{name} (pid {pid}) {unknown}",
+                &[("name", &"a.exe"), ("pid", &7)]
+            ),
+            "a.exe (pid 7) {unknown}"
+        );
+    }
+
+    #[test]
+    fn picks_plural_forms() {
+        let catalog = Catalog::load("en").unwrap();
+
+        assert_eq!(
+            catalog.plural("action.terminated_tree", 1, &[("name", &"a.exe")]),
+            "Terminated a.exe and 1 child process"
+        );
+        assert_eq!(
+            catalog.plural("action.terminated_tree", 2, &[("name", &"a.exe")]),
+            "Terminated a.exe and 2 child processes"
+        );
+    }
+}
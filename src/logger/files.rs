@@ -0,0 +1,442 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+const LOG_EXTENSION: &str = "log";
+
+/// Identifies a log file by the day it was written on and its index among
+/// the files of that day. The first file of a day is named `2024-05-01.log`,
+/// files rotated to after it reached the size cap `2024-05-01.1.log` and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LogFileName {
+    pub date: NaiveDate,
+    pub index: u32,
+}
+
+impl LogFileName {
+    /// Parses the name of a log file, returning `None` if `path` is not a log file.
+    #[must_use]
+    pub fn parse(path: &Path) -> Option<Self> {
+        if path.extension()? != LOG_EXTENSION {
+            return None;
+        }
+
+        let stem = path.file_stem()?.to_str()?;
+
+        let (date, index) = match stem.split_once('.') {
+            Some((date, index)) => (date, index.parse().ok()?),
+            None => (stem, 0),
+        };
+
+        Some(Self {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+            index,
+        })
+    }
+
+    #[must_use]
+    pub fn file_name(&self) -> String {
+        if self.index == 0 {
+            format!("{}.{LOG_EXTENSION}", self.date.format("%Y-%m-%d"))
+        } else {
+            format!(
+                "{}.{}.{LOG_EXTENSION}",
+                self.date.format("%Y-%m-%d"),
+                self.index
+            )
+        }
+    }
+}
+
+/// Limits on the log files kept in the log directory. Limits set to 0 are disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Retention {
+    pub max_age_days: u64,
+    pub max_files: usize,
+    pub max_total_bytes: u64,
+}
+
+/// Returns all log files in `directory`, oldest first. Files that
+/// are not log files are ignored.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read.
+pub fn log_files(directory: &Path) -> std::io::Result<Vec<(LogFileName, PathBuf)>> {
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+
+        if let Some(name) = LogFileName::parse(&path) {
+            files.push((name, path));
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Returns the most recent log file in `directory`, if any.
+#[must_use]
+pub fn latest(directory: &Path) -> Option<PathBuf> {
+    log_files(directory).ok()?.pop().map(|(_, path)| path)
+}
+
+/// Deletes the log files in `directory` that exceed the limits of `retention`,
+/// starting with the oldest, and returns the deleted files. The most recent log
+/// file is always kept since it may still be written to. Files that are not log
+/// files are neither deleted nor counted towards the limits.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read or a log file cannot be deleted.
+pub fn prune(
+    directory: &Path,
+    retention: &Retention,
+    today: NaiveDate,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = log_files(directory)?;
+
+    let Some((_, newest)) = files.pop() else {
+        return Ok(Vec::new());
+    };
+
+    let mut kept_files = 1;
+    let mut kept_bytes = file_size(&newest);
+
+    // Once a file exceeds the count or size limits, all older files do as well
+    let mut exhausted = false;
+
+    let mut deleted = Vec::new();
+
+    for (name, path) in files.into_iter().rev() {
+        let size = file_size(&path);
+
+        let expired = retention.max_age_days > 0
+            && name
+                .date
+                .checked_add_days(chrono::Days::new(retention.max_age_days))
+                .is_some_and(|expiry| expiry < today);
+
+        exhausted = exhausted
+            || (retention.max_files > 0 && kept_files >= retention.max_files)
+            || (retention.max_total_bytes > 0 && kept_bytes + size > retention.max_total_bytes);
+
+        if expired || exhausted {
+            std::fs::remove_file(&path)?;
+            deleted.push(path);
+        } else {
+            kept_files += 1;
+            kept_bytes += size;
+        }
+    }
+
+    Ok(deleted)
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |metadata| metadata.len())
+}
+
+struct OpenLogFile {
+    name: LogFileName,
+    size: u64,
+    writer: BufWriter<File>,
+}
+
+/// Appends lines to the log file of the day they were logged on, moving on
+/// to the day's next file once the current one would exceed the size cap.
+pub struct RotatingFile {
+    directory: PathBuf,
+
+    /// The size a log file may grow to before rotating, or 0 for no limit
+    max_bytes: u64,
+    current: Option<OpenLogFile>,
+}
+
+impl RotatingFile {
+    #[must_use]
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            max_bytes: 0,
+            current: None,
+        }
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: u64) {
+        self.max_bytes = max_bytes;
+    }
+
+    /// Appends a line to the log file of `date`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log file cannot be opened or written to.
+    pub fn write_line(&mut self, date: NaiveDate, line: &str) -> std::io::Result<()> {
+        let length = line.len() as u64 + 1;
+
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|current| current.name.date != date)
+        {
+            self.flush()?;
+            self.current = None;
+
+            // Continue with the most recent file of the day
+            // in case xterminate was restarted mid-day
+            self.current = Some(self.open(self.latest_of(date))?);
+        }
+
+        let name = self.current.as_ref().unwrap().name;
+
+        if self.is_full(self.current.as_ref().unwrap(), length) {
+            self.flush()?;
+            self.current = None;
+
+            self.current = Some(self.open(LogFileName {
+                date,
+                index: name.index + 1,
+            })?);
+        }
+
+        let current = self.current.as_mut().unwrap();
+
+        writeln!(current.writer, "{line}")?;
+        current.size += length;
+
+        Ok(())
+    }
+
+    /// Writes all buffered lines to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log file cannot be written to.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.current {
+            Some(current) => current.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if writing `length` more bytes would make the file exceed the size cap.
+    /// Empty files are never full, so lines longer than the cap are still written.
+    fn is_full(&self, file: &OpenLogFile, length: u64) -> bool {
+        self.max_bytes > 0 && file.size > 0 && file.size + length > self.max_bytes
+    }
+
+    fn latest_of(&self, date: NaiveDate) -> LogFileName {
+        log_files(&self.directory)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, _)| name)
+            .rfind(|name| name.date == date)
+            .unwrap_or(LogFileName { date, index: 0 })
+    }
+
+    fn open(&self, name: LogFileName) -> std::io::Result<OpenLogFile> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        let file = std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.directory.join(name.file_name()))?;
+
+        Ok(OpenLogFile {
+            name,
+            size: file.metadata()?.len(),
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn names(directory: &Path) -> Vec<String> {
+        log_files(directory)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name.file_name())
+            .collect()
+    }
+
+    fn create(directory: &Path, name: &str, size: usize) {
+        std::fs::write(directory.join(name), "x".repeat(size)).unwrap();
+    }
+
+    #[test]
+    fn parses_file_names() {
+        let name = LogFileName::parse(Path::new("2024-05-01.2.log")).unwrap();
+        assert_eq!(
+            name,
+            LogFileName {
+                date: date(1),
+                index: 2
+            }
+        );
+        assert_eq!(name.file_name(), "2024-05-01.2.log");
+
+        let name = LogFileName::parse(Path::new("2024-05-01.log")).unwrap();
+        assert_eq!(name.file_name(), "2024-05-01.log");
+
+        assert_eq!(LogFileName::parse(Path::new("2024-05-01.txt")), None);
+        assert_eq!(LogFileName::parse(Path::new("notes.log")), None);
+        assert_eq!(LogFileName::parse(Path::new("2024-05-01.x.log")), None);
+    }
+
+    #[test]
+    fn rotates_when_file_is_full() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::new(directory.path().to_owned());
+        file.set_max_bytes(10);
+
+        // Each line takes up 5 bytes including the line break
+        for _ in 0..5 {
+            file.write_line(date(1), "line").unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-01.log", "2024-05-01.1.log", "2024-05-01.2.log"]
+        );
+
+        let content = std::fs::read_to_string(directory.path().join("2024-05-01.log")).unwrap();
+        assert_eq!(content, "line\nline\n");
+    }
+
+    #[test]
+    fn writes_long_lines_to_empty_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::new(directory.path().to_owned());
+        file.set_max_bytes(4);
+
+        file.write_line(date(1), "a long line").unwrap();
+        file.write_line(date(1), "another long line").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-01.log", "2024-05-01.1.log"]
+        );
+    }
+
+    #[test]
+    fn rolls_over_to_new_date() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::new(directory.path().to_owned());
+
+        file.write_line(date(1), "first").unwrap();
+        file.write_line(date(2), "second").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-01.log", "2024-05-02.log"]
+        );
+
+        let content = std::fs::read_to_string(directory.path().join("2024-05-02.log")).unwrap();
+        assert_eq!(content, "second\n");
+    }
+
+    #[test]
+    fn continues_latest_file_of_the_day() {
+        let directory = tempfile::tempdir().unwrap();
+        create(directory.path(), "2024-05-01.log", 8);
+        create(directory.path(), "2024-05-01.1.log", 2);
+
+        let mut file = RotatingFile::new(directory.path().to_owned());
+        file.set_max_bytes(10);
+
+        file.write_line(date(1), "abc").unwrap();
+        file.write_line(date(1), "abcdef").unwrap();
+        file.flush().unwrap();
+
+        let content = std::fs::read_to_string(directory.path().join("2024-05-01.1.log")).unwrap();
+        assert_eq!(content, "xxabc\n");
+
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-01.log", "2024-05-01.1.log", "2024-05-01.2.log"]
+        );
+    }
+
+    #[test]
+    fn prunes_expired_files() {
+        let directory = tempfile::tempdir().unwrap();
+        create(directory.path(), "2024-05-01.log", 1);
+        create(directory.path(), "2024-05-02.log", 1);
+        create(directory.path(), "2024-05-03.log", 1);
+        create(directory.path(), "notes.txt", 1);
+
+        let retention = Retention {
+            max_age_days: 1,
+            ..Retention::default()
+        };
+        let deleted = prune(directory.path(), &retention, date(3)).unwrap();
+
+        assert_eq!(deleted, [directory.path().join("2024-05-01.log")]);
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-02.log", "2024-05-03.log"]
+        );
+        assert!(directory.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn prunes_files_exceeding_count_and_size() {
+        let directory = tempfile::tempdir().unwrap();
+        create(directory.path(), "2024-05-01.log", 10);
+        create(directory.path(), "2024-05-02.log", 10);
+        create(directory.path(), "2024-05-02.1.log", 10);
+        create(directory.path(), "2024-05-03.log", 10);
+
+        let retention = Retention {
+            max_files: 3,
+            ..Retention::default()
+        };
+        prune(directory.path(), &retention, date(3)).unwrap();
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-02.log", "2024-05-02.1.log", "2024-05-03.log"]
+        );
+
+        let retention = Retention {
+            max_total_bytes: 25,
+            ..Retention::default()
+        };
+        prune(directory.path(), &retention, date(3)).unwrap();
+        assert_eq!(
+            names(directory.path()),
+            ["2024-05-02.1.log", "2024-05-03.log"]
+        );
+    }
+
+    #[test]
+    fn always_keeps_newest_file() {
+        let directory = tempfile::tempdir().unwrap();
+        create(directory.path(), "2024-05-01.log", 100);
+
+        let retention = Retention {
+            max_age_days: 1,
+            max_files: 1,
+            max_total_bytes: 1,
+        };
+        let deleted = prune(directory.path(), &retention, date(30)).unwrap();
+
+        assert!(deleted.is_empty());
+        assert_eq!(names(directory.path()), ["2024-05-01.log"]);
+    }
+}
@@ -0,0 +1,814 @@
+extern crate chrono;
+
+use chrono::{Local, NaiveDate};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{OnceLock, PoisonError, RwLock};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use crate::config::Logging;
+
+mod files;
+mod record;
+
+pub use files::{LogFileName, Retention, RotatingFile};
+pub use record::{read_records, LogFormat, LogRecord};
+
+/// Environment variable that overrides the log filters in `config.toml`.
+/// Uses the same syntax as the `[logging]` section, e.g. `debug,reqwest=info`.
+pub const FILTER_ENV_VAR: &str = "XTERMINATE_LOG";
+
+/// The amount of messages that can be queued for the writer thread. Once
+/// the queue is full, messages are dropped rather than waiting for room.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// The amount of queued messages above which debug and trace messages are dropped.
+const VERBOSE_HIGH_WATER: usize = QUEUE_CAPACITY / 2;
+
+/// The room at the end of the queue that only warnings and errors may take,
+/// so that they are not lost when the writer falls behind on info messages.
+const RESERVED_CAPACITY: usize = 128;
+
+/// The maximum amount of messages written to disk at once.
+const BATCH_SIZE: usize = 256;
+
+/// How long [`flush`] waits for the writer thread before giving up.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Formatted logging macro
+///
+/// Messages are logged through the [`log`] facade at the info level, unless
+/// they start with `ERROR: ` or `WARNING: `, which are logged at the error
+/// and warn levels respectively. Prefix the arguments with a level to log
+/// at another level, e.g. `logf!(Debug: "Config: {config:#?}")`.
+#[macro_export]
+macro_rules! logf {
+    ($level:ident: $($arg:tt)*) => {
+        ::log::log!(::log::Level::$level, $($arg)*)
+    };
+    ($($arg:tt)*) => {
+        {
+            let message = format!("{}", format_args!($($arg)*));
+            let (level, message) = $crate::logger::split_level(&message);
+            ::log::log!(level, "{message}");
+        }
+    };
+}
+
+/// The minimum level a message must have to be logged, either globally
+/// or for specific modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    default: LevelFilter,
+
+    /// Module paths and their levels, which override the default level
+    /// for the module and all of its submodules
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    #[must_use]
+    pub const fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Sets the level of a module and all of its submodules, e.g. `reqwest`.
+    pub fn set_module(&mut self, module: &str, level: LevelFilter) {
+        self.modules.retain(|(existing, _)| existing != module);
+        self.modules.push((module.to_owned(), level));
+    }
+
+    /// Applies a comma-separated list of directives, which are either a level
+    /// that becomes the default level or `module=level` pairs. Directives that
+    /// appear later override earlier ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first directive that is not valid. Directives
+    /// before it have been applied by then.
+    pub fn parse_directives(&mut self, directives: &str) -> Result<(), String> {
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+
+            let invalid = || format!("invalid log filter directive '{directive}'");
+
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let level = LevelFilter::from_str(level.trim()).map_err(|_| invalid())?;
+                    self.set_module(module.trim(), level);
+                }
+
+                None => {
+                    self.default = LevelFilter::from_str(directive).map_err(|_| invalid())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the level that applies to messages logged from `target`,
+    /// which is the level of the most specific matching module.
+    #[must_use]
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// Returns the most verbose level of any module.
+    #[must_use]
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, std::cmp::max)
+    }
+}
+
+/// A message queued for the writer thread.
+enum Message {
+    Line {
+        date: NaiveDate,
+        level: Level,
+        text: String,
+    },
+
+    /// Sends a reply once all messages queued before it have been written
+    Flush(SyncSender<()>),
+}
+
+struct Logger {
+    filter: RwLock<Filter>,
+
+    /// Queues messages for the writer thread, set by [`install`]
+    sender: OnceLock<SyncSender<Message>>,
+}
+
+static LOGGER: Logger = Logger {
+    filter: RwLock::new(Filter::new(LevelFilter::Info)),
+    sender: OnceLock::new(),
+};
+
+/// The amount of messages dropped since the writer
+/// thread last wrote to the log file.
+static DROPPED_LINES: AtomicUsize = AtomicUsize::new(0);
+
+/// The amount of messages queued that the writer thread has not received yet.
+static QUEUED_LINES: AtomicUsize = AtomicUsize::new(0);
+
+/// The size a log file may grow to before the writer thread rotates to a new one.
+static MAX_FILE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Whether messages are written as [`LogFormat::Json`] rather than [`LogFormat::Text`].
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+
+static SESSION_ID: OnceLock<String> = OnceLock::new();
+
+/// The thread started by [`install`], which must never wait for itself in [`flush`].
+static WRITER_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let filter = self.filter.read().unwrap_or_else(PoisonError::into_inner);
+
+        metadata.level() <= filter.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        write(
+            record.level(),
+            &record.args().to_string(),
+            record.file().unwrap_or("<unknown>"),
+            record.line().unwrap_or(0),
+        );
+    }
+
+    fn flush(&self) {
+        flush();
+    }
+}
+
+/// Installs xterminate's logger and starts the thread that writes messages
+/// to disk, filtering messages by [`FILTER_ENV_VAR`] until the config has
+/// been loaded. Messages logged before this are discarded.
+///
+/// # Panics
+///
+/// Panics if the writer thread cannot be spawned.
+pub fn install() {
+    let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+
+    if LOGGER.sender.set(sender).is_err() {
+        return;
+    }
+
+    let writer = std::thread::Builder::new()
+        .name(String::from("log writer"))
+        .spawn(move || run_writer(&receiver, PathBuf::from(crate::app::logfiles_path())))
+        .expect("failed to spawn log writer thread");
+
+    let _ = WRITER_THREAD.set(writer.thread().id());
+
+    if log::set_logger(&LOGGER).is_err() {
+        return;
+    }
+
+    let mut filter = Filter::new(LevelFilter::Info);
+
+    if let Ok(directives) = std::env::var(FILTER_ENV_VAR) {
+        let _ = filter.parse_directives(&directives);
+    }
+
+    set_filter(filter);
+}
+
+/// Applies the `[logging]` section of the config and deletes log files that
+/// exceed its retention limits. The filters from [`FILTER_ENV_VAR`] are applied
+/// after the ones from the config, so they take precedence.
+///
+/// Problems with the config are logged as warnings and returned.
+#[must_use]
+pub fn configure(logging: &Logging) -> Vec<String> {
+    let mut filter = Filter::new(LevelFilter::Info);
+    let mut errors = Vec::new();
+
+    if let Err(error) = filter.parse_directives(&logging.level) {
+        errors.push(error);
+    }
+
+    for (module, level) in &logging.modules {
+        match LevelFilter::from_str(level) {
+            Ok(level) => filter.set_module(module, level),
+            Err(_) => errors.push(format!("invalid log level '{level}' for module '{module}'")),
+        }
+    }
+
+    if let Ok(directives) = std::env::var(FILTER_ENV_VAR) {
+        if let Err(error) = filter.parse_directives(&directives) {
+            errors.push(format!("{error} in {FILTER_ENV_VAR}"));
+        }
+    }
+
+    set_filter(filter);
+
+    JSON_FORMAT.store(logging.format == LogFormat::Json, Ordering::Relaxed);
+
+    MAX_FILE_BYTES.store(
+        logging.max_file_size_mb.saturating_mul(1024 * 1024),
+        Ordering::Relaxed,
+    );
+
+    let retention = Retention {
+        max_age_days: logging.retention_days,
+        max_files: logging.max_files,
+        max_total_bytes: logging.max_total_size_mb.saturating_mul(1024 * 1024),
+    };
+
+    match files::prune(
+        Path::new(&crate::app::logfiles_path()),
+        &retention,
+        Local::now().date_naive(),
+    ) {
+        Ok(deleted) if !deleted.is_empty() => {
+            logf!(
+                "Deleted {} log files exceeding the retention limits",
+                deleted.len()
+            );
+        }
+        Ok(_) => {}
+        Err(error) => errors.push(format!("failed to delete old log files: {error}")),
+    }
+
+    for error in &errors {
+        logf!("WARNING: {error}");
+    }
+
+    errors
+}
+
+/// Returns the random id of this run of xterminate, which is included
+/// in every [`LogRecord`] to tell apart messages of different runs.
+#[must_use]
+pub fn session_id() -> &'static str {
+    SESSION_ID.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Returns the most recent log file, if any.
+#[must_use]
+pub fn latest_log_file() -> Option<PathBuf> {
+    files::latest(Path::new(&crate::app::logfiles_path()))
+}
+
+/// Returns up to `count` of the most recent log files, newest first.
+///
+/// # Errors
+///
+/// Returns an error if the log directory cannot be read.
+pub fn recent_log_files(count: usize) -> std::io::Result<Vec<PathBuf>> {
+    Ok(files::log_files(Path::new(&crate::app::logfiles_path()))?
+        .into_iter()
+        .rev()
+        .take(count)
+        .map(|(_, path)| path)
+        .collect())
+}
+
+fn set_filter(filter: Filter) {
+    log::set_max_level(filter.max_level());
+    *LOGGER
+        .filter
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = filter;
+}
+
+/// Determines the level of a [`logf!`] message from its `ERROR: ` or
+/// `WARNING: ` prefix and returns it along with the message without it.
+#[must_use]
+pub fn split_level(message: &str) -> (Level, &str) {
+    if let Some(message) = message.strip_prefix("ERROR: ") {
+        (Level::Error, message)
+    } else if let Some(message) = message.strip_prefix("WARNING: ") {
+        (Level::Warn, message)
+    } else if message.starts_with("PANIC: ") {
+        (Level::Error, message)
+    } else {
+        (Level::Info, message)
+    }
+}
+
+/// Formats a message and hands it to the writer thread. Messages are dropped,
+/// least severe first, if the writer thread has fallen behind, since they are
+/// logged from the input hook among others, which must never wait for the disk.
+fn write(level: Level, message: &str, file: &str, line: u32) {
+    let Some(sender) = LOGGER.sender.get() else {
+        return;
+    };
+
+    enqueue(
+        sender,
+        line_message(level, message, file, line),
+        &QUEUED_LINES,
+        &DROPPED_LINES,
+    );
+}
+
+/// Formats a message as a line of the log file, in the configured [`LogFormat`].
+fn line_message(level: Level, message: &str, file: &str, line: u32) -> Message {
+    let record = LogRecord {
+        timestamp: Local::now(),
+        level: level.to_string(),
+        file: file.to_owned(),
+        line,
+        message: message.to_owned(),
+        session: session_id().to_owned(),
+    };
+
+    Message::Line {
+        date: record.timestamp.date_naive(),
+        level,
+        text: format_record(&record, JSON_FORMAT.load(Ordering::Relaxed)),
+    }
+}
+
+/// Formats a record as a line in the [`LogFormat::Json`] or [`LogFormat::Text`] format.
+fn format_record(record: &LogRecord, json: bool) -> String {
+    if json {
+        serde_json::to_string(record).unwrap_or_else(|_| record.to_text())
+    } else {
+        record.to_text()
+    }
+}
+
+/// Returns the record written in place of `dropped` messages that were dropped.
+fn dropped_notice(dropped: usize) -> LogRecord {
+    LogRecord {
+        timestamp: Local::now(),
+        level: Level::Warn.to_string(),
+        file: file!().to_owned(),
+        line: line!(),
+        message: format!("{dropped} messages were dropped because logging fell behind"),
+        session: session_id().to_owned(),
+    }
+}
+
+/// Returns how many messages may be queued, including this one, for a message
+/// of `level` to still be queued. Less severe messages are dropped first.
+fn queue_limit(level: Level) -> usize {
+    match level {
+        Level::Error | Level::Warn => QUEUE_CAPACITY,
+        Level::Info => QUEUE_CAPACITY - RESERVED_CAPACITY,
+        Level::Debug | Level::Trace => VERBOSE_HIGH_WATER,
+    }
+}
+
+/// Queues a message without blocking, counting it in `queued` until the writer
+/// receives it. The message is dropped and counted in `dropped` instead if the
+/// queue is full, or too full for the level of the message, see [`queue_limit`].
+fn enqueue(
+    sender: &SyncSender<Message>,
+    message: Message,
+    queued: &AtomicUsize,
+    dropped: &AtomicUsize,
+) {
+    let limit = match &message {
+        Message::Line { level, .. } => queue_limit(*level),
+        Message::Flush(_) => QUEUE_CAPACITY,
+    };
+
+    if queued.fetch_add(1, Ordering::Relaxed) >= limit {
+        queued.fetch_sub(1, Ordering::Relaxed);
+        dropped.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    if let Err(TrySendError::Full(_)) = sender.try_send(message) {
+        queued.fetch_sub(1, Ordering::Relaxed);
+        dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Waits until all messages logged so far have been written to disk, or
+/// until [`FLUSH_TIMEOUT`] has passed. Called on shutdown and on panic.
+///
+/// Does nothing when called on the writer thread, e.g. by the panic hook
+/// after the writer panicked, since it would wait for itself.
+pub fn flush() {
+    let Some(sender) = LOGGER.sender.get() else {
+        return;
+    };
+
+    if WRITER_THREAD.get() == Some(&std::thread::current().id()) {
+        return;
+    }
+
+    flush_within(sender, FLUSH_TIMEOUT);
+}
+
+/// Asks the writer thread to flush and waits for it to be done for at most
+/// `timeout`, including the time spent waiting for room in a full queue.
+/// Returns true if the writer flushed in time.
+fn flush_within(sender: &SyncSender<Message>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let (done_sender, done_receiver) = std::sync::mpsc::sync_channel(1);
+
+    let mut message = Message::Flush(done_sender);
+
+    loop {
+        match sender.try_send(message) {
+            Ok(()) => break,
+            Err(TrySendError::Disconnected(_)) => return false,
+            Err(TrySendError::Full(returned)) => {
+                if Instant::now() >= deadline {
+                    return false;
+                }
+
+                message = returned;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    done_receiver
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .is_ok()
+}
+
+/// Runs on the writer thread, writing messages to the console and to the
+/// log file of the day they were logged on in batches of up to [`BATCH_SIZE`].
+fn run_writer(receiver: &Receiver<Message>, directory: PathBuf) {
+    let mut log_file = RotatingFile::new(directory);
+
+    // Block until a message arrives, then write everything
+    // else that has been queued up in the meantime with it.
+    while let Ok(first) = receiver.recv() {
+        log_file.set_max_bytes(MAX_FILE_BYTES.load(Ordering::Relaxed));
+
+        let mut flushes = Vec::new();
+
+        for message in std::iter::once(first).chain(receiver.try_iter().take(BATCH_SIZE - 1)) {
+            let (date, level, text) = match message {
+                Message::Line { date, level, text } => {
+                    QUEUED_LINES.fetch_sub(1, Ordering::Relaxed);
+                    (date, level, text)
+                }
+                Message::Flush(done) => {
+                    flushes.push(done);
+                    continue;
+                }
+            };
+
+            if level <= Level::Warn {
+                eprintln!("{text}");
+            } else {
+                println!("{text}");
+            }
+
+            let dropped = DROPPED_LINES.swap(0, Ordering::Relaxed);
+
+            if dropped > 0 {
+                let notice = format_record(
+                    &dropped_notice(dropped),
+                    JSON_FORMAT.load(Ordering::Relaxed),
+                );
+                let _ = log_file.write_line(date, &notice);
+            }
+
+            if let Err(error) = log_file.write_line(date, &text) {
+                eprintln!("failed to write to log file: {error}");
+            }
+        }
+
+        if let Err(error) = log_file.flush() {
+            eprintln!("failed to flush log file: {error}");
+        }
+
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(level: Level) -> Message {
+        Message::Line {
+            date: Local::now().date_naive(),
+            level,
+            text: String::from("message"),
+        }
+    }
+
+    #[test]
+    fn formats_dropped_notice_like_other_records() {
+        let notice = dropped_notice(3);
+
+        let json = format_record(&notice, true);
+        assert_eq!(serde_json::from_str::<LogRecord>(&json).unwrap(), notice);
+
+        let text = format_record(&notice, false);
+        assert!(text.contains("[WARN]"));
+        assert!(text.ends_with("3 messages were dropped because logging fell behind"));
+    }
+
+    #[test]
+    fn flush_gives_up_on_full_queue() {
+        let (sender, _receiver) = std::sync::mpsc::sync_channel(1);
+        sender.try_send(line(Level::Info)).unwrap();
+
+        let start = Instant::now();
+
+        assert!(!flush_within(&sender, Duration::from_millis(50)));
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn flush_waits_for_writer() {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+
+        std::thread::spawn(move || {
+            while let Ok(message) = receiver.recv() {
+                if let Message::Flush(done) = message {
+                    let _ = done.send(());
+                }
+            }
+        });
+
+        assert!(flush_within(&sender, FLUSH_TIMEOUT));
+
+        let (disconnected, _) = std::sync::mpsc::sync_channel(1);
+        assert!(!flush_within(&disconnected, FLUSH_TIMEOUT));
+    }
+
+    #[test]
+    fn full_queue_does_not_block() {
+        // The receiver is kept alive but never read from, like a writer thread stuck on the disk
+        let (sender, _receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+        let queued = AtomicUsize::new(0);
+        let dropped = AtomicUsize::new(0);
+
+        for _ in 0..QUEUE_CAPACITY {
+            enqueue(&sender, line(Level::Error), &queued, &dropped);
+        }
+
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+
+        let start = Instant::now();
+
+        for level in [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ] {
+            for _ in 0..1000 {
+                enqueue(&sender, line(level), &queued, &dropped);
+            }
+        }
+
+        assert_eq!(dropped.load(Ordering::Relaxed), 5000);
+        assert_eq!(queued.load(Ordering::Relaxed), QUEUE_CAPACITY);
+        assert!(
+            start.elapsed() < Duration::from_millis(500),
+            "queueing 5000 messages took {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn sheds_less_severe_messages_first() {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+        let queued = AtomicUsize::new(0);
+        let dropped = AtomicUsize::new(0);
+
+        // A flood of debug and info messages the writer cannot keep up with
+        for _ in 0..QUEUE_CAPACITY {
+            enqueue(&sender, line(Level::Debug), &queued, &dropped);
+        }
+        for _ in 0..QUEUE_CAPACITY {
+            enqueue(&sender, line(Level::Info), &queued, &dropped);
+        }
+
+        assert_eq!(
+            queued.load(Ordering::Relaxed),
+            QUEUE_CAPACITY - RESERVED_CAPACITY
+        );
+
+        // Warnings and errors still fit into the reserved room
+        for level in [Level::Warn, Level::Error] {
+            for _ in 0..RESERVED_CAPACITY / 2 {
+                enqueue(&sender, line(level), &queued, &dropped);
+            }
+        }
+
+        assert_eq!(
+            dropped.load(Ordering::Relaxed),
+            2 * QUEUE_CAPACITY - (QUEUE_CAPACITY - RESERVED_CAPACITY)
+        );
+
+        let levels: Vec<Level> = receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Line { level, .. } => level,
+                Message::Flush(_) => unreachable!(),
+            })
+            .collect();
+
+        let count = |level| levels.iter().filter(|l| **l == level).count();
+
+        assert_eq!(count(Level::Debug), VERBOSE_HIGH_WATER);
+        assert_eq!(
+            count(Level::Info),
+            QUEUE_CAPACITY - RESERVED_CAPACITY - VERBOSE_HIGH_WATER
+        );
+        assert_eq!(count(Level::Warn), RESERVED_CAPACITY / 2);
+        assert_eq!(count(Level::Error), RESERVED_CAPACITY / 2);
+    }
+
+    /// Logs through the synchronous path used before messages were queued:
+    /// every call formats the message and appends it to the log file itself.
+    struct SynchronousLogger {
+        directory: PathBuf,
+    }
+
+    impl Log for SynchronousLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            use std::io::Write;
+
+            let Message::Line { date, text, .. } = line_message(
+                record.level(),
+                &record.args().to_string(),
+                record.file().unwrap_or("<unknown>"),
+                record.line().unwrap_or(0),
+            ) else {
+                unreachable!();
+            };
+
+            std::fs::create_dir_all(&self.directory).unwrap();
+
+            let mut log_file = std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(
+                    self.directory
+                        .join(format!("{}.log", date.format("%Y-%m-%d"))),
+                )
+                .unwrap();
+
+            writeln!(log_file, "{text}").unwrap();
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Logs like [`Logger`], through the queue to a writer thread.
+    struct QueuedLogger {
+        sender: SyncSender<Message>,
+    }
+
+    impl Log for QueuedLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            enqueue(
+                &self.sender,
+                line_message(
+                    record.level(),
+                    &record.args().to_string(),
+                    record.file().unwrap_or("<unknown>"),
+                    record.line().unwrap_or(0),
+                ),
+                &QUEUED_LINES,
+                &DROPPED_LINES,
+            );
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Returns the average time `logger` takes per `log::info!` call.
+    fn time_per_call(logger: &dyn Log, calls: u32) -> Duration {
+        let start = Instant::now();
+
+        for index in 0..calls {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!(
+                        "Terminated notepad.exe (pid: {index}) successfully"
+                    ))
+                    .level(Level::Info)
+                    .target(module_path!())
+                    .file(Some(file!()))
+                    .line(Some(line!()))
+                    .build(),
+            );
+        }
+
+        start.elapsed() / calls
+    }
+
+    /// Compares the per-call overhead of logging before and after messages were
+    /// queued. Run with `cargo test --release -- --ignored --nocapture log_overhead`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn log_overhead() {
+        const CALLS: u32 = 20_000;
+
+        let directory = tempfile::tempdir().unwrap();
+
+        let synchronous = SynchronousLogger {
+            directory: directory.path().join("synchronous"),
+        };
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+        let queued_directory = directory.path().join("queued");
+        let writer = std::thread::spawn(move || run_writer(&receiver, queued_directory));
+        let queued = QueuedLogger { sender };
+
+        // Warm up the file system cache and the writer thread
+        time_per_call(&synchronous, 100);
+        time_per_call(&queued, 100);
+        DROPPED_LINES.store(0, Ordering::Relaxed);
+
+        let synchronous_time = time_per_call(&synchronous, CALLS);
+        let queued_time = time_per_call(&queued, CALLS);
+        let dropped = DROPPED_LINES.load(Ordering::Relaxed);
+
+        assert!(flush_within(&queued.sender, FLUSH_TIMEOUT));
+        drop(queued);
+        writer.join().unwrap();
+
+        println!("synchronous: {synchronous_time:?} per call");
+        println!("queued:      {queued_time:?} per call ({dropped} of {CALLS} messages dropped)");
+    }
+}
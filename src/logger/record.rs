@@ -0,0 +1,65 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// The format messages are written to the log files in.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `[time] [level] [file:line] message`
+    #[default]
+    Text,
+    /// One [`LogRecord`] JSON object per line
+    Json,
+}
+
+/// A single message in a log file written in the [`LogFormat::Json`] format.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub timestamp: DateTime<Local>,
+    pub level: String,
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+
+    /// Identifies the run of xterminate the message was logged by
+    pub session: String,
+}
+
+impl LogRecord {
+    /// Formats the record in the [`LogFormat::Text`] format.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        format!(
+            "[{}] [{}] [{}:{}] {}",
+            self.timestamp.format("%H:%M:%S.%f"),
+            self.level,
+            self.file,
+            self.line,
+            self.message
+        )
+        .replace('\n', "\n\t")
+    }
+}
+
+/// Reads the JSON records in a log file back. Lines written in
+/// the text format or that cannot be parsed are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the log file cannot be read.
+pub fn read_records(path: &Path) -> std::io::Result<Vec<LogRecord>> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+
+    let mut records = Vec::new();
+
+    for line in file.lines() {
+        if let Ok(record) = serde_json::from_str::<LogRecord>(&line?) {
+            records.push(record);
+        }
+    }
+
+    Ok(records)
+}
@@ -4,81 +4,111 @@
 #![windows_subsystem = "windows"]
 
 pub mod app;
+pub mod audit;
+pub mod cli;
+pub mod clipboard;
 pub mod config;
+pub mod crash;
 pub mod cursor;
+pub mod diagnostics;
 pub mod error;
+pub mod history;
 pub mod input;
+pub mod locale;
 pub mod logger;
+pub mod notification;
 pub mod process;
 pub mod registry;
+pub mod rules;
 pub mod tray;
 pub mod ui;
 pub mod updater;
+pub mod watchdog;
+pub mod wide;
 pub mod window;
 
 use app::App;
+use notification::Severity;
+use ui::dialogs::{Dialog, Dialogs};
+use ui::taskdialog::TaskDialogs;
 
 fn main() {
+    logger::install();
+
+    if let Err(error) = process::enable_debug_privilege() {
+        logf!("WARNING: failed to enable the debug privilege, processes of other users cannot be terminated [{error}]");
+    }
+
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if let Some(exit_code) = cli::run(&args) {
+        logger::flush();
+        std::process::exit(exit_code);
+    }
+
     logf!(
-        "Environment information:\nOS: {}\nApplication: {}",
+        "Environment information:\nOS: {}\nApplication: {}\nSession: {}",
         os_info::get(),
-        env!("CARGO_PKG_VERSION")
+        env!("CARGO_PKG_VERSION"),
+        logger::session_id()
     );
 
     error::set_panic_hook();
 
     if instance_count() > 1 {
         // An instance is already running
-        ui::taskdialog::TaskDialog::new()
-            .set_title("Already running")
-            .set_heading("Already running")
-            .set_content("An instance of xterminate is already running. Please exit the running instance before starting a new one.")
-            .set_icon(ui::taskdialog::TaskDialogIcon::InformationIcon)
-            .display()
-            .result();
+        TaskDialogs.message(
+            &Dialog::new(
+                Severity::Information,
+                tr!("dialog.already_running.title"),
+                tr!("dialog.already_running.heading"),
+                tr!("dialog.already_running.content"),
+            ),
+            true,
+        );
 
         return;
     }
 
-    App::run(&App::new());
+    App::run(&App::new(std::sync::Arc::new(TaskDialogs)));
+
+    logger::flush();
 }
 
 /// Returns the amount of processes with the same file name as the current one.
 fn instance_count() -> u32 {
     use windows::Win32::System::Diagnostics::ToolHelp::{
-        CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32, TH32CS_SNAPPROCESS,
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
     };
 
     let mut count = 0;
 
+    let executable_name = std::env::current_exe()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_owned();
+
     unsafe {
-        let mut process_entry = PROCESSENTRY32 {
-            dwSize: std::mem::size_of::<PROCESSENTRY32>().try_into().unwrap(),
+        let mut process_entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>().try_into().unwrap(),
             ..Default::default()
         };
 
         let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).unwrap();
 
-        let mut result = Process32First(snapshot, &mut process_entry);
+        let mut result = Process32FirstW(snapshot, &mut process_entry);
 
         loop {
             if result.is_ok() {
-                if process_entry.szExeFile.starts_with(
-                    std::env::current_exe()
-                        .unwrap()
-                        .file_name()
-                        .unwrap()
-                        .as_encoded_bytes(),
-                )
-                // The rest should be nuls
-                {
+                if wide::from_wide(&process_entry.szExeFile) == executable_name {
                     count += 1;
                 }
             } else {
                 break;
             }
 
-            result = Process32Next(snapshot, &mut process_entry);
+            result = Process32NextW(snapshot, &mut process_entry);
         }
     }
 
@@ -0,0 +1,121 @@
+//! Non-blocking notifications telling the user what xterminate did, such as
+//! which process was terminated. Notifications are shown by a [`Notifier`],
+//! which is the tray icon while xterminate is running, and filtered by the
+//! [`Verbosity`] configured in the `[notifications]` section of the config.
+
+use serde::{Deserialize, Serialize};
+
+use crate::logf;
+
+/// How important a [`Notification`] is, which also decides its icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Information,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Shows [`Notification`]s to the user.
+pub trait Notifier {
+    fn notify(&self, notification: &Notification);
+}
+
+/// Which notifications are shown.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// Every notification
+    #[default]
+    All,
+
+    /// Only warnings and errors
+    Warnings,
+
+    /// Only errors
+    Errors,
+
+    /// No notifications at all
+    None,
+}
+
+impl Verbosity {
+    /// Returns true if notifications of the specified severity are shown.
+    #[must_use]
+    pub fn allows(self, severity: Severity) -> bool {
+        match self {
+            Self::All => true,
+            Self::Warnings => severity >= Severity::Warning,
+            Self::Errors => severity >= Severity::Error,
+            Self::None => false,
+        }
+    }
+}
+
+/// Sends notifications to a [`Notifier`] if their severity is allowed by
+/// the configured [`Verbosity`]. Notifications sent before a notifier is
+/// set, e.g. before the tray icon exists, are only logged.
+pub struct Notifications {
+    notifier: Option<Box<dyn Notifier>>,
+    verbosity: Verbosity,
+}
+
+impl Notifications {
+    #[must_use]
+    pub fn new(verbosity: Verbosity) -> Self {
+        Self {
+            notifier: None,
+            verbosity,
+        }
+    }
+
+    pub fn set_notifier(&mut self, notifier: Box<dyn Notifier>) {
+        self.notifier = Some(notifier);
+    }
+
+    pub fn set_verbosity(&mut self, verbosity: Verbosity) {
+        self.verbosity = verbosity;
+    }
+
+    pub fn info(&self, message: impl Into<String>) {
+        self.notify(Notification {
+            severity: Severity::Information,
+            message: message.into(),
+        });
+    }
+
+    pub fn warning(&self, message: impl Into<String>) {
+        self.notify(Notification {
+            severity: Severity::Warning,
+            message: message.into(),
+        });
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.notify(Notification {
+            severity: Severity::Error,
+            message: message.into(),
+        });
+    }
+
+    pub fn notify(&self, notification: Notification) {
+        let Notification { severity, message } = &notification;
+
+        if !self.verbosity.allows(*severity) {
+            logf!(Debug: "Notification suppressed by verbosity ({severity:?}): {message}");
+            return;
+        }
+
+        let Some(notifier) = &self.notifier else {
+            logf!(Debug: "No notifier available ({severity:?}): {message}");
+            return;
+        };
+
+        notifier.notify(&notification);
+    }
+}
@@ -0,0 +1,342 @@
+use std::ffi::c_void;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, FILETIME, HANDLE};
+use windows::Win32::Security::{
+    GetTokenInformation, LookupAccountSidW, TokenElevation, TokenSessionId, TokenUser,
+    SID_NAME_USE, TOKEN_ELEVATION, TOKEN_INFORMATION_CLASS, TOKEN_QUERY, TOKEN_USER,
+};
+use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+use windows::Win32::System::SystemInformation::{
+    IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64,
+    IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_UNKNOWN,
+};
+use windows::Win32::System::Threading::{GetProcessTimes, IsWow64Process2, OpenProcessToken};
+
+use super::Process;
+use crate::{logf, wide};
+
+/// The amount of 100-nanosecond intervals between 1601-01-01 (the `FILETIME` epoch)
+/// and 1970-01-01 (the Unix epoch).
+const FILETIME_UNIX_EPOCH_OFFSET: u64 = 116_444_736_000_000_000;
+
+/// The instruction set a process is running as.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Architecture {
+    X86,
+    X64,
+    Arm,
+    Arm64,
+}
+
+impl Architecture {
+    fn from_machine(machine: IMAGE_FILE_MACHINE) -> Option<Self> {
+        match machine {
+            IMAGE_FILE_MACHINE_I386 => Some(Self::X86),
+            IMAGE_FILE_MACHINE_AMD64 => Some(Self::X64),
+            IMAGE_FILE_MACHINE_ARMNT => Some(Self::Arm),
+            IMAGE_FILE_MACHINE_ARM64 => Some(Self::Arm64),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Architecture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::X86 => "x86",
+                Self::X64 => "x64",
+                Self::Arm => "ARM",
+                Self::Arm64 => "ARM64",
+            }
+        )
+    }
+}
+
+/// A snapshot of a process' metadata, taken with [`Process::info`].
+///
+/// Unlike [`Process`], the snapshot does not refer to a live process, so it can
+/// be logged, serialized and matched against rules after the process is gone.
+/// Metadata that could not be queried, e.g. because access was denied, is `None`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub path: String,
+    pub parent_pid: Option<u32>,
+    pub command_line: Option<String>,
+    pub start_time: Option<DateTime<Local>>,
+
+    /// The account the process runs as, formatted as `DOMAIN\user`.
+    pub user: Option<String>,
+    pub session_id: Option<u32>,
+    pub architecture: Option<Architecture>,
+    pub elevated: Option<bool>,
+    pub working_set_bytes: Option<u64>,
+
+    /// Total time spent executing in both kernel and user mode, in milliseconds.
+    pub cpu_time_ms: Option<u64>,
+}
+
+impl std::fmt::Display for ProcessInfo {
+    #[allow(clippy::cast_precision_loss)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (pid: {}", self.name, self.pid)?;
+
+        if let Some(parent_pid) = self.parent_pid {
+            write!(f, ", parent: {parent_pid}")?;
+        }
+
+        if let Some(user) = &self.user {
+            write!(f, ", user: {user}")?;
+        }
+
+        if let Some(session_id) = self.session_id {
+            write!(f, ", session: {session_id}")?;
+        }
+
+        if let Some(architecture) = self.architecture {
+            write!(f, ", {architecture}")?;
+        }
+
+        if self.elevated == Some(true) {
+            write!(f, ", elevated")?;
+        }
+
+        if let Some(working_set_bytes) = self.working_set_bytes {
+            write!(
+                f,
+                ", memory: {:.1} MB",
+                working_set_bytes as f64 / (1024.0 * 1024.0)
+            )?;
+        }
+
+        if let Some(cpu_time_ms) = self.cpu_time_ms {
+            write!(f, ", cpu: {:.1}s", cpu_time_ms as f64 / 1000.0)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl Process {
+    /// Takes a [`ProcessInfo`] snapshot of the process' current metadata.
+    /// The name and path are left empty if the path cannot be queried.
+    #[must_use]
+    pub fn info(&self) -> ProcessInfo {
+        let token = Token::open(self);
+        let times = self.times();
+
+        let path = self.try_path().unwrap_or_else(|err| {
+            logf!(
+                "WARNING: failed to get path for process ({}) [{err}]",
+                self.id()
+            );
+
+            PathBuf::new()
+        });
+
+        ProcessInfo {
+            pid: self.id(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: path.display().to_string(),
+            parent_pid: self.basic_information().and_then(|information| {
+                u32::try_from(information.InheritedFromUniqueProcessId).ok()
+            }),
+            command_line: self
+                .command_line()
+                .map(|command_line| command_line.to_string_lossy().into_owned()),
+            start_time: times.and_then(|(start_time, _)| start_time),
+            user: token.as_ref().and_then(Token::user),
+            session_id: token.as_ref().and_then(Token::session_id),
+            architecture: self.architecture(),
+            elevated: token.as_ref().and_then(Token::elevated),
+            working_set_bytes: self.working_set_bytes(),
+            cpu_time_ms: times.map(|(_, cpu_time_ms)| cpu_time_ms),
+        }
+    }
+
+    /// Returns the start time of the process and the total CPU time
+    /// it has used so far in milliseconds.
+    fn times(&self) -> Option<(Option<DateTime<Local>>, u64)> {
+        let mut creation_time = FILETIME::default();
+        let mut exit_time = FILETIME::default();
+        let mut kernel_time = FILETIME::default();
+        let mut user_time = FILETIME::default();
+
+        unsafe {
+            GetProcessTimes(
+                HANDLE(self.handle),
+                &mut creation_time,
+                &mut exit_time,
+                &mut kernel_time,
+                &mut user_time,
+            )
+            .ok()?;
+        }
+
+        // Kernel and user times are durations in 100-nanosecond intervals
+        let cpu_time_ms = (filetime_to_u64(kernel_time) + filetime_to_u64(user_time)) / 10_000;
+
+        Some((filetime_to_local(creation_time), cpu_time_ms))
+    }
+
+    fn architecture(&self) -> Option<Architecture> {
+        let mut process_machine = IMAGE_FILE_MACHINE::default();
+        let mut native_machine = IMAGE_FILE_MACHINE::default();
+
+        unsafe {
+            IsWow64Process2(
+                HANDLE(self.handle),
+                &mut process_machine,
+                Some(&mut native_machine),
+            )
+            .ok()?;
+        }
+
+        // The process machine is only set for processes running under WOW64,
+        // any other process runs natively.
+        if process_machine == IMAGE_FILE_MACHINE_UNKNOWN {
+            Architecture::from_machine(native_machine)
+        } else {
+            Architecture::from_machine(process_machine)
+        }
+    }
+
+    fn working_set_bytes(&self) -> Option<u64> {
+        let mut counters = PROCESS_MEMORY_COUNTERS::default();
+
+        unsafe {
+            GetProcessMemoryInfo(
+                HANDLE(self.handle),
+                &mut counters,
+                u32::try_from(std::mem::size_of::<PROCESS_MEMORY_COUNTERS>()).unwrap(),
+            )
+            .ok()?;
+        }
+
+        u64::try_from(counters.WorkingSetSize).ok()
+    }
+}
+
+/// An access token of a process, opened for querying and closed when dropped.
+struct Token(HANDLE);
+
+impl Token {
+    fn open(process: &Process) -> Option<Self> {
+        let mut token = HANDLE(0);
+
+        unsafe { OpenProcessToken(HANDLE(process.handle), TOKEN_QUERY, &mut token).ok()? };
+
+        Some(Self(token))
+    }
+
+    /// Reads token information of a fixed-size type `T`.
+    fn information<T: Copy + Default>(&self, class: TOKEN_INFORMATION_CLASS) -> Option<T> {
+        let mut value = T::default();
+        let mut length = 0;
+
+        unsafe {
+            GetTokenInformation(
+                self.0,
+                class,
+                Some(std::ptr::addr_of_mut!(value).cast::<c_void>()),
+                u32::try_from(std::mem::size_of::<T>()).unwrap(),
+                &mut length,
+            )
+            .ok()?;
+        }
+
+        Some(value)
+    }
+
+    fn session_id(&self) -> Option<u32> {
+        self.information::<u32>(TokenSessionId)
+    }
+
+    fn elevated(&self) -> Option<bool> {
+        self.information::<TOKEN_ELEVATION>(TokenElevation)
+            .map(|elevation| elevation.TokenIsElevated != 0)
+    }
+
+    /// Returns the account the token belongs to, formatted as `DOMAIN\user`.
+    fn user(&self) -> Option<String> {
+        unsafe {
+            // The first call fails but reports the required buffer size since
+            // TOKEN_USER is followed by a variable-length SID.
+            let mut length = 0;
+            let _ = GetTokenInformation(self.0, TokenUser, None, 0, &mut length);
+
+            // Allocated as u64s so the buffer is sufficiently aligned for TOKEN_USER
+            let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+
+            GetTokenInformation(
+                self.0,
+                TokenUser,
+                Some(buffer.as_mut_ptr().cast::<c_void>()),
+                length,
+                &mut length,
+            )
+            .ok()?;
+
+            let sid = (*buffer.as_ptr().cast::<TOKEN_USER>()).User.Sid;
+
+            let mut name = vec![0u16; 256];
+            let mut name_length = u32::try_from(name.len()).unwrap();
+            let mut domain = vec![0u16; 256];
+            let mut domain_length = u32::try_from(domain.len()).unwrap();
+            let mut sid_name_use = SID_NAME_USE::default();
+
+            LookupAccountSidW(
+                PCWSTR::null(),
+                sid,
+                PWSTR(name.as_mut_ptr()),
+                &mut name_length,
+                PWSTR(domain.as_mut_ptr()),
+                &mut domain_length,
+                &mut sid_name_use,
+            )
+            .ok()?;
+
+            Some(format!(
+                "{}\\{}",
+                wide::from_wide(&domain).to_string_lossy(),
+                wide::from_wide(&name).to_string_lossy()
+            ))
+        }
+    }
+}
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+fn filetime_to_u64(filetime: FILETIME) -> u64 {
+    (u64::from(filetime.dwHighDateTime) << 32) | u64::from(filetime.dwLowDateTime)
+}
+
+/// Converts a `FILETIME` timestamp to local time. Returns `None` for
+/// timestamps that predate the Unix epoch.
+fn filetime_to_local(filetime: FILETIME) -> Option<DateTime<Local>> {
+    let intervals = filetime_to_u64(filetime).checked_sub(FILETIME_UNIX_EPOCH_OFFSET)?;
+
+    let seconds = i64::try_from(intervals / 10_000_000).ok()?;
+    let nanoseconds = u32::try_from((intervals % 10_000_000) * 100).ok()?;
+
+    DateTime::from_timestamp(seconds, nanoseconds).map(|time| time.with_timezone(&Local))
+}
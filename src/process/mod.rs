@@ -0,0 +1,506 @@
+use std::ffi::{c_void, OsStr, OsString};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Wdk::System::Threading::{NtQueryInformationProcess, ProcessBasicInformation};
+use windows::Win32::Security::{
+    AdjustTokenPrivileges, LookupPrivilegeValueW, SE_DEBUG_NAME, SE_PRIVILEGE_ENABLED,
+    TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES,
+};
+
+use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, GetExitCodeProcess, OpenProcess, OpenProcessToken,
+    QueryFullProcessImageNameW, TerminateProcess, WaitForSingleObject, PROCESS_BASIC_INFORMATION,
+    PROCESS_NAME_WIN32, PROCESS_QUERY_INFORMATION, PROCESS_SYNCHRONIZE, PROCESS_TERMINATE,
+    PROCESS_VM_READ,
+};
+
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_APP_HANG, ERROR_INSUFFICIENT_BUFFER, HANDLE, LUID, MAX_PATH, STILL_ACTIVE,
+    UNICODE_STRING, WAIT_OBJECT_0,
+};
+
+use crate::{app, logf, wide};
+
+mod info;
+mod threads;
+mod tree;
+
+pub use info::{Architecture, ProcessInfo};
+pub use threads::{resume, suspend};
+pub use tree::descendants;
+
+/// Everything needed to start a process again the same way it was
+/// originally started, captured before the process is terminated.
+#[derive(Debug, Clone)]
+pub struct LaunchSpec {
+    pub path: PathBuf,
+    pub command_line: OsString,
+    pub working_directory: PathBuf,
+
+    /// The environment block of the process: nul-separated `KEY=VALUE`
+    /// pairs terminated by an additional nul character.
+    pub environment: Vec<u16>,
+}
+
+impl LaunchSpec {
+    /// Starts a new instance of the process described by this [`LaunchSpec`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`windows::core::Error`] if the process could not be created,
+    /// e.g. because the executable has since been moved or deleted.
+    pub fn launch(&self) -> Result<(), windows::core::Error> {
+        app::run_executable_in(
+            &self.path,
+            &self.command_line,
+            Some(&self.working_directory),
+            Some(&self.environment),
+        )
+    }
+}
+
+/// The leading part of the undocumented `RTL_USER_PROCESS_PARAMETERS` structure
+/// pointed to by a process' PEB. The public definition in the Windows SDK hides
+/// the current directory and environment behind reserved fields.
+///
+/// Offsets are only valid for 64-bit processes, which is what xterminate is
+/// built as. The PEB of WOW64 processes is read through its native 64-bit view.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ProcessParameters {
+    _reserved1: [u8; 16],
+    _console: [*mut c_void; 5],
+    current_directory: UNICODE_STRING,
+    _current_directory_handle: *mut c_void,
+    _dll_path: UNICODE_STRING,
+    _image_path_name: UNICODE_STRING,
+    command_line: UNICODE_STRING,
+    environment: *mut c_void,
+    _reserved2: [u8; 0x368],
+    environment_size: usize,
+}
+
+/// How long to wait for a process to exit after [`Process::terminate`].
+pub const TERMINATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The state a process was left in after xterminate tried to terminate it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationOutcome {
+    /// The process has exited.
+    Exited,
+    /// The process did not exit within [`TERMINATION_TIMEOUT`].
+    StillRunning,
+    /// The process has begun exiting but has not finished within [`TERMINATION_TIMEOUT`],
+    /// which typically happens when one of its threads is stuck in a kernel call.
+    Zombie,
+}
+
+impl std::fmt::Display for TerminationOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::Exited => "Exited",
+                Self::StillRunning => "StillRunning",
+                Self::Zombie => "Zombie",
+            }
+        )
+    }
+}
+
+/// Enables the debug privilege for xterminate, which allows it to open and
+/// terminate processes of other users. Needs to be called once at startup.
+///
+/// # Errors
+///
+/// Returns an error if the access token of xterminate cannot be opened or adjusted.
+pub fn enable_debug_privilege() -> windows::core::Result<()> {
+    let mut token_handle = HANDLE(0);
+
+    unsafe {
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_ADJUST_PRIVILEGES,
+            &mut token_handle,
+        )?;
+    }
+
+    // Can't find any privilege specifically for terminating so
+    // I'll just use SE_DEBUG_NAME since that one gives all of them.
+    let result = (|| unsafe {
+        let mut luid = LUID::default();
+        LookupPrivilegeValueW(PCWSTR(std::ptr::null()), SE_DEBUG_NAME, &mut luid)?;
+
+        let mut token_privileges = TOKEN_PRIVILEGES {
+            PrivilegeCount: 1,
+            ..TOKEN_PRIVILEGES::default()
+        };
+
+        token_privileges.Privileges[0].Luid = luid;
+        token_privileges.Privileges[0].Attributes = SE_PRIVILEGE_ENABLED;
+
+        AdjustTokenPrivileges(
+            token_handle,
+            false,
+            Some(&token_privileges),
+            u32::try_from(std::mem::size_of::<TOKEN_PRIVILEGES>()).unwrap(),
+            None,
+            None,
+        )
+    })();
+
+    unsafe {
+        let _ = CloseHandle(token_handle);
+    }
+
+    result
+}
+
+pub struct Process {
+    id: u32,
+    handle: isize,
+    valid: bool, // true if xterminate has exited or terminated the process
+}
+
+impl Process {
+    /// Opens the process with the specified PID and returns a [`Process`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the internal call to [`OpenProcess()`] returns a [`HANDLE`] of value `0`.
+    #[must_use]
+    pub fn open(pid: u32) -> Self {
+        Self::try_open(pid).unwrap_or_else(|err| {
+            panic!("failed to open target process ({pid}) [{err}]");
+        })
+    }
+
+    /// Opens the process with the specified PID and returns a [`Process`],
+    /// or an error if the process does not exist or could not be opened.
+    ///
+    /// Processes of other users can only be opened after [`enable_debug_privilege`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`windows::core::Error`] reported by [`OpenProcess()`].
+    pub fn try_open(pid: u32) -> windows::core::Result<Self> {
+        let handle = unsafe {
+            OpenProcess(
+                PROCESS_SYNCHRONIZE
+                    | PROCESS_VM_READ
+                    | PROCESS_QUERY_INFORMATION
+                    | PROCESS_TERMINATE,
+                false,
+                pid,
+            )
+        }?;
+
+        Ok(Self {
+            id: pid,
+            handle: handle.0,
+            valid: true,
+        })
+    }
+
+    /// Tells the `self` process to terminate without waiting for it to exit.
+    /// Use [`Process::outcome`] to wait for it and find out whether it did.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error reported by [`TerminateProcess()`], e.g. if access is denied.
+    pub fn terminate(&mut self) -> windows::core::Result<()> {
+        logf!("Terminating process {}", self);
+
+        if !self.valid {
+            logf!(
+                "ERROR: unable to terminate process [{}] as it is no longer valid",
+                self.id()
+            );
+
+            return Ok(());
+        }
+
+        unsafe { TerminateProcess(HANDLE(self.handle), ERROR_APP_HANG.0) }?;
+
+        // Process terminated successfully and is no longer valid
+        self.valid = false;
+
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for the process to exit and determines
+    /// the [`TerminationOutcome`] from the state it is left in.
+    #[must_use]
+    pub fn outcome(&self, timeout: Duration) -> TerminationOutcome {
+        if self.wait_for_exit(timeout) {
+            return TerminationOutcome::Exited;
+        }
+
+        // A process that has been assigned an exit code but whose handle is not
+        // yet signaled is being torn down but is stuck, e.g. on pending kernel I/O.
+        let mut exit_code = 0u32;
+        let has_exit_code = unsafe { GetExitCodeProcess(HANDLE(self.handle), &mut exit_code) }
+            .is_ok_and(|()| exit_code != u32::try_from(STILL_ACTIVE.0).unwrap());
+
+        if has_exit_code {
+            TerminationOutcome::Zombie
+        } else {
+            TerminationOutcome::StillRunning
+        }
+    }
+
+    /// Blocks until the process has exited or until `timeout` has passed.
+    /// Returns true if the process exited within the given time.
+    #[must_use]
+    pub fn wait_for_exit(&self, timeout: Duration) -> bool {
+        let timeout_ms = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+
+        unsafe { WaitForSingleObject(HANDLE(self.handle), timeout_ms) == WAIT_OBJECT_0 }
+    }
+
+    /// Captures the executable path, command line, working directory and
+    /// environment of the process so that it can be started again after
+    /// it has been terminated.
+    ///
+    /// Returns `None` if the process is no longer valid or if its memory
+    /// could not be read, which is the case for protected processes.
+    #[must_use]
+    pub fn launch_spec(&self) -> Option<LaunchSpec> {
+        if !self.valid {
+            return None;
+        }
+
+        let handle = HANDLE(self.handle);
+        let parameters = self.process_parameters()?;
+
+        unsafe {
+            let command_line = read_remote_unicode_string(handle, &parameters.command_line)?;
+            let working_directory =
+                read_remote_unicode_string(handle, &parameters.current_directory)?;
+
+            let mut environment = vec![0u16; parameters.environment_size / 2];
+            ReadProcessMemory(
+                handle,
+                parameters.environment,
+                environment.as_mut_ptr().cast::<c_void>(),
+                environment.len() * 2,
+                None,
+            )
+            .ok()?;
+
+            Some(LaunchSpec {
+                path: self.path(),
+                command_line: wide::from_wide(&command_line),
+                working_directory: PathBuf::from(wide::from_wide(&working_directory)),
+                environment,
+            })
+        }
+    }
+
+    /// Queries the [`PROCESS_BASIC_INFORMATION`] of the process, which
+    /// contains the address of its PEB and the ID of its parent process.
+    fn basic_information(&self) -> Option<PROCESS_BASIC_INFORMATION> {
+        let mut basic_information = PROCESS_BASIC_INFORMATION::default();
+
+        unsafe {
+            NtQueryInformationProcess(
+                HANDLE(self.handle),
+                ProcessBasicInformation,
+                std::ptr::addr_of_mut!(basic_information).cast::<c_void>(),
+                u32::try_from(std::mem::size_of::<PROCESS_BASIC_INFORMATION>()).unwrap(),
+                std::ptr::null_mut(),
+            )
+            .ok()
+            .ok()?;
+        }
+
+        Some(basic_information)
+    }
+
+    /// Reads the [`ProcessParameters`] from the address space of the process.
+    fn process_parameters(&self) -> Option<ProcessParameters> {
+        let handle = HANDLE(self.handle);
+        let basic_information = self.basic_information()?;
+
+        // Read the PEB of the target process followed by the process
+        // parameters it points to, both of which live in its address space.
+        unsafe {
+            let peb = read_remote::<windows::Win32::System::Threading::PEB>(
+                handle,
+                basic_information.PebBaseAddress.cast::<c_void>(),
+            )?;
+
+            read_remote::<ProcessParameters>(handle, peb.ProcessParameters.cast::<c_void>())
+        }
+    }
+
+    /// Returns the command line the process was started with.
+    #[must_use]
+    pub fn command_line(&self) -> Option<OsString> {
+        if !self.valid {
+            return None;
+        }
+
+        let parameters = self.process_parameters()?;
+
+        unsafe {
+            read_remote_unicode_string(HANDLE(self.handle), &parameters.command_line)
+                .map(|command_line| wide::from_wide(&command_line))
+        }
+    }
+
+    /// Returns the absolute path to the process executable.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the path of the process could not be retrieved.
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        if !self.valid {
+            return PathBuf::from("invalid process handle");
+        }
+
+        self.try_path()
+            .unwrap_or_else(|err| panic!("failed to get path for process ({}) [{err}]", self.id()))
+    }
+
+    /// Returns the absolute path to the process executable like [`Process::path`],
+    /// or an error if it could not be retrieved.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`windows::core::Error`] reported by [`QueryFullProcessImageNameW()`].
+    pub fn try_path(&self) -> windows::core::Result<PathBuf> {
+        // Start out with room for a regular path and grow the buffer
+        // as needed to accommodate extended-length paths.
+        let mut capacity = MAX_PATH as usize;
+
+        loop {
+            let mut buffer = vec![0u16; capacity];
+            let mut length = u32::try_from(capacity).unwrap();
+
+            let result = unsafe {
+                QueryFullProcessImageNameW(
+                    HANDLE(self.handle()),
+                    PROCESS_NAME_WIN32,
+                    PWSTR(buffer.as_mut_ptr()),
+                    &mut length,
+                )
+            };
+
+            match result {
+                Ok(()) => return Ok(PathBuf::from(wide::from_wide(&buffer[..length as usize]))),
+
+                Err(err)
+                    if err.code() == ERROR_INSUFFICIENT_BUFFER.to_hresult()
+                        && capacity < wide::MAX_LONG_PATH =>
+                {
+                    capacity = (capacity * 2).min(wide::MAX_LONG_PATH + 1);
+                }
+
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Returns the name of the process executable (including its extension).
+    /// The name is returned as it is, use [`OsStr::to_string_lossy`] to display it.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the path of the process could not be retrieved.
+    #[must_use]
+    pub fn name(&self) -> OsString {
+        if !self.valid {
+            return OsString::from("invalid process handle");
+        }
+
+        // Strips the absolute path and keeps only the executable filename
+        self.path()
+            .file_name()
+            .map(OsStr::to_os_string)
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    #[must_use]
+    pub fn handle(&self) -> isize {
+        self.handle
+    }
+}
+
+/// Reads a value of type `T` located at `address` in the address space of the
+/// process referred to by `handle`. Returns `None` if the memory could not be read.
+unsafe fn read_remote<T: Copy>(handle: HANDLE, address: *const c_void) -> Option<T> {
+    let mut value = std::mem::MaybeUninit::<T>::uninit();
+
+    ReadProcessMemory(
+        handle,
+        address,
+        value.as_mut_ptr().cast::<c_void>(),
+        std::mem::size_of::<T>(),
+        None,
+    )
+    .ok()?;
+
+    Some(value.assume_init())
+}
+
+/// Reads the UTF-16 buffer of a [`UNICODE_STRING`] that belongs to
+/// another process. Returns `None` if the memory could not be read.
+unsafe fn read_remote_unicode_string(handle: HANDLE, string: &UNICODE_STRING) -> Option<Vec<u16>> {
+    let mut buffer = vec![0u16; usize::from(string.Length) / 2];
+
+    if buffer.is_empty() {
+        return Some(buffer);
+    }
+
+    ReadProcessMemory(
+        handle,
+        string.Buffer.as_ptr().cast::<c_void>(),
+        buffer.as_mut_ptr().cast::<c_void>(),
+        buffer.len() * 2,
+        None,
+    )
+    .ok()?;
+
+    Some(buffer)
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // The handle outlives termination so the exit can be waited on, but
+        // it has to be closed once the process is of no further interest.
+        unsafe {
+            let _ = CloseHandle(HANDLE(self.handle));
+        }
+    }
+}
+
+impl std::fmt::Debug for Process {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Process")
+            .field("name", &self.name())
+            .field("id", &self.id)
+            .field("handle", &format_args!("0x{0:08X}", self.handle))
+            .field("valid", &self.valid)
+            .finish()
+    }
+}
+
+impl std::fmt::Display for Process {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (pid: {})", self.name().to_string_lossy(), self.id())
+    }
+}
@@ -0,0 +1,77 @@
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+};
+use windows::Win32::System::Threading::{
+    OpenThread, ResumeThread, SuspendThread, THREAD_SUSPEND_RESUME,
+};
+
+/// Suspends every thread of the process with the specified id, which
+/// freezes it until [`resume`] is called. Returns the number of threads
+/// that were suspended.
+///
+/// # Errors
+///
+/// Returns an error if the threads cannot be listed, or if the process
+/// has threads but none of them could be suspended, e.g. because access
+/// is denied.
+pub fn suspend(pid: u32) -> windows::core::Result<usize> {
+    for_each_thread(pid, |thread| unsafe { SuspendThread(thread) })
+}
+
+/// Resumes every thread of the process with the specified id
+/// that was suspended by [`suspend`]. Returns the number of
+/// threads that were resumed.
+///
+/// # Errors
+///
+/// Returns an error if the threads cannot be listed, or if the process
+/// has threads but none of them could be resumed.
+pub fn resume(pid: u32) -> windows::core::Result<usize> {
+    for_each_thread(pid, |thread| unsafe { ResumeThread(thread) })
+}
+
+/// Calls `action` with a handle to every thread of a process. `action`
+/// returns `u32::MAX` on failure, like [`SuspendThread()`] and [`ResumeThread()`].
+fn for_each_thread(pid: u32, action: impl Fn(HANDLE) -> u32) -> windows::core::Result<usize> {
+    let mut count = 0;
+    let mut last_error = None;
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)?;
+
+        let mut thread_entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>().try_into().unwrap(),
+            ..Default::default()
+        };
+
+        let mut result = Thread32First(snapshot, &mut thread_entry);
+
+        while result.is_ok() {
+            if thread_entry.th32OwnerProcessID == pid {
+                match OpenThread(THREAD_SUSPEND_RESUME, false, thread_entry.th32ThreadID) {
+                    Ok(thread) => {
+                        if action(thread) == u32::MAX {
+                            last_error = Some(windows::core::Error::from_win32());
+                        } else {
+                            count += 1;
+                        }
+
+                        let _ = CloseHandle(thread);
+                    }
+
+                    Err(error) => last_error = Some(error),
+                }
+            }
+
+            result = Thread32Next(snapshot, &mut thread_entry);
+        }
+
+        let _ = CloseHandle(snapshot);
+    }
+
+    match last_error {
+        Some(error) if count == 0 => Err(error),
+        _ => Ok(count),
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use windows::Win32::Foundation::{CloseHandle, FILETIME};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::Threading::{
+    GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+/// Returns the ids of every process started by the process with the specified
+/// id, directly or by one of its children, parents before their children.
+///
+/// Windows records the id of the parent a process was started by, which may
+/// since have exited and had its id reused. Processes created before their
+/// supposed parent are therefore left out.
+#[must_use]
+pub fn descendants(pid: u32) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+
+    for (child, parent) in snapshot() {
+        // The idle process is its own parent
+        if child != parent {
+            children.entry(parent).or_default().push(child);
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut parents = vec![(pid, creation_time(pid))];
+
+    while let Some((parent, parent_created)) = parents.pop() {
+        for child in children.get(&parent).into_iter().flatten() {
+            let created = creation_time(*child);
+
+            let started_by_parent = match (parent_created, created) {
+                (Some(parent_created), Some(created)) => created >= parent_created,
+                // Processes that cannot be queried are assumed to be children
+                _ => true,
+            };
+
+            if started_by_parent && *child != pid && !result.contains(child) {
+                result.push(*child);
+                parents.push((*child, created));
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the id and parent id of every running process.
+fn snapshot() -> Vec<(u32, u32)> {
+    let mut processes = Vec::new();
+
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return processes;
+        };
+
+        let mut process_entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>().try_into().unwrap(),
+            ..Default::default()
+        };
+
+        let mut result = Process32FirstW(snapshot, &mut process_entry);
+
+        while result.is_ok() {
+            processes.push((
+                process_entry.th32ProcessID,
+                process_entry.th32ParentProcessID,
+            ));
+
+            result = Process32NextW(snapshot, &mut process_entry);
+        }
+
+        let _ = CloseHandle(snapshot);
+    }
+
+    processes
+}
+
+/// Returns when the process was created in 100-nanosecond
+/// intervals since 1601, or `None` if it cannot be queried.
+fn creation_time(pid: u32) -> Option<u64> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+
+        let mut created = FILETIME::default();
+        let mut exited = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+
+        let result = GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user);
+
+        let _ = CloseHandle(handle);
+        result.ok()?;
+
+        Some(u64::from(created.dwHighDateTime) << 32 | u64::from(created.dwLowDateTime))
+    }
+}
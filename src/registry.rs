@@ -1,14 +1,16 @@
-use windows::core::PCSTR;
+use std::ffi::OsStr;
+
+use windows::core::PCWSTR;
 use windows::Win32::Foundation::GetLastError;
 
 use windows::Win32::System::Registry::{
-    RegCloseKey, RegCreateKeyExA, RegDeleteValueA, RegOpenKeyExA, RegQueryValueExA,
-    RegSetKeyValueA, HKEY, HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER,
+    RegCloseKey, RegCreateKeyExW, RegDeleteValueW, RegOpenKeyExW, RegQueryValueExW,
+    RegSetKeyValueW, HKEY, HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER,
     HKEY_LOCAL_MACHINE, HKEY_USERS, KEY_READ, KEY_SET_VALUE, KEY_WRITE, REG_BINARY, REG_DWORD,
     REG_DWORD_BIG_ENDIAN, REG_EXPAND_SZ, REG_LINK, REG_OPTION_NON_VOLATILE, REG_QWORD, REG_SZ,
 };
 
-use crate::logf;
+use crate::{logf, wide};
 
 #[derive(Copy, Clone, Debug)]
 #[repr(isize)]
@@ -76,15 +78,37 @@ impl std::fmt::Display for ValueType {
 /// or created, could not be set, or could not be closed. This should only
 /// occur if the given parameters are invalid. On panic, the full OS error
 /// code will be printed on-screen and to stderr.
-pub fn set_value(root_key: HKey, subkey: &str, name: &str, value_type: ValueType, value: &str) {
+pub fn set_value(
+    root_key: HKey,
+    subkey: &str,
+    name: &str,
+    value_type: ValueType,
+    value: impl AsRef<OsStr>,
+) {
+    let value = value.as_ref();
+
+    let subkey_wide = wide::to_wide_nul(subkey);
+    let name_wide = wide::to_wide_nul(name);
+
+    // String values are stored as nul-terminated UTF-16, anything
+    // else is stored as the raw bytes of the supplied value.
+    let data: Vec<u8> = match value_type {
+        ValueType::Sz | ValueType::ExpandSZ => wide::to_wide_nul(value)
+            .iter()
+            .flat_map(|c| c.to_le_bytes())
+            .collect(),
+
+        _ => value.as_encoded_bytes().to_vec(),
+    };
+
     unsafe {
         let mut hkey = HKEY(root_key as isize);
 
-        if RegCreateKeyExA(
+        if RegCreateKeyExW(
             hkey,
-            PCSTR(std::ffi::CString::new(subkey).unwrap().as_bytes().as_ptr()),
+            PCWSTR(subkey_wide.as_ptr()),
             0,
-            PCSTR(std::ptr::null()),
+            PCWSTR(std::ptr::null()),
             REG_OPTION_NON_VOLATILE,
             KEY_WRITE,
             None,
@@ -95,7 +119,7 @@ pub fn set_value(root_key: HKey, subkey: &str, name: &str, value_type: ValueType
         {
             panic!(
                 "failed to set registry key: could not create or open registry key 
-            '{root_key}:{subkey}' for writing: RegCreateKeyExA() failed [{}]",
+            '{root_key}:{subkey}' for writing: RegCreateKeyExW() failed [{}]",
                 GetLastError().unwrap_err()
             );
         }
@@ -106,35 +130,23 @@ pub fn set_value(root_key: HKey, subkey: &str, name: &str, value_type: ValueType
             subkey,
             name,
             value_type,
-            value
+            value.to_string_lossy()
         );
 
-        if RegSetKeyValueA(
+        if RegSetKeyValueW(
             hkey,
-            PCSTR(std::ptr::null()),
-            PCSTR(std::ffi::CString::new(name).unwrap().as_bytes().as_ptr()),
+            PCWSTR(std::ptr::null()),
+            PCWSTR(name_wide.as_ptr()),
             value_type as u32,
-            Some(
-                std::ffi::CString::new(value)
-                    .unwrap()
-                    .as_bytes()
-                    .as_ptr()
-                    .cast::<std::ffi::c_void>(),
-            ),
-            match value_type {
-                ValueType::Sz | ValueType::ExpandSZ => {
-                    u32::try_from(value.len() + 1).unwrap()
-                    // Include null-terminator
-                }
-
-                _ => u32::try_from(value.len()).unwrap(),
-            },
+            Some(data.as_ptr().cast::<std::ffi::c_void>()),
+            u32::try_from(data.len()).unwrap(),
         )
         .is_err()
         {
             panic!(
                 "failed to set registry key '{root_key}\\{subkey}\\{name}' 
-            to '{value}': RegSetKeyValueA() failed [{}]",
+            to '{}': RegSetKeyValueW() failed [{}]",
+                value.to_string_lossy(),
                 GetLastError().unwrap_err()
             );
         }
@@ -159,9 +171,9 @@ pub fn delete_value(root_key: HKey, subkey: &str, name: &str) {
     unsafe {
         let mut hkey = HKEY(root_key as isize);
 
-        if RegOpenKeyExA(
+        if RegOpenKeyExW(
             hkey,
-            PCSTR(std::ffi::CString::new(subkey).unwrap().as_bytes().as_ptr()),
+            PCWSTR(wide::to_wide_nul(subkey).as_ptr()),
             0,
             KEY_SET_VALUE,
             &mut hkey,
@@ -170,7 +182,7 @@ pub fn delete_value(root_key: HKey, subkey: &str, name: &str) {
         {
             panic!(
                 "could not delete registry value '{root_key}\\{subkey}\\{name}': 
-            RegOpenKeyExA() [{}]",
+            RegOpenKeyExW() [{}]",
                 GetLastError().unwrap_err()
             );
         }
@@ -182,15 +194,10 @@ pub fn delete_value(root_key: HKey, subkey: &str, name: &str) {
             name
         );
 
-        if RegDeleteValueA(
-            hkey,
-            PCSTR(std::ffi::CString::new(name).unwrap().as_bytes().as_ptr()),
-        )
-        .is_err()
-        {
+        if RegDeleteValueW(hkey, PCWSTR(wide::to_wide_nul(name).as_ptr())).is_err() {
             panic!(
                 "could not delete registry value '{root_key}\\{subkey}\\{name}': 
-            RegDeleteValueA() failed [{}]",
+            RegDeleteValueW() failed [{}]",
                 GetLastError().unwrap_err()
             );
         }
@@ -209,17 +216,15 @@ pub fn delete_value(root_key: HKey, subkey: &str, name: &str) {
 ///
 /// # Panics
 ///
-/// Panics if:
-///  - `name` is null-terminated
-///  - The underlying calls to Windows registry fails
+/// Panics if the underlying calls to Windows registry fails
 #[must_use]
 pub fn exists(root_key: HKey, subkey: &str, name: Option<&str>) -> bool {
     unsafe {
         let mut hkey = HKEY(root_key as isize);
 
-        if RegOpenKeyExA(
+        if RegOpenKeyExW(
             hkey,
-            PCSTR(std::ffi::CString::new(subkey).unwrap().as_bytes().as_ptr()),
+            PCWSTR(wide::to_wide_nul(subkey).as_ptr()),
             0,
             KEY_READ,
             &mut hkey,
@@ -240,14 +245,9 @@ pub fn exists(root_key: HKey, subkey: &str, name: Option<&str>) -> bool {
             return true;
         }
 
-        let result = RegQueryValueExA(
+        let result = RegQueryValueExW(
             hkey,
-            PCSTR(
-                std::ffi::CString::new(name.unwrap())
-                    .unwrap()
-                    .as_bytes()
-                    .as_ptr(),
-            ),
+            PCWSTR(wide::to_wide_nul(name.unwrap()).as_ptr()),
             None,
             None,
             None,
@@ -260,7 +260,7 @@ pub fn exists(root_key: HKey, subkey: &str, name: Option<&str>) -> bool {
             GetLastError().unwrap_err()
         );
 
-        // RegQueryValueExA will return an error if the key doesn't exist
+        // RegQueryValueExW will return an error if the key doesn't exist
         result.is_ok()
     }
 }
@@ -0,0 +1,411 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::process::ProcessInfo;
+
+/// What to do when a window has been unresponsive for longer
+/// than the watchdog threshold.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HangAction {
+    /// Ask the user whether to terminate, wait or ignore the window
+    #[default]
+    Prompt,
+    /// Terminate the window's process without asking
+    Kill,
+    /// Never act on the window
+    Ignore,
+}
+
+/// A per-application rule read from the `[[rules]]` section of `config.toml`.
+///
+/// A rule applies to a process if all of its matchers match. Matchers
+/// that are left out match any process, but a rule needs at least one.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Executable name the rule applies to, e.g. `notepad.exe`.
+    /// Matching is case-insensitive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+
+    /// Directory the executable must be located in, either directly
+    /// or in one of its subdirectories. Matching is case-insensitive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Account the process must run as, either `user` or `DOMAIN\user`.
+    /// Matching is case-insensitive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// Whether the process must (or must not) run with elevated privileges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevated: Option<bool>,
+
+    /// Overrides the watchdog's default behaviour for this application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_hang: Option<HangAction>,
+
+    /// Prevents (`true`) or allows (`false`) xterminate to terminate,
+    /// restart or suspend the application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protected: Option<bool>,
+
+    /// Always (`true`) or never (`false`) ask for confirmation before
+    /// acting on the application, regardless of the `[confirm]` section.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<bool>,
+}
+
+impl Rule {
+    /// Returns true if the rule has at least one matcher, i.e. it does
+    /// not apply to every process.
+    #[must_use]
+    pub fn has_matcher(&self) -> bool {
+        self.process.is_some()
+            || self.path.is_some()
+            || self.user.is_some()
+            || self.elevated.is_some()
+    }
+
+    /// Returns true if this rule applies to the given process.
+    #[must_use]
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let name_matches = self
+            .process
+            .as_ref()
+            .is_none_or(|name| name.eq_ignore_ascii_case(&process.name));
+
+        let path_matches = self
+            .path
+            .as_ref()
+            .is_none_or(|directory| is_in_directory(&process.path, directory));
+
+        let user_matches = self.user.as_ref().is_none_or(|user| {
+            process
+                .user
+                .as_ref()
+                .is_some_and(|process_user| user_matches(process_user, user))
+        });
+
+        let elevated_matches = self
+            .elevated
+            .is_none_or(|elevated| process.elevated == Some(elevated));
+
+        name_matches && path_matches && user_matches && elevated_matches
+    }
+}
+
+/// Deserializes the `[[rules]]` of the config, rejecting rules without a
+/// matcher, which would apply to every process, e.g. because of a typo
+/// in the name of a matcher.
+///
+/// # Errors
+///
+/// Returns an error if a rule cannot be deserialized or has no matcher.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Rule>, D::Error> {
+    let rules = Vec::<Rule>::deserialize(deserializer)?;
+
+    if let Some(index) = rules.iter().position(|rule| !rule.has_matcher()) {
+        return Err(D::Error::custom(format!(
+            "rule {} needs at least one of `process`, `path`, `user` or `elevated`",
+            index + 1
+        )));
+    }
+
+    Ok(rules)
+}
+
+/// Returns what to do when a window of the given process stops responding,
+/// as set by the first rule in `rules` that applies to it and sets `on_hang`.
+#[must_use]
+pub fn hang_action(rules: &[Rule], process: &ProcessInfo) -> HangAction {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(process))
+        .find_map(|rule| rule.on_hang)
+        .unwrap_or_default()
+}
+
+/// Returns true if the first rule in `rules` that applies to the given
+/// process and sets `protected` protects it from being terminated.
+#[must_use]
+pub fn is_protected(rules: &[Rule], process: &ProcessInfo) -> bool {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(process))
+        .find_map(|rule| rule.protected)
+        .unwrap_or(false)
+}
+
+/// Returns whether to ask for confirmation before acting on the given process.
+/// The first rule in `rules` that applies to it and sets `confirm` overrides
+/// `default`, which is the setting for the keybind from the `[confirm]` section.
+#[must_use]
+pub fn requires_confirmation(rules: &[Rule], process: &ProcessInfo, default: bool) -> bool {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(process))
+        .find_map(|rule| rule.confirm)
+        .unwrap_or(default)
+}
+
+fn normalize_path(path: &str) -> String {
+    path.replace('/', "\\")
+        .trim_end_matches('\\')
+        .to_ascii_lowercase()
+}
+
+fn is_in_directory(path: &str, directory: &str) -> bool {
+    let path = normalize_path(path);
+    let directory = normalize_path(directory);
+
+    path.strip_prefix(&directory)
+        .is_some_and(|rest| rest.starts_with('\\'))
+}
+
+/// Compares a `DOMAIN\user` account against a rule's account, which
+/// may leave out the domain to match the user in any domain.
+fn user_matches(account: &str, user: &str) -> bool {
+    if user.contains('\\') {
+        account.eq_ignore_ascii_case(user)
+    } else {
+        account
+            .rsplit('\\')
+            .next()
+            .is_some_and(|name| name.eq_ignore_ascii_case(user))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    struct Rules {
+        #[serde(default, deserialize_with = "deserialize")]
+        rules: Vec<Rule>,
+    }
+
+    fn notepad() -> ProcessInfo {
+        ProcessInfo {
+            pid: 42,
+            name: String::from("notepad.exe"),
+            path: String::from("C:\\Windows\\System32\\notepad.exe"),
+            user: Some(String::from("DESKTOP-1\\alice")),
+            elevated: Some(false),
+            ..ProcessInfo::default()
+        }
+    }
+
+    fn parse(source: &str) -> Result<Vec<Rule>, toml::de::Error> {
+        toml::from_str::<Rules>(source).map(|rules| rules.rules)
+    }
+
+    #[test]
+    fn matches_name_case_insensitively() {
+        let rule = Rule {
+            process: Some(String::from("NOTEPAD.EXE")),
+            ..Rule::default()
+        };
+
+        assert!(rule.matches(&notepad()));
+
+        let rule = Rule {
+            process: Some(String::from("notepad")),
+            ..Rule::default()
+        };
+
+        assert!(!rule.matches(&notepad()));
+    }
+
+    #[test]
+    fn matches_directory_and_subdirectories() {
+        let matches = |directory: &str| {
+            Rule {
+                path: Some(directory.to_owned()),
+                ..Rule::default()
+            }
+            .matches(&notepad())
+        };
+
+        assert!(matches("C:\\Windows\\System32"));
+        assert!(matches("c:/windows/"));
+        assert!(matches("C:\\"));
+        assert!(!matches("C:\\Windows\\Sys"));
+        assert!(!matches("C:\\Windows\\System32\\notepad.exe"));
+        assert!(!matches("D:\\Windows"));
+    }
+
+    #[test]
+    fn matches_user_with_or_without_domain() {
+        let matches = |user: &str, process: &ProcessInfo| {
+            Rule {
+                user: Some(user.to_owned()),
+                ..Rule::default()
+            }
+            .matches(process)
+        };
+
+        assert!(matches("alice", &notepad()));
+        assert!(matches("desktop-1\\ALICE", &notepad()));
+        assert!(!matches("OTHER\\alice", &notepad()));
+        assert!(!matches("bob", &notepad()));
+
+        let unknown_user = ProcessInfo {
+            user: None,
+            ..notepad()
+        };
+
+        assert!(!matches("alice", &unknown_user));
+    }
+
+    #[test]
+    fn matches_elevation() {
+        let rule = Rule {
+            elevated: Some(true),
+            ..Rule::default()
+        };
+
+        assert!(!rule.matches(&notepad()));
+        assert!(rule.matches(&ProcessInfo {
+            elevated: Some(true),
+            ..notepad()
+        }));
+        assert!(!rule.matches(&ProcessInfo {
+            elevated: None,
+            ..notepad()
+        }));
+    }
+
+    #[test]
+    fn requires_all_matchers() {
+        let rule = Rule {
+            process: Some(String::from("notepad.exe")),
+            path: Some(String::from("C:\\Program Files")),
+            ..Rule::default()
+        };
+
+        assert!(!rule.matches(&notepad()));
+    }
+
+    #[test]
+    fn resolves_hang_action_from_first_rule_setting_it() {
+        let rules = parse(
+            r#"
+            [[rules]]
+            process = "calc.exe"
+            on_hang = "ignore"
+
+            [[rules]]
+            process = "notepad.exe"
+            protected = true
+
+            [[rules]]
+            path = "C:\\Windows"
+            on_hang = "kill"
+
+            [[rules]]
+            process = "notepad.exe"
+            on_hang = "prompt"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(hang_action(&rules, &notepad()), HangAction::Kill);
+
+        let other = ProcessInfo {
+            name: String::from("other.exe"),
+            path: String::from("D:\\other.exe"),
+            ..notepad()
+        };
+
+        assert_eq!(hang_action(&rules, &other), HangAction::Prompt);
+    }
+
+    #[test]
+    fn resolves_confirmation_from_first_rule_setting_it() {
+        let rules = parse(
+            r#"
+            [[rules]]
+            process = "notepad.exe"
+            on_hang = "kill"
+
+            [[rules]]
+            path = "C:\\Windows"
+            confirm = false
+
+            [[rules]]
+            process = "notepad.exe"
+            confirm = true
+            "#,
+        )
+        .unwrap();
+
+        assert!(!requires_confirmation(&rules, &notepad(), true));
+        assert!(requires_confirmation(&rules[..1], &notepad(), true));
+        assert!(!requires_confirmation(&rules[..1], &notepad(), false));
+    }
+
+    #[test]
+    fn resolves_protection_from_first_rule_setting_it() {
+        let rules = parse(
+            r#"
+            [[rules]]
+            process = "notepad.exe"
+            confirm = false
+
+            [[rules]]
+            path = "C:\\Windows"
+            protected = true
+
+            [[rules]]
+            process = "notepad.exe"
+            protected = false
+            "#,
+        )
+        .unwrap();
+
+        assert!(is_protected(&rules, &notepad()));
+        assert!(!is_protected(&rules[..1], &notepad()));
+
+        let mut rules = rules;
+        rules.swap(1, 2);
+        assert!(!is_protected(&rules, &notepad()));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let error = parse(
+            r#"
+            [[rules]]
+            name = "notepad.exe"
+            protected = true
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(
+            error.to_string().contains("unknown field `name`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn rejects_rules_without_matcher() {
+        let error = parse(
+            r#"
+            [[rules]]
+            process = "notepad.exe"
+
+            [[rules]]
+            on_hang = "kill"
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("rule 2 needs"), "{error}");
+        assert!(parse("").unwrap().is_empty());
+    }
+}
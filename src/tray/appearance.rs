@@ -0,0 +1,217 @@
+//! Decides what the tray icon and its tooltip look like for the current
+//! state of xterminate, independent of the Win32 calls that apply it.
+
+use crate::app::AppState;
+use crate::tr;
+
+/// The icons the tray icon switches between, stored in the `res` folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIcon {
+    Normal,
+    TerminationMode,
+    Paused,
+    UpdateAvailable,
+}
+
+impl TrayIcon {
+    pub const ALL: [Self; 4] = [
+        Self::Normal,
+        Self::TerminationMode,
+        Self::Paused,
+        Self::UpdateAvailable,
+    ];
+
+    /// Returns the name of the icon file relative to the `res` folder.
+    #[must_use]
+    pub fn filename(self) -> &'static str {
+        match self {
+            Self::Normal => "icon.ico",
+            Self::TerminationMode => "icon-active.ico",
+            Self::Paused => "icon-paused.ico",
+            Self::UpdateAvailable => "icon-update.ico",
+        }
+    }
+}
+
+/// Everything the appearance of the tray icon depends on.
+#[derive(Debug, Clone)]
+pub struct TrayStatus {
+    pub state: AppState,
+    pub hotkeys_paused: bool,
+
+    /// When paused hotkeys are resumed automatically, if configured
+    pub resume_at: Option<chrono::DateTime<chrono::Local>>,
+
+    /// The version of a newer release, if one was found
+    pub update_available: Option<String>,
+    pub terminate_click_keybind: String,
+    pub terminate_immediate_keybind: String,
+
+    /// The number of processes terminated since xterminate started
+    pub kills: usize,
+    pub last_action: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Appearance {
+    pub icon: TrayIcon,
+
+    /// One line per piece of information, most important first, since
+    /// Windows cuts tooltips off after 127 UTF-16 code units
+    pub tooltip: String,
+}
+
+/// Returns the icon and tooltip for the given status. Termination mode takes
+/// precedence over paused hotkeys, which take precedence over updates.
+#[must_use]
+pub fn appearance(status: &TrayStatus) -> Appearance {
+    let (icon, headline) = if status.state == AppState::Active {
+        (
+            TrayIcon::TerminationMode,
+            tr!("tray.tooltip.termination_mode"),
+        )
+    } else if status.hotkeys_paused {
+        (
+            TrayIcon::Paused,
+            status.resume_at.map_or_else(
+                || tr!("tray.tooltip.paused"),
+                |resume_at| {
+                    tr!(
+                        "tray.tooltip.paused_until",
+                        time = resume_at.format("%H:%M")
+                    )
+                },
+            ),
+        )
+    } else if let Some(version) = &status.update_available {
+        (
+            TrayIcon::UpdateAvailable,
+            tr!("tray.tooltip.update_available", version = version),
+        )
+    } else {
+        (TrayIcon::Normal, "xterminate".to_owned())
+    };
+
+    let mut lines = vec![
+        headline,
+        tr!("tray.tooltip.terminated", count = status.kills),
+    ];
+
+    if let Some(last_action) = &status.last_action {
+        lines.push(tr!("tray.tooltip.last_action", action = last_action));
+    }
+
+    if !status.hotkeys_paused {
+        lines.push(tr!(
+            "tray.tooltip.click",
+            keybind = status.terminate_click_keybind
+        ));
+        lines.push(tr!(
+            "tray.tooltip.active",
+            keybind = status.terminate_immediate_keybind
+        ));
+    }
+
+    Appearance {
+        icon,
+        tooltip: lines.join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Locale;
+    use chrono::TimeZone;
+
+    fn status() -> TrayStatus {
+        let _ = crate::locale::configure(&Locale {
+            language: Some(String::from("en")),
+        });
+
+        TrayStatus {
+            state: AppState::Standby,
+            hotkeys_paused: false,
+            resume_at: None,
+            update_available: None,
+            terminate_click_keybind: String::from("CTRL+ALT+F4"),
+            terminate_immediate_keybind: String::from("CTRL+ALT+END"),
+            kills: 3,
+            last_action: None,
+        }
+    }
+
+    #[test]
+    fn shows_normal_icon_by_default() {
+        let appearance = appearance(&status());
+
+        assert_eq!(appearance.icon, TrayIcon::Normal);
+        assert_eq!(
+            appearance.tooltip,
+            "xterminate\nTerminated: 3\nClick: CTRL+ALT+F4\nActive: CTRL+ALT+END"
+        );
+    }
+
+    #[test]
+    fn paused_takes_precedence_over_update() {
+        let appearance = appearance(&TrayStatus {
+            hotkeys_paused: true,
+            update_available: Some(String::from("9.9.9")),
+            ..status()
+        });
+
+        assert_eq!(appearance.icon, TrayIcon::Paused);
+        assert_eq!(
+            appearance.tooltip,
+            "xterminate - hotkeys paused\nTerminated: 3"
+        );
+    }
+
+    #[test]
+    fn termination_mode_takes_precedence_over_paused() {
+        let appearance = appearance(&TrayStatus {
+            state: AppState::Active,
+            hotkeys_paused: true,
+            update_available: Some(String::from("9.9.9")),
+            ..status()
+        });
+
+        assert_eq!(appearance.icon, TrayIcon::TerminationMode);
+        assert!(appearance
+            .tooltip
+            .starts_with("xterminate - termination mode\n"));
+    }
+
+    #[test]
+    fn shows_when_paused_hotkeys_resume() {
+        let resume_at = chrono::Local
+            .with_ymd_and_hms(2024, 5, 1, 14, 30, 0)
+            .unwrap();
+
+        let appearance = appearance(&TrayStatus {
+            hotkeys_paused: true,
+            resume_at: Some(resume_at),
+            last_action: Some(String::from("Terminated notepad.exe")),
+            ..status()
+        });
+
+        assert_eq!(appearance.icon, TrayIcon::Paused);
+        assert_eq!(
+            appearance.tooltip,
+            "xterminate - hotkeys paused until 14:30\nTerminated: 3\nLast: Terminated notepad.exe"
+        );
+    }
+
+    #[test]
+    fn shows_available_update() {
+        let appearance = appearance(&TrayStatus {
+            update_available: Some(String::from("9.9.9")),
+            ..status()
+        });
+
+        assert_eq!(appearance.icon, TrayIcon::UpdateAvailable);
+        assert!(appearance
+            .tooltip
+            .starts_with("xterminate - v9.9.9 available\n"));
+    }
+}
@@ -1,135 +1,96 @@
-use std::ffi::CString;
-
 use windows::{
-    core::PCSTR,
+    core::PCWSTR,
     Win32::{
         Foundation::{HWND, POINT},
         UI::WindowsAndMessaging::{
-            CreatePopupMenu, GetCursorPos, InsertMenuA, SetForegroundWindow, TrackPopupMenu, HMENU,
-            MF_BYPOSITION, MF_DISABLED, MF_SEPARATOR, TPM_BOTTOMALIGN,
+            AppendMenuW, CreatePopupMenu, DestroyMenu, GetCursorPos, SetForegroundWindow,
+            TrackPopupMenu, HMENU, MENU_ITEM_FLAGS, MF_CHECKED, MF_GRAYED, MF_POPUP, MF_SEPARATOR,
+            MF_STRING, TPM_BOTTOMALIGN,
         },
     },
 };
 
-use super::TrayEvent;
+use super::model::{self, ItemState, Menu, MenuBackend};
+use crate::wide;
 
-pub struct TrayMenu {
-    handle: HMENU,
-    tray_window_handle: HWND,
-    position: (i32, i32),
-}
+/// Renders [`Menu`]s as Win32 popup menus.
+struct Win32Backend;
+
+impl MenuBackend for Win32Backend {
+    type Handle = HMENU;
 
-impl TrayMenu {
-    #[must_use]
-    #[allow(clippy::new_ret_no_self)]
-    /// # Panics
-    ///
-    /// Will panic if the underlying call to [`CreatePopupMenu()`] fails.
-    pub fn new(tray_window_handle: HWND) -> TrayMenuBuilder {
-        TrayMenuBuilder {
-            handle: unsafe { CreatePopupMenu().unwrap() },
-            tray_window_handle,
-            position: None,
-            item_count: 0,
-        }
+    fn create(&mut self) -> HMENU {
+        unsafe { CreatePopupMenu() }.expect("failed to create popup menu")
     }
 
-    pub fn show(&self) {
-        unsafe {
-            SetForegroundWindow(self.tray_window_handle);
-            TrackPopupMenu(
-                self.handle,
-                TPM_BOTTOMALIGN,
-                self.position.0,
-                self.position.1,
-                0,
-                self.tray_window_handle,
-                None,
-            );
-        }
+    fn append_item(&mut self, menu: HMENU, id: u16, label: &str, state: ItemState) {
+        append(menu, MF_STRING | flags(state), usize::from(id), label);
+    }
+
+    fn append_separator(&mut self, menu: HMENU) {
+        unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null()) }
+            .expect("failed to append menu separator");
     }
-}
 
-pub struct TrayMenuBuilder {
-    handle: HMENU,
-    tray_window_handle: HWND,
-    item_count: u32,
-    position: Option<(i32, i32)>,
+    fn append_submenu(&mut self, menu: HMENU, submenu: HMENU, label: &str, state: ItemState) {
+        append(
+            menu,
+            MF_STRING | MF_POPUP | flags(state),
+            usize::try_from(submenu.0).unwrap(),
+            label,
+        );
+    }
 }
 
-impl TrayMenuBuilder {
-    pub fn set_position(&mut self, position: (i32, i32)) -> &mut Self {
-        self.position = Some(position);
+fn flags(state: ItemState) -> MENU_ITEM_FLAGS {
+    let mut flags = MENU_ITEM_FLAGS(0);
 
-        self
+    if !state.enabled {
+        flags |= MF_GRAYED;
     }
 
-    /// Adds a button to the tray popup menu.
-    ///
-    /// # Arguments
-    ///
-    /// * `label` - The text to displayed on the button item.
-    /// * `event` - The event to generate when the button is pressed.
-    ///             If `None`, the button will be disabled and greyed out.
-    ///
-    /// # Panics
-    ///
-    /// Will panic if `label` is not nul-terminated
-    pub fn add_button(&mut self, label: &str, event: Option<TrayEvent>) -> &mut Self {
-        self.item_count += 1;
-
-        let mut uflags = MF_BYPOSITION;
-
-        if event.is_none() {
-            uflags |= MF_DISABLED;
-        }
-
-        let c_label = CString::new(label).expect("invalid C-style string");
-
-        unsafe {
-            InsertMenuA(
-                self.handle,
-                self.item_count,
-                uflags,
-                event.map_or(0, |ev| ev as usize),
-                PCSTR(c_label.as_ptr().cast::<u8>()),
-            )
-            .unwrap();
-        }
-
-        self
+    if state.checked {
+        flags |= MF_CHECKED;
     }
 
-    #[allow(clippy::missing_panics_doc)]
-    pub fn add_separator(&mut self) -> &mut Self {
-        self.item_count += 1;
-
-        unsafe {
-            InsertMenuA(
-                self.handle,
-                self.item_count,
-                MF_BYPOSITION | MF_SEPARATOR,
-                0,
-                PCSTR::null(),
-            )
-            .unwrap();
-        }
-
-        self
-    }
+    flags
+}
+
+fn append(menu: HMENU, flags: MENU_ITEM_FLAGS, id: usize, label: &str) {
+    let label = wide::to_wide_nul(label);
+
+    unsafe { AppendMenuW(menu, flags, id, PCWSTR(label.as_ptr())) }
+        .expect("failed to append menu item");
+}
 
-    #[allow(clippy::missing_panics_doc)]
-    pub fn build(&mut self) -> TrayMenu {
-        TrayMenu {
-            handle: self.handle,
-            tray_window_handle: self.tray_window_handle,
-
-            // Default position to position of mouse cursor
-            position: self.position.unwrap_or({
-                let mut cursor_position = POINT::default();
-                unsafe { GetCursorPos(&mut cursor_position).unwrap() };
-                (cursor_position.x, cursor_position.y)
-            }),
-        }
+/// Shows `menu` as a popup menu at the position of the mouse cursor. When an item
+/// is clicked, a `WM_COMMAND` message holding the id of its event in the low word
+/// of its `wParam` is sent to `owner`.
+///
+/// # Panics
+///
+/// Will panic if the menu cannot be created or the cursor position cannot be determined.
+pub fn show(owner: HWND, menu: &Menu) {
+    let handle = model::render(menu, &mut Win32Backend);
+
+    let mut position = POINT::default();
+    unsafe { GetCursorPos(&mut position) }.expect("failed to get cursor position");
+
+    unsafe {
+        // The menu only closes when clicking elsewhere if its owner is in the foreground
+        SetForegroundWindow(owner);
+
+        TrackPopupMenu(
+            handle,
+            TPM_BOTTOMALIGN,
+            position.x,
+            position.y,
+            0,
+            owner,
+            None,
+        );
+
+        // Submenus are destroyed along with their parent
+        let _ = DestroyMenu(handle);
     }
 }
@@ -1,18 +1,22 @@
+pub mod appearance;
 pub mod menu;
+pub mod model;
 
 use windows::core::PCSTR;
 
 use windows::Win32::Foundation::{GetLastError, HMODULE, HWND, LPARAM, LRESULT, WPARAM};
 
 use windows::Win32::UI::Shell::{
-    Shell_NotifyIconA, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAA,
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_ERROR, NIIF_INFO,
+    NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW, NOTIFY_ICON_INFOTIP_FLAGS,
 };
 
 use windows::Win32::UI::WindowsAndMessaging::{
-    CreateWindowExA, DefWindowProcA, DestroyWindow, DispatchMessageA, GetWindowLongPtrW,
-    LoadImageA, PeekMessageA, RegisterClassExA, SetWindowLongPtrW, TranslateMessage, GWLP_USERDATA,
-    HICON, HMENU, IMAGE_ICON, LR_LOADFROMFILE, MSG, PM_REMOVE, WINDOW_EX_STYLE, WINDOW_STYLE,
-    WM_COMMAND, WM_LBUTTONDOWN, WM_RBUTTONDOWN, WM_USER, WNDCLASSEXA,
+    CreateWindowExA, DefWindowProcA, DestroyIcon, DestroyWindow, DispatchMessageA,
+    GetWindowLongPtrW, LoadImageA, PeekMessageA, RegisterClassExA, SetWindowLongPtrW,
+    TranslateMessage, GWLP_USERDATA, HICON, HMENU, IMAGE_ICON, LR_LOADFROMFILE, MSG, PM_REMOVE,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_COMMAND, WM_LBUTTONDOWN, WM_RBUTTONDOWN, WM_USER,
+    WNDCLASSEXA,
 };
 
 use windows::Win32::System::LibraryLoader::GetModuleHandleA;
@@ -21,49 +25,342 @@ const TRAYICON_ID: u32 = 1;
 const WM_USER_TRAYICON: u32 = WM_USER + TRAYICON_ID;
 
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::{input::Keybind, logf, registry};
+use crate::history::{HistoryEntry, HISTORY_ID_LIMIT};
+use crate::notification::{Notification, Notifier, Severity};
+use crate::process::TerminationOutcome;
+use crate::{app, logf, tr};
 
-#[repr(usize)]
+use appearance::{Appearance, TrayIcon};
+use model::{Menu, MenuItem};
+
+/// An action selected from the tray menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrayEvent {
-    OnMenuSelectExit = 0,
-    OnMenuSelectStartWithWindows = 1,
-    OnMenuSelectOpenConfig = 3,
-    OnMenuSelectEnterTerminationMode = 4,
-    OnMenuSelectAbout = 5,
-    OnMenuSelectOpenLoggingDirectory = 6,
-    OnMenuSelectCheckForUpdates = 7,
-    OnMenuSelectUpdateOnStartup = 8,
+    OnMenuSelectExit,
+    OnMenuSelectStartWithWindows,
+    OnMenuSelectOpenConfig,
+    OnMenuSelectEnterTerminationMode,
+    OnMenuSelectAbout,
+    OnMenuSelectOpenLoggingDirectory,
+    OnMenuSelectCheckForUpdates,
+    OnMenuSelectUpdateOnStartup,
+    OnMenuSelectRelaunchLast,
+    OnMenuSelectExportDiagnostics,
+    OnMenuSelectReloadConfig,
+    OnMenuSelectPauseHotkeys,
+
+    /// An action on the entry of the "Recently terminated" submenu
+    /// for the history entry with the id `entry`.
+    OnMenuSelectRecent {
+        entry: u16,
+        action: RecentAction,
+    },
 }
 
-pub trait TrayEventHandler {
-    fn handle(&mut self, event: TrayEvent);
+/// The actions offered for each entry of the "Recently terminated" submenu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentAction {
+    Relaunch,
+    CopyPath,
+    OpenFolder,
+    Protect,
+}
+
+impl RecentAction {
+    const ALL: [Self; RECENT_ACTION_COUNT as usize] = [
+        Self::Relaunch,
+        Self::CopyPath,
+        Self::OpenFolder,
+        Self::Protect,
+    ];
 }
 
-impl From<u16> for TrayEvent {
-    fn from(v: u16) -> Self {
-        match v {
-            0 => Self::OnMenuSelectExit,
+const RECENT_ACTION_COUNT: u16 = 4;
+
+/// The first command id used by [`TrayEvent::OnMenuSelectRecent`]. Each
+/// history entry id is assigned one command id per [`RecentAction`].
+const RECENT_FIRST_ID: u16 = 0x1000;
+
+impl TrayEvent {
+    /// Returns the command id of the menu item that generates this event.
+    /// Ids are stable and never 0, which Windows uses for items without an id.
+    #[must_use]
+    pub fn id(self) -> u16 {
+        match self {
+            Self::OnMenuSelectExit => 2,
+            Self::OnMenuSelectStartWithWindows => 1,
+            Self::OnMenuSelectOpenConfig => 3,
+            Self::OnMenuSelectEnterTerminationMode => 4,
+            Self::OnMenuSelectAbout => 5,
+            Self::OnMenuSelectOpenLoggingDirectory => 6,
+            Self::OnMenuSelectCheckForUpdates => 7,
+            Self::OnMenuSelectUpdateOnStartup => 8,
+            Self::OnMenuSelectRelaunchLast => 9,
+            Self::OnMenuSelectExportDiagnostics => 10,
+            Self::OnMenuSelectReloadConfig => 11,
+            Self::OnMenuSelectPauseHotkeys => 12,
+            Self::OnMenuSelectRecent { entry, action } => {
+                debug_assert!(entry < HISTORY_ID_LIMIT, "history entry id out of range");
+
+                RECENT_FIRST_ID + entry * RECENT_ACTION_COUNT + action as u16
+            }
+        }
+    }
+
+    /// Returns the event of the menu item with the specified
+    /// command id, or `None` if no item has that id.
+    #[must_use]
+    pub fn from_id(id: u16) -> Option<Self> {
+        Some(match id {
             1 => Self::OnMenuSelectStartWithWindows,
+            2 => Self::OnMenuSelectExit,
             3 => Self::OnMenuSelectOpenConfig,
             4 => Self::OnMenuSelectEnterTerminationMode,
             5 => Self::OnMenuSelectAbout,
             6 => Self::OnMenuSelectOpenLoggingDirectory,
             7 => Self::OnMenuSelectCheckForUpdates,
             8 => Self::OnMenuSelectUpdateOnStartup,
-            _ => panic!("Invalid enum value '{v}'"),
+            9 => Self::OnMenuSelectRelaunchLast,
+            10 => Self::OnMenuSelectExportDiagnostics,
+            11 => Self::OnMenuSelectReloadConfig,
+            12 => Self::OnMenuSelectPauseHotkeys,
+            _ => {
+                let offset = id.checked_sub(RECENT_FIRST_ID)?;
+                let entry = offset / RECENT_ACTION_COUNT;
+                if entry >= HISTORY_ID_LIMIT {
+                    return None;
+                }
+
+                Self::OnMenuSelectRecent {
+                    entry,
+                    action: RecentAction::ALL[usize::from(offset % RECENT_ACTION_COUNT)],
+                }
+            }
+        })
+    }
+}
+
+pub trait TrayEventHandler {
+    fn handle(&mut self, event: TrayEvent);
+
+    /// Returns the state the tray menu is built from when it is opened.
+    fn menu_state(&self) -> MenuState;
+}
+
+/// Everything the contents of the tray menu depend on.
+#[derive(Debug, Clone, Default)]
+pub struct MenuState {
+    pub terminate_click_keybind: String,
+    pub terminate_immediate_keybind: String,
+    pub restart_immediate_keybind: String,
+    pub pause_toggle_keybind: String,
+    pub hotkeys_paused: bool,
+
+    /// The most recently terminated processes, most recent first
+    pub recent: Vec<HistoryEntry>,
+    pub autostart: bool,
+    pub autoupdate: bool,
+}
+
+/// Builds the tray menu for the given state.
+#[must_use]
+pub fn build_menu(state: &MenuState) -> Menu {
+    let last_relaunchable = state.recent.iter().find(|entry| entry.launch.is_some());
+
+    let relaunch_label = last_relaunchable.map_or_else(
+        || tr!("tray.menu.relaunch_last"),
+        |entry| tr!("tray.menu.relaunch_named", name = entry.name),
+    );
+
+    Menu::new()
+        .button(tr!("tray.menu.about"), Some(TrayEvent::OnMenuSelectAbout))
+        .button(
+            tr!("tray.menu.check_for_updates"),
+            Some(TrayEvent::OnMenuSelectCheckForUpdates),
+        )
+        .button(
+            tr!("tray.menu.open_logging_directory"),
+            Some(TrayEvent::OnMenuSelectOpenLoggingDirectory),
+        )
+        .button(
+            tr!("tray.menu.export_diagnostics"),
+            Some(TrayEvent::OnMenuSelectExportDiagnostics),
+        )
+        .button(
+            tr!("tray.menu.edit_config"),
+            Some(TrayEvent::OnMenuSelectOpenConfig),
+        )
+        .button(
+            tr!("tray.menu.reload_config"),
+            Some(TrayEvent::OnMenuSelectReloadConfig),
+        )
+        .separator()
+        .button(
+            tr!(
+                "tray.menu.enter_termination_mode",
+                keybind = state.terminate_click_keybind
+            ),
+            Some(TrayEvent::OnMenuSelectEnterTerminationMode),
+        )
+        .button(
+            tr!(
+                "tray.menu.terminate_active",
+                keybind = state.terminate_immediate_keybind
+            ),
+            None,
+        )
+        .button(
+            tr!(
+                "tray.menu.restart_active",
+                keybind = state.restart_immediate_keybind
+            ),
+            None,
+        )
+        .button(
+            relaunch_label,
+            last_relaunchable.map(|_| TrayEvent::OnMenuSelectRelaunchLast),
+        )
+        .submenu(
+            tr!("tray.menu.recently_terminated"),
+            Menu::new().section(
+                state.recent.iter().map(recent_entry),
+                Some(&tr!("tray.menu.no_terminated")),
+            ),
+        )
+        .separator()
+        .checkbox(
+            tr!(
+                "tray.menu.pause_hotkeys",
+                keybind = state.pause_toggle_keybind
+            ),
+            state.hotkeys_paused,
+            TrayEvent::OnMenuSelectPauseHotkeys,
+        )
+        .checkbox(
+            tr!("tray.menu.start_with_windows"),
+            state.autostart,
+            TrayEvent::OnMenuSelectStartWithWindows,
+        )
+        .checkbox(
+            tr!("tray.menu.update_on_startup"),
+            state.autoupdate,
+            TrayEvent::OnMenuSelectUpdateOnStartup,
+        )
+        .separator()
+        .button(tr!("tray.menu.exit"), Some(TrayEvent::OnMenuSelectExit))
+}
+
+/// Builds the submenu of an entry of the "Recently terminated" submenu.
+fn recent_entry(entry: &HistoryEntry) -> MenuItem {
+    let event = |action| TrayEvent::OnMenuSelectRecent {
+        entry: entry.id,
+        action,
+    };
+
+    let has_path = !entry.path.is_empty();
+
+    MenuItem::Submenu {
+        label: tr!(
+            "tray.recent.entry",
+            time = entry.time.format("%H:%M:%S"),
+            name = entry.name,
+            outcome = tr!(match entry.outcome {
+                TerminationOutcome::Exited => "tray.outcome.exited",
+                TerminationOutcome::StillRunning => "tray.outcome.still_running",
+                TerminationOutcome::Zombie => "tray.outcome.zombie",
+            })
+        ),
+        menu: Menu::new()
+            .button(
+                tr!("tray.recent.relaunch"),
+                entry.launch.as_ref().map(|_| event(RecentAction::Relaunch)),
+            )
+            .button(
+                tr!("tray.recent.copy_path"),
+                has_path.then(|| event(RecentAction::CopyPath)),
+            )
+            .button(
+                tr!("tray.recent.open_folder"),
+                has_path.then(|| event(RecentAction::OpenFolder)),
+            )
+            .separator()
+            .button(
+                tr!("tray.recent.protect"),
+                Some(event(RecentAction::Protect)),
+            ),
+    }
+}
+
+/// Returns the icon displayed next to the text of a tray notification.
+fn infotip_flags(severity: Severity) -> NOTIFY_ICON_INFOTIP_FLAGS {
+    match severity {
+        Severity::Information => NIIF_INFO,
+        Severity::Warning => NIIF_WARNING,
+        Severity::Error => NIIF_ERROR,
+    }
+}
+
+/// Displays non-blocking balloon notifications through xterminate's tray icon.
+/// Unlike [`Tray`] itself, it can be freely copied and handed out.
+#[derive(Clone, Copy)]
+pub struct TrayNotifier {
+    hwnd: HWND,
+}
+
+impl Notifier for TrayNotifier {
+    /// Shows a notification next to the tray icon. Messages
+    /// longer than 255 UTF-16 code units are truncated.
+    fn notify(&self, notification: &Notification) {
+        let mut nid = NOTIFYICONDATAW {
+            cbSize: u32::try_from(std::mem::size_of::<NOTIFYICONDATAW>()).unwrap(),
+            hWnd: self.hwnd,
+            uID: TRAYICON_ID,
+            uFlags: NIF_INFO,
+            dwInfoFlags: infotip_flags(notification.severity),
+            ..Default::default()
+        };
+
+        copy_truncated(&mut nid.szInfoTitle, "xterminate");
+        copy_truncated(&mut nid.szInfo, &notification.message);
+
+        if !unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid) }.as_bool() {
+            logf!(
+                "ERROR: failed to show tray notification '{}'",
+                notification.message
+            );
         }
     }
 }
 
+/// Copies as much of `text` as fits into the nul-terminated UTF-16 `buffer`.
+fn copy_truncated(buffer: &mut [u16], text: &str) {
+    let mut length = 0;
+
+    // Do not cut surrogate pairs in half
+    for c in text.chars() {
+        if length + c.len_utf16() >= buffer.len() {
+            break;
+        }
+
+        length += c.encode_utf16(&mut buffer[length..]).len();
+    }
+
+    buffer[length] = 0;
+}
+
 pub struct Tray {
     hwnd: HWND,
-    nid: NOTIFYICONDATAA,
+    nid: NOTIFYICONDATAW,
+
+    /// The loaded icons, in the order of [`TrayIcon::ALL`]
+    icons: Vec<HICON>,
+
+    /// The appearance last applied by [`Self::set_appearance`]
+    appearance: Option<Appearance>,
 
     event_handler: Rc<RefCell<dyn TrayEventHandler>>,
-    keybinds: HashMap<String, Keybind>,
 }
 
 impl Drop for Tray {
@@ -73,19 +370,26 @@ impl Drop for Tray {
 }
 
 impl Tray {
-    pub fn create(
-        icon_filename: &str,
-        event_handler: Rc<RefCell<dyn TrayEventHandler>>,
-        keybinds: HashMap<String, Keybind>,
-    ) -> Rc<RefCell<Self>> {
+    /// Creates the tray icon, loading the icons from the `res` folder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window or any of the icons cannot be created.
+    pub fn create(event_handler: Rc<RefCell<dyn TrayEventHandler>>) -> Rc<RefCell<Self>> {
+        let icons = TrayIcon::ALL
+            .iter()
+            .map(|icon| Self::load_icon_from_file(&app::resource_path(icon.filename())))
+            .collect::<Vec<HICON>>();
+
         let hwnd = Self::create_window();
-        let nid = Self::create_trayicon(hwnd, icon_filename);
+        let nid = Self::create_trayicon(hwnd, icons[TrayIcon::Normal as usize]);
 
         let tray = Rc::new(RefCell::new(Self {
             hwnd,
             nid,
+            icons,
+            appearance: None,
             event_handler,
-            keybinds,
         }));
 
         // Todo: Move this into create_window()?
@@ -102,18 +406,28 @@ impl Tray {
 
     /// # Panics
     ///
-    /// Panics if [`Shell_NotifyIconA`] fails.
+    /// Panics if [`Shell_NotifyIconW`] fails.
     pub fn delete(&self) {
         unsafe {
             assert!(
-                Shell_NotifyIconA(NIM_DELETE, &self.nid).as_bool(),
+                Shell_NotifyIconW(NIM_DELETE, &self.nid).as_bool(),
                 "tray icon could not be deleted"
             );
 
             DestroyWindow(self.hwnd).unwrap();
+
+            for icon in &self.icons {
+                let _ = DestroyIcon(*icon);
+            }
         }
     }
 
+    /// Returns a [`TrayNotifier`] for showing notifications through this tray icon.
+    #[must_use]
+    pub fn notifier(&self) -> TrayNotifier {
+        TrayNotifier { hwnd: self.hwnd }
+    }
+
     fn create_window() -> HWND {
         unsafe {
             let class_name =
@@ -159,100 +473,53 @@ impl Tray {
         }
     }
 
-    fn create_trayicon(hwnd: HWND, icon_filename: &str) -> NOTIFYICONDATAA {
+    fn create_trayicon(hwnd: HWND, icon: HICON) -> NOTIFYICONDATAW {
         unsafe {
-            let mut nid = NOTIFYICONDATAA {
-                cbSize: u32::try_from(std::mem::size_of::<NOTIFYICONDATAA>()).unwrap(),
+            let mut nid = NOTIFYICONDATAW {
+                cbSize: u32::try_from(std::mem::size_of::<NOTIFYICONDATAW>()).unwrap(),
                 hWnd: hwnd,
                 uID: TRAYICON_ID,
                 uFlags: NIF_MESSAGE | NIF_ICON | NIF_TIP,
                 uCallbackMessage: WM_USER_TRAYICON,
-                hIcon: Self::load_icon_from_file(icon_filename),
+                hIcon: icon,
                 ..Default::default()
             };
 
-            // NIF_TIP
-            // str to CHAR array conversion
-            let tooltip_str = "xterminate says hi! :)";
-            assert!(
-                tooltip_str.len() < 128,
-                "tooltip cannot be more than 127 characters!"
-            );
-
-            let mut tooltip_array = [0u8; 128];
-            tooltip_array[..tooltip_str.len()].copy_from_slice(tooltip_str.as_bytes());
-
-            nid.szTip = tooltip_array;
+            // The tooltip is replaced by the first call to `set_appearance`
+            copy_truncated(&mut nid.szTip, "xterminate");
 
             logf!("Creating system tray icon");
-            Shell_NotifyIconA(NIM_ADD, &nid);
+            Shell_NotifyIconW(NIM_ADD, &nid);
 
             nid
         }
     }
 
-    fn show_menu(&mut self) {
-        let terminate_click_keybind = self.keybinds.get("terminate_click").unwrap().to_string();
-        let terminate_immediate_keybind = self
-            .keybinds
-            .get("terminate_immediate")
-            .unwrap()
-            .to_string();
-
-        let autostart_enabled = registry::exists(
-            registry::HKey::HKeyCurrentUser,
-            "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run",
-            Some("xterminate"),
-        );
+    /// Changes the icon and tooltip of the tray icon, unless they
+    /// are the same as the last time this was called. Tooltips
+    /// longer than 127 UTF-16 code units are truncated.
+    pub fn set_appearance(&mut self, appearance: Appearance) {
+        if self.appearance.as_ref() == Some(&appearance) {
+            return;
+        }
 
-        let autoupdate_enabled = !registry::exists(
-            registry::HKey::HKeyCurrentUser,
-            "SOFTWARE\\xterminate",
-            Some("autoupdate"),
-        );
+        logf!(Debug: "Changing tray icon appearance to {appearance:?}");
 
-        let menu = menu::TrayMenu::new(self.hwnd)
-            .add_button("About xterminate...", Some(TrayEvent::OnMenuSelectAbout))
-            .add_button(
-                "Check for updates...",
-                Some(TrayEvent::OnMenuSelectCheckForUpdates),
-            )
-            .add_button(
-                "Open logging directory...",
-                Some(TrayEvent::OnMenuSelectOpenLoggingDirectory),
-            )
-            .add_button("Edit config...", Some(TrayEvent::OnMenuSelectOpenConfig))
-            .add_separator()
-            .add_button(
-                format!("Enter termination mode ({terminate_click_keybind})").as_str(),
-                Some(TrayEvent::OnMenuSelectEnterTerminationMode),
-            )
-            .add_button(
-                format!("Terminate active window ({terminate_immediate_keybind})").as_str(),
-                None,
-            )
-            .add_separator()
-            .add_button(
-                if autostart_enabled {
-                    "Disable autostart"
-                } else {
-                    "Enable autostart"
-                },
-                Some(TrayEvent::OnMenuSelectStartWithWindows),
-            )
-            .add_button(
-                if autoupdate_enabled {
-                    "Disable update check on startup"
-                } else {
-                    "Enable update check on startup"
-                },
-                Some(TrayEvent::OnMenuSelectUpdateOnStartup),
-            )
-            .add_separator()
-            .add_button("Exit xterminate", Some(TrayEvent::OnMenuSelectExit))
-            .build();
+        self.nid.uFlags = NIF_ICON | NIF_TIP;
+        self.nid.hIcon = self.icons[appearance.icon as usize];
+        copy_truncated(&mut self.nid.szTip, &appearance.tooltip);
 
-        menu.show();
+        if !unsafe { Shell_NotifyIconW(NIM_MODIFY, &self.nid) }.as_bool() {
+            logf!("ERROR: failed to change tray icon appearance");
+        }
+
+        self.appearance = Some(appearance);
+    }
+
+    fn show_menu(&mut self) {
+        let state = self.event_handler.borrow().menu_state();
+
+        menu::show(self.hwnd, &build_menu(&state));
     }
 
     pub fn poll(&self) {
@@ -317,15 +584,18 @@ unsafe extern "system" fn trayicon_input_callback(
         },
 
         WM_COMMAND => {
-            // Separate the first and last 2 bytes (4 bits) of wparam, equivalent to LOWORD()/HIWORD()
-            // The low bytes tell us which of the popup menu's items were clicked ('command'), and
-            // correspond to the WMU_XXX events defined at the top of this file.
+            // The low word of wparam holds the command id of the clicked menu item, see
+            // `TrayEvent::id`. The high word is 0 for menus and must not affect the id.
+            let id = u16::try_from(wparam.0 & 0xFFFF).unwrap();
 
-            // let id = (wparam.0 & 0b11110000) as u16;
-            let cmd = u16::try_from(wparam.0 & 0b0000_1111).unwrap();
+            match TrayEvent::from_id(id) {
+                Some(event) => {
+                    let handler = &mut instance.as_mut().unwrap().event_handler;
+                    handler.borrow_mut().handle(event);
+                }
 
-            let handler = &mut instance.as_mut().unwrap().event_handler;
-            handler.borrow_mut().handle(TrayEvent::from(cmd));
+                None => logf!("WARNING: ignoring unknown tray menu command (id: {id})"),
+            }
 
             LRESULT(0)
         }
@@ -336,3 +606,215 @@ unsafe extern "system" fn trayicon_input_callback(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Locale;
+    use crate::process::LaunchSpec;
+
+    fn use_english() {
+        let _ = crate::locale::configure(&Locale {
+            language: Some(String::from("en")),
+        });
+    }
+
+    fn entry(id: u16, name: &str, path: &str, relaunchable: bool) -> HistoryEntry {
+        let launch = relaunchable.then(|| LaunchSpec {
+            path: path.into(),
+            command_line: name.into(),
+            working_directory: "C:\\".into(),
+            environment: vec![0, 0],
+        });
+
+        HistoryEntry {
+            id,
+            ..HistoryEntry::new(
+                name.to_owned(),
+                1000 + u32::from(id),
+                path.to_owned(),
+                TerminationOutcome::Exited,
+                launch,
+            )
+        }
+    }
+
+    /// Returns the event of the button labeled `label`, panicking if there is none.
+    fn button(menu: &Menu, label: &str) -> Option<TrayEvent> {
+        menu.items
+            .iter()
+            .find_map(|item| match item {
+                MenuItem::Button { label: l, event } if l == label => Some(*event),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no button labeled {label:?} in {menu:?}"))
+    }
+
+    fn recent_submenu(menu: &Menu) -> &Menu {
+        let label = tr!("tray.menu.recently_terminated");
+
+        menu.items
+            .iter()
+            .find_map(|item| match item {
+                MenuItem::Submenu { label: l, menu } if *l == label => Some(menu),
+                _ => None,
+            })
+            .expect("no recently terminated submenu")
+    }
+
+    #[test]
+    fn builds_menu_without_history() {
+        use_english();
+
+        let menu = build_menu(&MenuState {
+            hotkeys_paused: true,
+            autoupdate: true,
+            ..MenuState::default()
+        });
+
+        assert_eq!(button(&menu, &tr!("tray.menu.relaunch_last")), None);
+        assert_eq!(
+            button(&menu, &tr!("tray.menu.about")),
+            Some(TrayEvent::OnMenuSelectAbout)
+        );
+        assert_eq!(
+            button(&menu, &tr!("tray.menu.terminate_active", keybind = "")),
+            None
+        );
+        assert_eq!(
+            button(&menu, &tr!("tray.menu.restart_active", keybind = "")),
+            None
+        );
+
+        assert_eq!(
+            recent_submenu(&menu).items,
+            [MenuItem::Button {
+                label: tr!("tray.menu.no_terminated"),
+                event: None,
+            }]
+        );
+
+        let checked: Vec<(TrayEvent, bool)> = menu
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                MenuItem::Checkbox { checked, event, .. } => Some((*event, *checked)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            checked,
+            [
+                (TrayEvent::OnMenuSelectPauseHotkeys, true),
+                (TrayEvent::OnMenuSelectStartWithWindows, false),
+                (TrayEvent::OnMenuSelectUpdateOnStartup, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn relaunches_most_recent_relaunchable_entry() {
+        use_english();
+
+        let menu = build_menu(&MenuState {
+            recent: vec![
+                entry(7, "unknown.exe", "", false),
+                entry(6, "notepad.exe", "C:\\Windows\\notepad.exe", true),
+                entry(5, "calc.exe", "C:\\Windows\\calc.exe", true),
+            ],
+            ..MenuState::default()
+        });
+
+        assert_eq!(
+            button(
+                &menu,
+                &tr!("tray.menu.relaunch_named", name = "notepad.exe")
+            ),
+            Some(TrayEvent::OnMenuSelectRelaunchLast)
+        );
+        assert_eq!(recent_submenu(&menu).items.len(), 3);
+    }
+
+    #[test]
+    fn builds_recent_entries() {
+        use_english();
+
+        let recent = |action| TrayEvent::OnMenuSelectRecent { entry: 3, action };
+
+        let MenuItem::Submenu { menu: full, .. } =
+            recent_entry(&entry(3, "notepad.exe", "C:\\Windows\\notepad.exe", true))
+        else {
+            panic!("recent entry is not a submenu");
+        };
+
+        assert_eq!(
+            button(&full, &tr!("tray.recent.relaunch")),
+            Some(recent(RecentAction::Relaunch))
+        );
+        assert_eq!(
+            button(&full, &tr!("tray.recent.copy_path")),
+            Some(recent(RecentAction::CopyPath))
+        );
+        assert_eq!(
+            button(&full, &tr!("tray.recent.open_folder")),
+            Some(recent(RecentAction::OpenFolder))
+        );
+        assert_eq!(
+            button(&full, &tr!("tray.recent.protect")),
+            Some(recent(RecentAction::Protect))
+        );
+
+        let MenuItem::Submenu { label, menu: bare } =
+            recent_entry(&entry(3, "unknown.exe", "", false))
+        else {
+            panic!("recent entry is not a submenu");
+        };
+
+        assert!(label.contains("unknown.exe"), "{label}");
+        assert_eq!(button(&bare, &tr!("tray.recent.relaunch")), None);
+        assert_eq!(button(&bare, &tr!("tray.recent.copy_path")), None);
+        assert_eq!(button(&bare, &tr!("tray.recent.open_folder")), None);
+        assert_eq!(
+            button(&bare, &tr!("tray.recent.protect")),
+            Some(recent(RecentAction::Protect))
+        );
+    }
+
+    #[test]
+    fn truncates_wide_text() {
+        let mut buffer = [0xFFFF; 8];
+
+        copy_truncated(&mut buffer, "Prozess beendet");
+        assert_eq!(String::from_utf16(&buffer[..7]).unwrap(), "Prozess");
+        assert_eq!(buffer[7], 0);
+
+        copy_truncated(&mut buffer, "Größe");
+        assert_eq!(String::from_utf16(&buffer[..5]).unwrap(), "Größe");
+        assert_eq!(buffer[5], 0);
+
+        // A character outside the BMP takes two code units and is left out
+        // entirely rather than cut in half
+        copy_truncated(&mut buffer, "abcdef🚀");
+        assert_eq!(String::from_utf16(&buffer[..6]).unwrap(), "abcdef");
+        assert_eq!(buffer[6], 0);
+    }
+
+    #[test]
+    fn relaunch_does_not_require_path() {
+        use_english();
+
+        let MenuItem::Submenu { menu, .. } = recent_entry(&entry(1, "app.exe", "", true)) else {
+            panic!("recent entry is not a submenu");
+        };
+
+        assert_eq!(
+            button(&menu, &tr!("tray.recent.relaunch")),
+            Some(TrayEvent::OnMenuSelectRecent {
+                entry: 1,
+                action: RecentAction::Relaunch,
+            })
+        );
+        assert_eq!(button(&menu, &tr!("tray.recent.copy_path")), None);
+    }
+}
@@ -0,0 +1,466 @@
+//! A description of the tray popup menu that is independent of Win32. The
+//! menu is built as plain data and turned into a native menu by a
+//! [`MenuBackend`], so its contents can be inspected without showing it.
+
+use super::TrayEvent;
+
+/// An entry of a [`Menu`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuItem {
+    /// A button that generates `event` when clicked, or
+    /// is greyed out if `event` is `None`.
+    Button {
+        label: String,
+        event: Option<TrayEvent>,
+    },
+
+    /// A button with a check mark next to it if `checked` is true.
+    Checkbox {
+        label: String,
+        checked: bool,
+        event: TrayEvent,
+    },
+
+    /// A horizontal line between items.
+    Separator,
+
+    /// An item that opens another menu when hovered. Empty
+    /// submenus are greyed out since they cannot be opened.
+    Submenu { label: String, menu: Menu },
+}
+
+/// A list of [`MenuItem`]s, built using the methods below.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Menu {
+    pub items: Vec<MenuItem>,
+}
+
+impl Menu {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a button. If `event` is `None`, the button is greyed out.
+    #[must_use]
+    pub fn button(mut self, label: impl Into<String>, event: Option<TrayEvent>) -> Self {
+        self.items.push(MenuItem::Button {
+            label: label.into(),
+            event,
+        });
+
+        self
+    }
+
+    #[must_use]
+    pub fn checkbox(mut self, label: impl Into<String>, checked: bool, event: TrayEvent) -> Self {
+        self.items.push(MenuItem::Checkbox {
+            label: label.into(),
+            checked,
+            event,
+        });
+
+        self
+    }
+
+    /// Adds a separator. Separators at the start or end of the menu and
+    /// directly after another separator are left out, so that sections
+    /// which turn out empty do not leave gaps.
+    #[must_use]
+    pub fn separator(mut self) -> Self {
+        if self
+            .items
+            .last()
+            .is_some_and(|item| *item != MenuItem::Separator)
+        {
+            self.items.push(MenuItem::Separator);
+        }
+
+        self
+    }
+
+    #[must_use]
+    pub fn submenu(mut self, label: impl Into<String>, menu: Menu) -> Self {
+        self.items.push(MenuItem::Submenu {
+            label: label.into(),
+            menu: menu.trimmed(),
+        });
+
+        self
+    }
+
+    /// Adds a section whose items are only known when the menu is shown, such
+    /// as the contents of a list. If there are no items, a greyed out button
+    /// labelled `placeholder` is added instead, unless it is `None`.
+    #[must_use]
+    pub fn section(
+        mut self,
+        items: impl IntoIterator<Item = MenuItem>,
+        placeholder: Option<&str>,
+    ) -> Self {
+        let length = self.items.len();
+        self.items.extend(items);
+
+        if self.items.len() == length {
+            if let Some(placeholder) = placeholder {
+                return self.button(placeholder, None);
+            }
+        }
+
+        self
+    }
+
+    /// Removes a trailing separator.
+    #[must_use]
+    pub fn trimmed(mut self) -> Self {
+        if self.items.last() == Some(&MenuItem::Separator) {
+            self.items.pop();
+        }
+
+        self
+    }
+
+    /// Returns every event that can be generated by this menu and
+    /// its submenus, in the order the items appear in.
+    #[must_use]
+    pub fn events(&self) -> Vec<TrayEvent> {
+        self.items
+            .iter()
+            .flat_map(|item| match item {
+                MenuItem::Button { event, .. } => event.iter().copied().collect(),
+                MenuItem::Checkbox { event, .. } => vec![*event],
+                MenuItem::Separator => Vec::new(),
+                MenuItem::Submenu { menu, .. } => menu.events(),
+            })
+            .collect()
+    }
+
+    /// Returns the item that generates `event`, searching submenus as well.
+    #[must_use]
+    pub fn find(&self, event: TrayEvent) -> Option<&MenuItem> {
+        self.items.iter().find_map(|item| match item {
+            MenuItem::Button {
+                event: Some(item_event),
+                ..
+            }
+            | MenuItem::Checkbox {
+                event: item_event, ..
+            } if *item_event == event => Some(item),
+            MenuItem::Submenu { menu, .. } => menu.find(event),
+            _ => None,
+        })
+    }
+}
+
+/// The state of a menu item as passed to a [`MenuBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ItemState {
+    pub enabled: bool,
+    pub checked: bool,
+}
+
+/// Creates the native menu for a [`Menu`]. Labels are passed with every
+/// `&` doubled, see [`render`].
+pub trait MenuBackend {
+    type Handle: Copy;
+
+    /// Creates a new, empty menu.
+    fn create(&mut self) -> Self::Handle;
+
+    /// Appends a clickable item that reports `id` when clicked.
+    fn append_item(&mut self, menu: Self::Handle, id: u16, label: &str, state: ItemState);
+
+    fn append_separator(&mut self, menu: Self::Handle);
+
+    /// Appends an item that opens `submenu` when hovered.
+    fn append_submenu(
+        &mut self,
+        menu: Self::Handle,
+        submenu: Self::Handle,
+        label: &str,
+        state: ItemState,
+    );
+}
+
+/// Creates the native menu for `menu` and its submenus through `backend`
+/// and returns the handle of the top-level menu. Items are identified by
+/// the ids of their events, see [`TrayEvent::id`].
+///
+/// A single `&` in a native menu label underlines the next character, so
+/// labels containing e.g. process names are escaped to be displayed as written.
+pub fn render<B: MenuBackend>(menu: &Menu, backend: &mut B) -> B::Handle {
+    let handle = backend.create();
+
+    for item in &menu.items {
+        let label = match item {
+            MenuItem::Button { label, .. }
+            | MenuItem::Checkbox { label, .. }
+            | MenuItem::Submenu { label, .. } => label.replace('&', "&&"),
+            MenuItem::Separator => String::new(),
+        };
+
+        match item {
+            MenuItem::Button { event, .. } => backend.append_item(
+                handle,
+                event.map_or(0, TrayEvent::id),
+                &label,
+                ItemState {
+                    enabled: event.is_some(),
+                    checked: false,
+                },
+            ),
+
+            MenuItem::Checkbox { checked, event, .. } => backend.append_item(
+                handle,
+                event.id(),
+                &label,
+                ItemState {
+                    enabled: true,
+                    checked: *checked,
+                },
+            ),
+
+            MenuItem::Separator => backend.append_separator(handle),
+
+            MenuItem::Submenu { menu, .. } => {
+                let submenu = render(menu, backend);
+
+                backend.append_submenu(
+                    handle,
+                    submenu,
+                    &label,
+                    ItemState {
+                        enabled: !menu.items.is_empty(),
+                        checked: false,
+                    },
+                );
+            }
+        }
+    }
+
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HISTORY_ID_LIMIT;
+    use crate::tray::RecentAction;
+
+    /// An item appended to a menu of the [`Recorder`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Recorded {
+        Item(u16, String, ItemState),
+        Separator,
+        Submenu(usize, String, ItemState),
+    }
+
+    /// Records the menus it is asked to create, identified by their index.
+    #[derive(Default)]
+    struct Recorder {
+        menus: Vec<Vec<Recorded>>,
+    }
+
+    impl MenuBackend for Recorder {
+        type Handle = usize;
+
+        fn create(&mut self) -> usize {
+            self.menus.push(Vec::new());
+            self.menus.len() - 1
+        }
+
+        fn append_item(&mut self, menu: usize, id: u16, label: &str, state: ItemState) {
+            self.menus[menu].push(Recorded::Item(id, label.to_owned(), state));
+        }
+
+        fn append_separator(&mut self, menu: usize) {
+            self.menus[menu].push(Recorded::Separator);
+        }
+
+        fn append_submenu(&mut self, menu: usize, submenu: usize, label: &str, state: ItemState) {
+            self.menus[menu].push(Recorded::Submenu(submenu, label.to_owned(), state));
+        }
+    }
+
+    const ENABLED: ItemState = ItemState {
+        enabled: true,
+        checked: false,
+    };
+
+    const DISABLED: ItemState = ItemState {
+        enabled: false,
+        checked: false,
+    };
+
+    fn recent(entry: u16, action: RecentAction) -> TrayEvent {
+        TrayEvent::OnMenuSelectRecent { entry, action }
+    }
+
+    #[test]
+    fn round_trips_event_ids() {
+        let events = [
+            TrayEvent::OnMenuSelectExit,
+            TrayEvent::OnMenuSelectStartWithWindows,
+            TrayEvent::OnMenuSelectOpenConfig,
+            TrayEvent::OnMenuSelectEnterTerminationMode,
+            TrayEvent::OnMenuSelectAbout,
+            TrayEvent::OnMenuSelectOpenLoggingDirectory,
+            TrayEvent::OnMenuSelectCheckForUpdates,
+            TrayEvent::OnMenuSelectUpdateOnStartup,
+            TrayEvent::OnMenuSelectRelaunchLast,
+            TrayEvent::OnMenuSelectExportDiagnostics,
+            TrayEvent::OnMenuSelectReloadConfig,
+            TrayEvent::OnMenuSelectPauseHotkeys,
+        ];
+
+        for event in events {
+            assert_ne!(event.id(), 0, "{event:?}");
+            assert_eq!(TrayEvent::from_id(event.id()), Some(event));
+        }
+    }
+
+    #[test]
+    fn round_trips_recent_ids() {
+        let actions = [
+            RecentAction::Relaunch,
+            RecentAction::CopyPath,
+            RecentAction::OpenFolder,
+            RecentAction::Protect,
+        ];
+
+        for entry in [0, 1, HISTORY_ID_LIMIT / 2, HISTORY_ID_LIMIT - 1] {
+            for action in actions {
+                let event = recent(entry, action);
+                assert_eq!(TrayEvent::from_id(event.id()), Some(event));
+            }
+        }
+
+        assert_eq!(
+            recent(0, RecentAction::Relaunch).id(),
+            super::super::RECENT_FIRST_ID
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_ids() {
+        let last = recent(HISTORY_ID_LIMIT - 1, RecentAction::Protect).id();
+
+        assert_eq!(TrayEvent::from_id(0), None);
+        assert_eq!(TrayEvent::from_id(13), None);
+        assert_eq!(TrayEvent::from_id(super::super::RECENT_FIRST_ID - 1), None);
+        assert_eq!(TrayEvent::from_id(last + 1), None);
+        assert_eq!(TrayEvent::from_id(u16::MAX), None);
+    }
+
+    #[test]
+    fn renders_items_and_submenus() {
+        let menu = Menu::new()
+            .button("About", Some(TrayEvent::OnMenuSelectAbout))
+            .button("Greyed out", None)
+            .separator()
+            .checkbox("Paused", true, TrayEvent::OnMenuSelectPauseHotkeys)
+            .submenu(
+                "Recent",
+                Menu::new().button("Protect", Some(recent(3, RecentAction::Protect))),
+            )
+            .submenu("Empty", Menu::new());
+
+        let mut recorder = Recorder::default();
+        let handle = render(&menu, &mut recorder);
+
+        assert_eq!(handle, 0);
+        assert_eq!(
+            recorder.menus[0],
+            [
+                Recorded::Item(
+                    TrayEvent::OnMenuSelectAbout.id(),
+                    String::from("About"),
+                    ENABLED
+                ),
+                Recorded::Item(0, String::from("Greyed out"), DISABLED),
+                Recorded::Separator,
+                Recorded::Item(
+                    TrayEvent::OnMenuSelectPauseHotkeys.id(),
+                    String::from("Paused"),
+                    ItemState {
+                        enabled: true,
+                        checked: true,
+                    }
+                ),
+                Recorded::Submenu(1, String::from("Recent"), ENABLED),
+                Recorded::Submenu(2, String::from("Empty"), DISABLED),
+            ]
+        );
+        assert_eq!(
+            recorder.menus[1],
+            [Recorded::Item(
+                recent(3, RecentAction::Protect).id(),
+                String::from("Protect"),
+                ENABLED
+            )]
+        );
+        assert!(recorder.menus[2].is_empty());
+    }
+
+    #[test]
+    fn escapes_ampersands() {
+        let menu = Menu::new()
+            .button("Tom & Jerry.exe", None)
+            .checkbox("A&&B", false, TrayEvent::OnMenuSelectStartWithWindows)
+            .submenu("R&D", Menu::new());
+
+        let mut recorder = Recorder::default();
+        render(&menu, &mut recorder);
+
+        let labels: Vec<&str> = recorder.menus[0]
+            .iter()
+            .map(|item| match item {
+                Recorded::Item(_, label, _) | Recorded::Submenu(_, label, _) => label.as_str(),
+                Recorded::Separator => "",
+            })
+            .collect();
+
+        assert_eq!(labels, ["Tom && Jerry.exe", "A&&&&B", "R&&D"]);
+    }
+
+    #[test]
+    fn collapses_separators() {
+        let menu = Menu::new()
+            .separator()
+            .button("First", None)
+            .separator()
+            .separator()
+            .button("Second", None)
+            .separator()
+            .trimmed();
+
+        let mut recorder = Recorder::default();
+        render(&menu, &mut recorder);
+
+        assert_eq!(
+            recorder.menus[0],
+            [
+                Recorded::Item(0, String::from("First"), DISABLED),
+                Recorded::Separator,
+                Recorded::Item(0, String::from("Second"), DISABLED),
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_placeholder_to_empty_section() {
+        let empty = Menu::new().section(Vec::new(), Some("Nothing"));
+        assert_eq!(
+            empty.items,
+            [MenuItem::Button {
+                label: String::from("Nothing"),
+                event: None,
+            }]
+        );
+
+        let filled = Menu::new().section([MenuItem::Separator], Some("Nothing"));
+        assert_eq!(filled.items, [MenuItem::Separator]);
+
+        assert!(Menu::new().section(Vec::new(), None).items.is_empty());
+    }
+}
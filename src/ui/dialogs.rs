@@ -0,0 +1,265 @@
+//! Modal dialogs shown to the user, behind the [`Dialogs`] trait so that flows
+//! involving a prompt, such as update consent, can run without a desktop. The
+//! Win32 implementation is [`TaskDialogs`](super::taskdialog::TaskDialogs),
+//! and [`ScriptedDialogs`] answers with preset answers instead.
+
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU32;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::notification::Severity;
+
+/// The text of a dialog, which all kinds of dialogs share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialog {
+    /// Decides the icon of the dialog
+    pub severity: Severity,
+    pub title: String,
+    pub heading: String,
+    pub content: String,
+
+    /// Shown in a collapsed section, for stack traces or process details
+    pub details: Option<String>,
+
+    /// Whether `<a href="...">` tags in the content are shown as links
+    pub hyperlinks: bool,
+}
+
+impl Dialog {
+    #[must_use]
+    pub fn new(
+        severity: Severity,
+        title: impl Into<String>,
+        heading: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            title: title.into(),
+            heading: heading.into(),
+            content: content.into(),
+            details: None,
+            hyperlinks: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_hyperlinks(mut self) -> Self {
+        self.hyperlinks = true;
+        self
+    }
+}
+
+/// A checkbox shown below a prompt, such as "Don't ask again".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub label: String,
+
+    /// Whether the checkbox is checked when the dialog is shown
+    pub checked: bool,
+}
+
+/// One of the options of a [`Dialogs::choice`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    /// Reported in the [`Answer`] when chosen, must not be between 1 and 7
+    pub id: i32,
+    pub label: String,
+
+    /// A line explaining the option, shown below its label
+    pub note: Option<String>,
+}
+
+/// Picks an option of a [`Dialogs::choice`] automatically once a number of
+/// seconds have passed, unless the user answers before then.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Countdown {
+    pub seconds: u32,
+
+    /// The id of the [`Choice`] picked when the countdown runs out
+    pub choice: i32,
+
+    /// Shown while counting down, with `{seconds}` replaced
+    /// by the number of seconds remaining
+    pub footer: String,
+}
+
+/// The answer to a prompt and whether its [`Verification`] was checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Answer<T> {
+    pub value: T,
+    pub verified: bool,
+}
+
+/// A dialog showing the progress of a task, see [`Dialogs::progress`].
+pub trait ProgressDialog {
+    /// Closes the dialog once the task is done. Returns false if the user
+    /// dismissed the dialog before the progress was complete.
+    fn finish(self: Box<Self>) -> bool;
+}
+
+/// Shows modal dialogs to the user. Dialogs may be shown from any thread, e.g.
+/// so that a prompt does not hold up the event loop while it is open.
+pub trait Dialogs: Send + Sync {
+    /// Shows `dialog` with an OK button, and waits for it to be dismissed if `wait` is true.
+    fn message(&self, dialog: &Dialog, wait: bool);
+
+    /// Asks a yes or no question, answering true if the user chose yes.
+    fn confirm(&self, dialog: &Dialog, verification: Option<&Verification>) -> Answer<bool>;
+
+    /// Asks the user to pick one of `choices`, answering with the id of the chosen
+    /// option or `None` if the dialog was cancelled.
+    fn choice(
+        &self,
+        dialog: &Dialog,
+        choices: &[Choice],
+        verification: Option<&Verification>,
+        countdown: Option<&Countdown>,
+    ) -> Answer<Option<i32>>;
+
+    /// Shows `dialog` with a progress bar that follows `progress`,
+    /// from 0 to 100, until [`ProgressDialog::finish`] is called.
+    fn progress(&self, dialog: &Dialog, progress: Arc<AtomicU32>) -> Box<dyn ProgressDialog>;
+}
+
+/// A dialog shown through [`ScriptedDialogs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shown {
+    Message(Dialog),
+    Confirm(Dialog, Option<Verification>),
+    Choice(Dialog, Vec<Choice>, Option<Verification>),
+    Progress(Dialog),
+}
+
+#[derive(Debug, Default)]
+struct Script {
+    confirms: VecDeque<Answer<bool>>,
+    choices: VecDeque<Answer<Option<i32>>>,
+    progress: VecDeque<bool>,
+    shown: Vec<Shown>,
+}
+
+/// A [`Dialogs`] implementation that shows nothing. Prompts are answered with
+/// the answers queued beforehand, in order, and every dialog is recorded.
+/// Clones share the same script, so a clone can be kept to inspect what was
+/// shown after passing the original to the code under test.
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct ScriptedDialogs {
+    script: Arc<Mutex<Script>>,
+}
+
+#[allow(dead_code)]
+impl ScriptedDialogs {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues the answer to the next [`Dialogs::confirm`].
+    pub fn answer_confirm(&self, answer: Answer<bool>) -> &Self {
+        self.script().confirms.push_back(answer);
+        self
+    }
+
+    /// Queues the answer to the next [`Dialogs::choice`].
+    pub fn answer_choice(&self, answer: Answer<Option<i32>>) -> &Self {
+        self.script().choices.push_back(answer);
+        self
+    }
+
+    /// Queues whether the next [`Dialogs::progress`] is left open until the
+    /// task is done (true) or dismissed by the user before then (false).
+    pub fn answer_progress(&self, completed: bool) -> &Self {
+        self.script().progress.push_back(completed);
+        self
+    }
+
+    /// Returns every dialog shown so far, oldest first.
+    #[must_use]
+    pub fn shown(&self) -> Vec<Shown> {
+        self.script().shown.clone()
+    }
+
+    fn script(&self) -> MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Dialogs for ScriptedDialogs {
+    fn message(&self, dialog: &Dialog, _wait: bool) {
+        self.script().shown.push(Shown::Message(dialog.clone()));
+    }
+
+    /// # Panics
+    ///
+    /// Panics if no answer was queued with [`ScriptedDialogs::answer_confirm`].
+    fn confirm(&self, dialog: &Dialog, verification: Option<&Verification>) -> Answer<bool> {
+        let mut script = self.script();
+
+        script
+            .shown
+            .push(Shown::Confirm(dialog.clone(), verification.cloned()));
+
+        script
+            .confirms
+            .pop_front()
+            .unwrap_or_else(|| panic!("no scripted answer for confirm '{}'", dialog.heading))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if no answer was queued with [`ScriptedDialogs::answer_choice`].
+    fn choice(
+        &self,
+        dialog: &Dialog,
+        choices: &[Choice],
+        verification: Option<&Verification>,
+        _countdown: Option<&Countdown>,
+    ) -> Answer<Option<i32>> {
+        let mut script = self.script();
+
+        script.shown.push(Shown::Choice(
+            dialog.clone(),
+            choices.to_vec(),
+            verification.cloned(),
+        ));
+
+        script
+            .choices
+            .pop_front()
+            .unwrap_or_else(|| panic!("no scripted answer for choice '{}'", dialog.heading))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if no answer was queued with [`ScriptedDialogs::answer_progress`].
+    fn progress(&self, dialog: &Dialog, _progress: Arc<AtomicU32>) -> Box<dyn ProgressDialog> {
+        let mut script = self.script();
+
+        script.shown.push(Shown::Progress(dialog.clone()));
+
+        let completed = script
+            .progress
+            .pop_front()
+            .unwrap_or_else(|| panic!("no scripted answer for progress '{}'", dialog.heading));
+
+        Box::new(ScriptedProgress { completed })
+    }
+}
+
+struct ScriptedProgress {
+    completed: bool,
+}
+
+impl ProgressDialog for ScriptedProgress {
+    fn finish(self: Box<Self>) -> bool {
+        self.completed
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::UI::Controls::Dialogs::{
+    GetSaveFileNameW, OFN_OVERWRITEPROMPT, OFN_PATHMUSTEXIST, OPENFILENAMEW,
+};
+
+use crate::wide;
+
+/// Shows the common "Save As" dialog and returns the path chosen by the
+/// user, or `None` if the dialog was cancelled.
+///
+/// # Arguments
+///
+/// * `title` - The title of the dialog window.
+/// * `file_name` - The file name the dialog is pre-filled with.
+/// * `filter_name` - A description of the file type, e.g. `Zip archives`.
+/// * `extension` - The file extension without a leading dot, e.g. `zip`.
+#[must_use]
+pub fn save_file(
+    title: &str,
+    file_name: &str,
+    filter_name: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let title = wide::to_wide_nul(title);
+    let extension_wide = wide::to_wide_nul(extension);
+
+    // The filter is a list of nul-separated name and pattern pairs terminated by two nuls
+    let filter = wide::to_wide_nul(format!("{filter_name}\0*.{extension}\0"));
+
+    let mut buffer = vec![0u16; wide::MAX_LONG_PATH];
+    let initial = wide::to_wide_nul(file_name);
+    let length = initial.len().min(buffer.len());
+    buffer[..length].copy_from_slice(&initial[..length]);
+
+    let mut ofn = OPENFILENAMEW {
+        lStructSize: u32::try_from(std::mem::size_of::<OPENFILENAMEW>()).unwrap(),
+        lpstrFilter: PCWSTR(filter.as_ptr()),
+        lpstrFile: PWSTR(buffer.as_mut_ptr()),
+        nMaxFile: u32::try_from(buffer.len()).unwrap(),
+        lpstrTitle: PCWSTR(title.as_ptr()),
+        lpstrDefExt: PCWSTR(extension_wide.as_ptr()),
+        Flags: OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST,
+        ..Default::default()
+    };
+
+    if unsafe { GetSaveFileNameW(&mut ofn) }.as_bool() {
+        Some(PathBuf::from(wide::from_wide(&buffer)))
+    } else {
+        None
+    }
+}
@@ -1 +1,3 @@
+pub mod dialogs;
+pub mod filedialog;
 pub mod taskdialog;
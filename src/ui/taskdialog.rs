@@ -5,6 +5,9 @@ use std::{
     thread::JoinHandle,
 };
 
+use super::dialogs::{self, Answer, Choice, Dialog, Dialogs, ProgressDialog, Verification};
+use crate::notification::Severity;
+
 use windows::{
     core::{w, HRESULT, PCSTR, PCWSTR},
     Win32::{
@@ -12,18 +15,20 @@ use windows::{
         System::LibraryLoader::GetModuleHandleA,
         UI::{
             Controls::{
-                TaskDialogIndirect, TASKDIALOGCONFIG, TASKDIALOGCONFIG_0,
-                TASKDIALOG_COMMON_BUTTON_FLAGS, TASKDIALOG_FLAGS, TDCBF_CANCEL_BUTTON,
-                TDCBF_NO_BUTTON, TDCBF_OK_BUTTON, TDCBF_YES_BUTTON, TDF_CALLBACK_TIMER,
-                TDF_ENABLE_HYPERLINKS, TDF_SHOW_PROGRESS_BAR, TDF_VERIFICATION_FLAG_CHECKED,
-                TDM_SET_PROGRESS_BAR_POS, TDM_SET_PROGRESS_BAR_RANGE, TDN_BUTTON_CLICKED,
-                TDN_DIALOG_CONSTRUCTED, TDN_HYPERLINK_CLICKED, TDN_TIMER, TD_ERROR_ICON,
-                TD_INFORMATION_ICON, TD_SHIELD_ICON, TD_WARNING_ICON,
+                TaskDialogIndirect, TASKDIALOGCONFIG, TASKDIALOGCONFIG_0, TASKDIALOGCONFIG_1,
+                TASKDIALOG_BUTTON, TASKDIALOG_COMMON_BUTTON_FLAGS, TASKDIALOG_FLAGS,
+                TDCBF_CANCEL_BUTTON, TDCBF_NO_BUTTON, TDCBF_OK_BUTTON, TDCBF_YES_BUTTON,
+                TDE_FOOTER, TDF_CALLBACK_TIMER, TDF_ENABLE_HYPERLINKS, TDF_EXPANDED_BY_DEFAULT,
+                TDF_SHOW_PROGRESS_BAR, TDF_USE_COMMAND_LINKS, TDF_VERIFICATION_FLAG_CHECKED,
+                TDM_CLICK_BUTTON, TDM_SET_PROGRESS_BAR_POS, TDM_SET_PROGRESS_BAR_RANGE,
+                TDM_UPDATE_ELEMENT_TEXT, TDN_BUTTON_CLICKED, TDN_DIALOG_CONSTRUCTED,
+                TDN_HYPERLINK_CLICKED, TDN_TIMER, TD_ERROR_ICON, TD_INFORMATION_ICON,
+                TD_SHIELD_ICON, TD_WARNING_ICON,
             },
             Shell::ShellExecuteW,
             WindowsAndMessaging::{
-                EndDialog, PostMessageA, SendMessageA, BN_CLICKED, IDCANCEL, IDNO, IDOK, IDYES,
-                MESSAGEBOX_RESULT, SW_SHOWNORMAL, WM_COMMAND,
+                EndDialog, PostMessageA, SendMessageA, IDCANCEL, IDNO, IDOK, IDYES,
+                MESSAGEBOX_RESULT, SW_SHOWNORMAL,
             },
         },
     },
@@ -66,18 +71,27 @@ impl TaskDialog {
             dialog_heading_utf16_nul: [0u16; 1].to_vec(),
             dialog_content_utf16_nul: [0u16; 1].to_vec(),
             dialog_footer_utf16_nul: [0u16; 1].to_vec(),
+            dialog_details_utf16_nul: None,
+            dialog_details_expanded: false,
             dialog_icon: TaskDialogIcon::NoIcon,
+            dialog_footer_icon: TaskDialogIcon::NoIcon,
             dialog_allow_hyperlinks: false,
             dialog_verification_text_utf16_nul: [0u16; 1].to_vec(),
             initial_verification_value: false,
             dialog_buttons: [].to_vec(),
+            dialog_custom_buttons: [].to_vec(),
+            dialog_command_links: false,
+            dialog_radio_buttons: [].to_vec(),
+            dialog_default_radio_button: None,
+            dialog_countdown: None,
             dialog_progress_source: None,
             dialog_progress_min: 0,
             dialog_progress_max: 100,
         }
     }
 
-    /// Returns the task dialogs window handle ([HWND]) as [isize].
+    /// Returns the task dialogs window handle ([HWND]) as [isize],
+    /// or 0 if the dialog has not been constructed yet.
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub fn handle(&self) -> isize {
@@ -93,29 +107,32 @@ impl TaskDialog {
         rs.join().unwrap()
     }
 
-    /// Sends the specified close action to the dialog and blockingly waits
-    /// for the dialog to exit and return its result.
+    /// Clicks the button resulting in `action` and blockingly waits for
+    /// the dialog to exit and return its result. If the dialog has not been
+    /// constructed yet, this waits for it to be shown first.
     ///
-    /// # Notes
+    /// # Panics
     ///
-    /// The resulting action when this is called is always [`TaskDialogAction::Cancel`].
-    // Todo: Figure out why PostMessage with WM_COMMAND always results in
-    //       IDCANCEL regardless of what command is specified.
-    #[allow(clippy::must_use_candidate, clippy::missing_panics_doc)]
-    pub fn close(self) -> TaskDialogResult {
-        let low_word = u16::try_from(IDOK.0).unwrap();
-        let high_word = u16::try_from(BN_CLICKED).unwrap();
-
-        let dword_wparam = (u32::from(low_word)) | u32::from(high_word) << 16;
+    /// Panics if `action` is [`TaskDialogAction::None`].
+    #[allow(clippy::must_use_candidate)]
+    pub fn close(self, action: TaskDialogAction) -> TaskDialogResult {
+        let id = action.to_id().0;
+
+        // The handle is set by the dialog callback once the dialog has been constructed
+        while self.handle() == 0 && !self.result.is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
 
-        unsafe {
-            PostMessageA(
-                HWND(self.handle()),
-                WM_COMMAND,
-                WPARAM(dword_wparam as usize),
-                LPARAM(0),
-            )
-            .unwrap();
+        if !self.result.is_finished() {
+            unsafe {
+                // Fails if the dialog was dismissed in the meantime, which is fine
+                let _ = PostMessageA(
+                    HWND(self.handle()),
+                    TDM_CLICK_BUTTON.0.try_into().unwrap(),
+                    WPARAM(usize::try_from(id).unwrap()),
+                    LPARAM(0),
+                );
+            }
         }
 
         self.result()
@@ -129,6 +146,10 @@ pub enum TaskDialogAction {
     Yes,
     No,
     Cancel,
+
+    /// A button added with [`TaskDialogBuilder::add_custom_button`]
+    /// or [`TaskDialogBuilder::add_command_link`]
+    Custom(i32),
 }
 
 impl TaskDialogAction {
@@ -139,7 +160,8 @@ impl TaskDialogAction {
             IDYES => TaskDialogAction::Yes,
             IDNO => TaskDialogAction::No,
             IDOK => TaskDialogAction::Ok,
-            _ => TaskDialogAction::None,
+            MESSAGEBOX_RESULT(0) => TaskDialogAction::None,
+            MESSAGEBOX_RESULT(id) => TaskDialogAction::Custom(id),
         }
     }
 
@@ -153,6 +175,7 @@ impl TaskDialogAction {
             TaskDialogAction::Yes => IDYES,
             TaskDialogAction::No => IDNO,
             TaskDialogAction::Ok => IDOK,
+            TaskDialogAction::Custom(id) => MESSAGEBOX_RESULT(*id),
             TaskDialogAction::None => {
                 panic!("conversion failed because `TaskDialogAction::None` is not a valid MESSAGEBOX_RESULT");
             }
@@ -162,7 +185,7 @@ impl TaskDialogAction {
     #[must_use]
     pub fn to_common_button_flag(&self) -> TASKDIALOG_COMMON_BUTTON_FLAGS {
         match self {
-            Self::None => TASKDIALOG_COMMON_BUTTON_FLAGS(0),
+            Self::None | Self::Custom(_) => TASKDIALOG_COMMON_BUTTON_FLAGS(0),
             Self::Yes => TDCBF_YES_BUTTON,
             Self::No => TDCBF_NO_BUTTON,
             Self::Ok => TDCBF_OK_BUTTON,
@@ -171,8 +194,31 @@ impl TaskDialogAction {
     }
 }
 
+/// Clicks a button automatically once a number of seconds have passed.
+#[derive(Clone)]
+struct Countdown {
+    seconds: u32,
+    action: TaskDialogAction,
+
+    /// The footer shown while counting down, with `{seconds}`
+    /// replaced by the number of seconds remaining
+    footer: String,
+
+    /// The number of seconds last shown in the footer
+    remaining: u32,
+}
+
+impl Countdown {
+    fn footer(&self, remaining: u32) -> Vec<u16> {
+        str::encode_utf16(&self.footer.replace("{seconds}", &remaining.to_string()))
+            .chain(Some(0))
+            .collect::<Vec<u16>>()
+    }
+}
+
 pub struct TaskDialogCallbackData {
     dialog_handle: isize,
+    dialog_countdown: Option<Countdown>,
     dialog_progress_source: Option<Arc<AtomicU32>>,
     dialog_progress_min: u16,
     dialog_progress_max: u16,
@@ -182,6 +228,9 @@ pub struct TaskDialogCallbackData {
 pub struct TaskDialogResult {
     pub verified: bool,
     pub action: TaskDialogAction,
+
+    /// The id of the selected radio button, if the dialog has any
+    pub radio: Option<i32>,
     pub progress: Option<u32>,
 }
 
@@ -191,14 +240,24 @@ pub struct TaskDialogBuilder {
     dialog_heading_utf16_nul: Vec<u16>,
     dialog_content_utf16_nul: Vec<u16>,
     dialog_footer_utf16_nul: Vec<u16>,
+    dialog_details_utf16_nul: Option<Vec<u16>>,
+    dialog_details_expanded: bool,
 
     dialog_icon: TaskDialogIcon,
+    dialog_footer_icon: TaskDialogIcon,
     dialog_allow_hyperlinks: bool,
 
     dialog_verification_text_utf16_nul: Vec<u16>,
     initial_verification_value: bool,
 
     dialog_buttons: Vec<TaskDialogAction>,
+    dialog_custom_buttons: Vec<(i32, Vec<u16>)>,
+    dialog_command_links: bool,
+
+    dialog_radio_buttons: Vec<(i32, Vec<u16>)>,
+    dialog_default_radio_button: Option<i32>,
+
+    dialog_countdown: Option<Countdown>,
 
     dialog_progress_source: Option<Arc<AtomicU32>>,
     dialog_progress_min: u16,
@@ -234,16 +293,117 @@ impl TaskDialogBuilder {
         self
     }
 
+    /// Adds an expandable section below the content, collapsed unless
+    /// `expanded` is true, for information most users do not need such
+    /// as stack traces or process details.
+    pub fn set_details<Param: AsRef<str>>(&mut self, details: Param, expanded: bool) -> &mut Self {
+        self.dialog_details_utf16_nul = Some(
+            str::encode_utf16(details.as_ref())
+                .chain(Some(0))
+                .collect::<Vec<u16>>(),
+        );
+        self.dialog_details_expanded = expanded;
+        self
+    }
+
     pub fn set_icon(&mut self, icon: TaskDialogIcon) -> &mut Self {
         self.dialog_icon = icon;
         self
     }
 
+    /// Shows `icon` next to the footer. Has no effect without a footer.
+    pub fn set_footer_icon(&mut self, icon: TaskDialogIcon) -> &mut Self {
+        self.dialog_footer_icon = icon;
+        self
+    }
+
     pub fn add_button(&mut self, action: TaskDialogAction) -> &mut Self {
         self.dialog_buttons.push(action);
         self
     }
 
+    /// Adds a button labelled `label`, which results in
+    /// [`TaskDialogAction::Custom`] with the specified `id` when clicked.
+    /// Custom buttons are placed before the common buttons.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is the id of one of the common buttons (1 to 7).
+    pub fn add_custom_button<Param: AsRef<str>>(&mut self, id: i32, label: Param) -> &mut Self {
+        assert!(
+            !(1..=7).contains(&id),
+            "custom button id {id} is reserved for common buttons"
+        );
+
+        self.dialog_custom_buttons.push((
+            id,
+            str::encode_utf16(label.as_ref())
+                .chain(Some(0))
+                .collect::<Vec<u16>>(),
+        ));
+        self
+    }
+
+    /// Adds a custom button shown as a command link, a large button with
+    /// an optional `note` below its label. Once a command link is added,
+    /// all custom buttons of the dialog are shown as command links.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is the id of one of the common buttons (1 to 7).
+    pub fn add_command_link<Param: AsRef<str>>(
+        &mut self,
+        id: i32,
+        label: Param,
+        note: Option<&str>,
+    ) -> &mut Self {
+        self.dialog_command_links = true;
+
+        match note {
+            Some(note) => self.add_custom_button(id, format!("{}\n{note}", label.as_ref())),
+            None => self.add_custom_button(id, label),
+        }
+    }
+
+    /// Adds a radio button labelled `label`. The dialog has a single group of
+    /// radio buttons, and the id of the one selected when the dialog is
+    /// dismissed is reported in `TaskDialogResult::radio`. The first radio
+    /// button is selected by default unless changed with
+    /// [`TaskDialogBuilder::set_default_radio_button`].
+    pub fn add_radio_button<Param: AsRef<str>>(&mut self, id: i32, label: Param) -> &mut Self {
+        self.dialog_radio_buttons.push((
+            id,
+            str::encode_utf16(label.as_ref())
+                .chain(Some(0))
+                .collect::<Vec<u16>>(),
+        ));
+        self
+    }
+
+    /// Selects the radio button with the specified `id` when the dialog is shown.
+    pub fn set_default_radio_button(&mut self, id: i32) -> &mut Self {
+        self.dialog_default_radio_button = Some(id);
+        self
+    }
+
+    /// Clicks the button resulting in `action` after `seconds`, unless the dialog
+    /// is dismissed before then. While counting down, the footer is replaced by
+    /// `footer`, in which `{seconds}` is replaced by the seconds remaining.
+    pub fn set_countdown(
+        &mut self,
+        seconds: u32,
+        action: TaskDialogAction,
+        footer: &str,
+    ) -> &mut Self {
+        self.dialog_countdown = Some(Countdown {
+            seconds,
+            action,
+            footer: footer.to_owned(),
+            remaining: seconds,
+        });
+        self
+    }
+
     /// Adds a checkbox to the bottom of the task dialog. If the checkbox is
     /// checked when the task dialog is dismissed, `TaskDialogResult::verified`
     /// will be set to true. If not, it will be false.
@@ -299,12 +459,26 @@ impl TaskDialogBuilder {
         let dialog_title = Box::new(self.dialog_title_utf16_nul.clone());
         let dialog_heading = Box::new(self.dialog_heading_utf16_nul.clone());
         let dialog_content = Box::new(self.dialog_content_utf16_nul.clone());
-        let dialog_footer = Box::new(self.dialog_footer_utf16_nul.clone());
+        let dialog_countdown = self.dialog_countdown.clone();
+        let dialog_footer = Box::new(dialog_countdown.as_ref().map_or_else(
+            || self.dialog_footer_utf16_nul.clone(),
+            |countdown| countdown.footer(countdown.seconds),
+        ));
+        let dialog_details = Box::new(self.dialog_details_utf16_nul.clone());
+        let dialog_custom_buttons = Box::new(self.dialog_custom_buttons.clone());
+        let dialog_radio_buttons = Box::new(self.dialog_radio_buttons.clone());
         let dialog_verification_text_utf16_nul =
             Box::new(self.dialog_verification_text_utf16_nul.clone());
 
         let icon = self.dialog_icon;
+        let footer_icon = self.dialog_footer_icon;
         let allow_hyperlinks = self.dialog_allow_hyperlinks;
+        let command_links = self.dialog_command_links;
+        let details_expanded = self.dialog_details_expanded;
+        let default_radio_button = self
+            .dialog_default_radio_button
+            .or_else(|| self.dialog_radio_buttons.first().map(|(id, _)| *id))
+            .unwrap_or(0);
 
         let initial_verification_value = self.initial_verification_value;
 
@@ -319,6 +493,7 @@ impl TaskDialogBuilder {
 
         let callback_data = Arc::new(Mutex::new(TaskDialogCallbackData {
             dialog_handle: 0,
+            dialog_countdown: dialog_countdown.clone(),
             dialog_progress_source: dialog_progress_source.clone(),
             dialog_progress_min: 0,
             dialog_progress_max: 100,
@@ -342,9 +517,27 @@ impl TaskDialogBuilder {
                 dialog_flags |= TDF_SHOW_PROGRESS_BAR.0;
             }
 
+            if dialog_countdown.is_some() {
+                dialog_flags |= TDF_CALLBACK_TIMER.0;
+            }
+
+            if command_links {
+                dialog_flags |= TDF_USE_COMMAND_LINKS.0;
+            }
+
+            if details_expanded {
+                dialog_flags |= TDF_EXPANDED_BY_DEFAULT.0;
+            }
+
+            // The labels are borrowed from `dialog_custom_buttons` and
+            // `dialog_radio_buttons`, which outlive the dialog
+            let buttons = to_task_dialog_buttons(&dialog_custom_buttons);
+            let radio_buttons = to_task_dialog_buttons(&dialog_radio_buttons);
+
             let mut dialog_result = TaskDialogResult {
                 verified: false,
                 action: TaskDialogAction::None,
+                radio: None,
                 progress: None,
             };
 
@@ -355,13 +548,25 @@ impl TaskDialogBuilder {
                 pszMainInstruction: PCWSTR(dialog_heading.as_ptr()),
                 pszContent: PCWSTR(dialog_content.as_ptr()),
                 pszFooter: PCWSTR(dialog_footer.as_ptr()),
+                pszExpandedInformation: dialog_details
+                    .as_ref()
+                    .as_ref()
+                    .map_or(PCWSTR(std::ptr::null()), |details| PCWSTR(details.as_ptr())),
                 Anonymous1: TASKDIALOGCONFIG_0 {
                     pszMainIcon: icon.to_icon_id(),
                 },
+                Anonymous2: TASKDIALOGCONFIG_1 {
+                    pszFooterIcon: footer_icon.to_icon_id(),
+                },
                 dwFlags: TASKDIALOG_FLAGS(dialog_flags),
                 pfCallback: Some(dialog_notification_callback),
                 pszVerificationText: PCWSTR(dialog_verification_text_utf16_nul.as_ptr()),
                 dwCommonButtons: dialog_common_button_flags,
+                cButtons: u32::try_from(buttons.len()).unwrap(),
+                pButtons: buttons.as_ptr(),
+                cRadioButtons: u32::try_from(radio_buttons.len()).unwrap(),
+                pRadioButtons: radio_buttons.as_ptr(),
+                nDefaultRadioButton: default_radio_button,
                 lpCallbackData: std::ptr::from_mut::<Arc<Mutex<TaskDialogCallbackData>>>(
                     &mut callback_data_pass,
                 ) as isize,
@@ -384,6 +589,10 @@ impl TaskDialogBuilder {
 
             dialog_result.verified = verified.as_bool();
 
+            if !radio_buttons.is_empty() {
+                dialog_result.radio = Some(radio_id);
+            }
+
             let cb_data_clone = callback_data_pass.clone();
             let callback_data_guard = (*cb_data_clone).lock().unwrap();
 
@@ -400,21 +609,146 @@ impl TaskDialogBuilder {
         });
 
         TaskDialog {
-            // Handle has not yet been initialized by the dialog callback
-            // at this point in time, so dialog_handle will forever be 0
-            // Should I use a condvar on callback_data and block here?
+            // The handle is set by the dialog callback once the dialog has been constructed
             cb_data: callback_data.clone(),
             result: task_dialog_result,
         }
     }
 }
 
+/// Shows [`Dialogs`] as task dialogs.
+pub struct TaskDialogs;
+
+impl TaskDialogs {
+    /// Returns a builder with the text, icon and details of `dialog`.
+    fn builder(dialog: &Dialog) -> TaskDialogBuilder {
+        let mut builder = TaskDialog::new();
+
+        builder
+            .set_title(&dialog.title)
+            .set_heading(&dialog.heading)
+            .set_content(&dialog.content)
+            .set_icon(match dialog.severity {
+                Severity::Information => TaskDialogIcon::InformationIcon,
+                Severity::Warning => TaskDialogIcon::WarningIcon,
+                Severity::Error => TaskDialogIcon::ErrorIcon,
+            })
+            .set_hyperlinks_enabled(dialog.hyperlinks);
+
+        if let Some(details) = &dialog.details {
+            builder.set_details(details, false);
+        }
+
+        builder
+    }
+}
+
+impl Dialogs for TaskDialogs {
+    fn message(&self, dialog: &Dialog, wait: bool) {
+        let dialog = Self::builder(dialog).display();
+
+        if wait {
+            dialog.result();
+        }
+    }
+
+    fn confirm(&self, dialog: &Dialog, verification: Option<&Verification>) -> Answer<bool> {
+        let mut builder = Self::builder(dialog);
+
+        builder
+            .add_button(TaskDialogAction::Yes)
+            .add_button(TaskDialogAction::No);
+
+        if let Some(verification) = verification {
+            builder.set_verification(&verification.label, verification.checked);
+        }
+
+        let result = builder.display().result();
+
+        Answer {
+            value: result.action == TaskDialogAction::Yes,
+            verified: result.verified,
+        }
+    }
+
+    fn choice(
+        &self,
+        dialog: &Dialog,
+        choices: &[Choice],
+        verification: Option<&Verification>,
+        countdown: Option<&dialogs::Countdown>,
+    ) -> Answer<Option<i32>> {
+        let mut builder = Self::builder(dialog);
+
+        for choice in choices {
+            builder.add_command_link(choice.id, &choice.label, choice.note.as_deref());
+        }
+
+        builder.add_button(TaskDialogAction::Cancel);
+
+        if let Some(verification) = verification {
+            builder.set_verification(&verification.label, verification.checked);
+        }
+
+        if let Some(countdown) = countdown {
+            builder
+                .set_countdown(
+                    countdown.seconds,
+                    TaskDialogAction::Custom(countdown.choice),
+                    &countdown.footer,
+                )
+                .set_footer_icon(TaskDialogIcon::InformationIcon);
+        }
+
+        let result = builder.display().result();
+
+        Answer {
+            value: match result.action {
+                TaskDialogAction::Custom(id) => Some(id),
+                _ => None,
+            },
+            verified: result.verified,
+        }
+    }
+
+    fn progress(&self, dialog: &Dialog, progress: Arc<AtomicU32>) -> Box<dyn ProgressDialog> {
+        let dialog = Self::builder(dialog)
+            .add_button(TaskDialogAction::Cancel)
+            .add_button(TaskDialogAction::Ok)
+            .set_progress(progress, 0, 100)
+            .display();
+
+        Box::new(TaskProgressDialog(dialog))
+    }
+}
+
+struct TaskProgressDialog(TaskDialog);
+
+impl ProgressDialog for TaskProgressDialog {
+    fn finish(self: Box<Self>) -> bool {
+        // The progress is only reported if it was complete when the dialog closed
+        self.0.close(TaskDialogAction::Ok).progress.is_some()
+    }
+}
+
+/// Converts ids and nul-terminated labels to the buttons passed to the task
+/// dialog, which borrow the labels.
+fn to_task_dialog_buttons(buttons: &[(i32, Vec<u16>)]) -> Vec<TASKDIALOG_BUTTON> {
+    buttons
+        .iter()
+        .map(|(id, label)| TASKDIALOG_BUTTON {
+            nButtonID: *id,
+            pszButtonText: PCWSTR(label.as_ptr()),
+        })
+        .collect::<Vec<TASKDIALOG_BUTTON>>()
+}
+
 /// Handles messages for task dialog modals and, if needed, forwards the
 /// result of the dialog to the calling thread.
 unsafe extern "system" fn dialog_notification_callback(
     hwnd: HWND,
     notification: u32,
-    _wparam: WPARAM,
+    wparam: WPARAM,
     lparam: LPARAM,
     callback_data: isize,
 ) -> HRESULT {
@@ -445,6 +779,35 @@ unsafe extern "system" fn dialog_notification_callback(
             );
         }
     } else if notification == u32::try_from(TDN_TIMER.0).unwrap() {
+        // The timer fires about every 200 ms, with the time since the dialog was created in wparam
+        if let Some(countdown) = &mut callback_data_guard.dialog_countdown {
+            let elapsed = u32::try_from(wparam.0 / 1000).unwrap_or(u32::MAX);
+            let remaining = countdown.seconds.saturating_sub(elapsed);
+
+            if remaining == 0 {
+                let id = countdown.action.to_id().0;
+                callback_data_guard.dialog_countdown = None;
+
+                PostMessageA(
+                    hwnd,
+                    TDM_CLICK_BUTTON.0.try_into().unwrap(),
+                    WPARAM(usize::try_from(id).unwrap()),
+                    LPARAM(0),
+                )
+                .unwrap();
+            } else if remaining != countdown.remaining {
+                countdown.remaining = remaining;
+                let footer = countdown.footer(remaining);
+
+                SendMessageA(
+                    hwnd,
+                    TDM_UPDATE_ELEMENT_TEXT.0.try_into().unwrap(),
+                    WPARAM(usize::try_from(TDE_FOOTER.0).unwrap()),
+                    LPARAM(footer.as_ptr() as isize),
+                );
+            }
+        }
+
         // We update the progress bar in timed intervals if it is enabled
         if let Some(progress) = &(callback_data_guard.dialog_progress_source) {
             let progress = progress.load(std::sync::atomic::Ordering::SeqCst);
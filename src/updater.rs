@@ -1,4 +1,6 @@
-use crate::{logf, ui::taskdialog};
+use crate::notification::Severity;
+use crate::ui::dialogs::{Dialog, Dialogs};
+use crate::{logf, tr};
 
 use serde::{Deserialize, Serialize};
 use std::{env, io::Write};
@@ -17,7 +19,7 @@ pub enum UpdateError {
     Zip(zip::result::ZipError),
     #[error("update was interrupted by user")]
     Interrupted,
-    #[error("failed to remove previous version")]
+    #[error("failed to remove the previous version, is another instance of xterminate running?")]
     RemoveOld,
 }
 
@@ -48,29 +50,17 @@ pub struct ReleaseInfo {
 ///
 /// # Errors
 ///
-/// Returns an `Err` if no network connection is available or if GitHub
-/// returned an unexpected response that could not be parsed.
+/// Returns an `Err` if the previous version of xterminate could not be removed,
+/// if no network connection is available or if GitHub returned an unexpected
+/// response that could not be parsed.
 #[allow(clippy::missing_panics_doc)]
-#[must_use]
-pub fn check() -> Option<ReleaseInfo> {
+pub fn check() -> Result<Option<ReleaseInfo>, UpdateError> {
     let old_exe_path = env::current_exe().unwrap().with_extension("exe.old");
 
     if old_exe_path.exists() && std::fs::remove_file(old_exe_path).is_err() {
         logf!("ERROR: Unable to remove previous version of xterminate (is the file still in use?)");
 
-        taskdialog::TaskDialog::new()
-            .set_icon(taskdialog::TaskDialogIcon::ErrorIcon)
-            .set_title("Update check failed")
-            .set_heading("Could not check for updates")
-            .set_content(
-                "Update check could not start because xterminate was unable \
-                to remove an outdated version of itself. Ensure there are not \
-                multiple instances of xterminate running and try again.",
-            )
-            .display()
-            .result();
-
-        return None;
+        return Err(UpdateError::RemoveOld);
     }
 
     let current_version = env!("CARGO_PKG_VERSION");
@@ -78,59 +68,45 @@ pub fn check() -> Option<ReleaseInfo> {
     let current_minor = current_version[2..3].parse::<usize>().unwrap();
     let current_patch = current_version[4..5].parse::<usize>().unwrap();
 
-    match query_latest() {
-        Ok(latest) => {
-            if let Some(latest) = latest {
-                let latest_major = latest.version[0..1].parse::<usize>().unwrap();
-                let latest_minor = latest.version[2..3].parse::<usize>().unwrap();
-                let latest_patch = latest.version[4..5].parse::<usize>().unwrap();
-
-                if latest_major > current_major
-                    || (latest_major == current_major && latest_minor > current_minor)
-                    || (latest_major == current_major
-                        && latest_minor == current_minor
-                        && latest_patch > current_patch)
-                {
-                    return Some(latest);
-                }
-            }
-
-            None
-        }
-
-        Err(err) => {
-            logf!("ERROR: Unable to check for updates: {err}");
-
-            taskdialog::TaskDialog::new()
-                .set_icon(taskdialog::TaskDialogIcon::ErrorIcon)
-                .set_title("Update failed")
-                .set_heading("Could not update xterminate")
-                .set_content(format!(
-                    "Could not check for updates due to the following error: \n\n{err}"
-                ))
-                .display()
-                .result();
-
-            None
+    let latest = query_latest().map_err(|err| {
+        logf!("ERROR: Unable to check for updates: {err}");
+        err
+    })?;
+
+    if let Some(latest) = latest {
+        let latest_major = latest.version[0..1].parse::<usize>().unwrap();
+        let latest_minor = latest.version[2..3].parse::<usize>().unwrap();
+        let latest_patch = latest.version[4..5].parse::<usize>().unwrap();
+
+        if latest_major > current_major
+            || (latest_major == current_major && latest_minor > current_minor)
+            || (latest_major == current_major
+                && latest_minor == current_minor
+                && latest_patch > current_patch)
+        {
+            return Ok(Some(latest));
         }
     }
+
+    Ok(None)
 }
 
 /// Downlooads and installs the specified release of xterminate.
-/// This method will show [`TaskDialog`]s fto display download progress
+/// This method will show `dialogs` to display download progress
 /// and error messages if the update is unsuccessful.
 #[allow(clippy::missing_panics_doc)]
-pub async fn update(release: ReleaseInfo) {
+pub async fn update(release: ReleaseInfo, dialogs: &dyn Dialogs) {
     let progress = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
 
-    let dialog = taskdialog::TaskDialog::new()
-        .set_title("Updating xterminate")
-        .set_heading("Downloading update")
-        .set_content("Hold on for a moment while xterminate downloads the new update!")
-        .add_button(taskdialog::TaskDialogAction::Cancel)
-        .add_button(taskdialog::TaskDialogAction::Ok)
-        .set_progress(progress.clone(), 0, 100)
-        .display();
+    let dialog = dialogs.progress(
+        &Dialog::new(
+            Severity::Information,
+            tr!("dialog.update.progress_title"),
+            tr!("dialog.update.progress_heading"),
+            tr!("dialog.update.progress_content"),
+        ),
+        progress.clone(),
+    );
 
     logf!("Downloading latest release...");
     let file = download(release, progress.clone()).await;
@@ -138,15 +114,15 @@ pub async fn update(release: ReleaseInfo) {
     if let Err(err) = file {
         logf!("ERROR: Failed to download update: {err}");
 
-        taskdialog::TaskDialog::new()
-            .set_icon(taskdialog::TaskDialogIcon::ErrorIcon)
-            .set_title("Update failed")
-            .set_heading("Could not update xterminate")
-            .set_content(format!(
-                "Sorry, downloading the update failed due to the following error: \n\n{err}"
-            ))
-            .display()
-            .result();
+        dialogs.message(
+            &Dialog::new(
+                Severity::Error,
+                tr!("dialog.update.failed_title"),
+                tr!("dialog.update.failed_heading"),
+                tr!("dialog.update.failed_content", error = err),
+            ),
+            true,
+        );
 
         return;
     }
@@ -154,35 +130,34 @@ pub async fn update(release: ReleaseInfo) {
     logf!("Installing update...");
     install(file.unwrap());
 
-    let dialog_result = dialog.close();
-    let progress = dialog_result.progress.unwrap();
-
-    if progress < 100 {
+    if !dialog.finish() {
         logf!("Update progress was < 100 - most likely cancelled by the user");
 
         // User dismissed the update dialog while the update was in progress
-        taskdialog::TaskDialog::new()
-            .set_icon(taskdialog::TaskDialogIcon::InformationIcon)
-            .set_title("Update aborted")
-            .set_heading("The update was cancelled by the user")
-            .set_content("Maybe another time?")
-            .display()
-            .result();
+        dialogs.message(
+            &Dialog::new(
+                Severity::Information,
+                tr!("dialog.update.aborted_title"),
+                tr!("dialog.update.aborted_heading"),
+                tr!("dialog.update.aborted_content"),
+            ),
+            true,
+        );
 
         return;
     }
 
     logf!("Updated successfully");
 
-    taskdialog::TaskDialog::new()
-        .set_title("Update success")
-        .set_heading("Update complete!")
-        .set_content(
-            "The update was installed successfully and will \
-                    be applied next time xterminate restarts.",
-        )
-        .display()
-        .result();
+    dialogs.message(
+        &Dialog::new(
+            Severity::Information,
+            tr!("dialog.update.complete_title"),
+            tr!("dialog.update.complete_heading"),
+            tr!("dialog.update.complete_content"),
+        ),
+        true,
+    );
 }
 
 /// Attempts to download the archive associated with specified release.
@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::process::ProcessInfo;
+use crate::rules::{self, HangAction, Rule};
+
+/// A top-level window that Windows currently reports as "Not Responding".
+#[derive(Debug, Clone)]
+pub struct HungWindow {
+    pub handle: isize,
+    pub title: String,
+    pub process: ProcessInfo,
+}
+
+/// A window that has been unresponsive for at least the configured threshold.
+#[derive(Debug, Clone)]
+pub struct HangReport {
+    pub window: HungWindow,
+    pub duration: Duration,
+    pub action: HangAction,
+}
+
+/// Keeps track of how long windows have been unresponsive and decides when
+/// and how to act on them. The tracker does not talk to Windows itself; it
+/// is fed the currently hung windows along with the current time, so it can
+/// be driven by a fake window list and clock.
+pub struct HangTracker {
+    threshold: Duration,
+    hung_since: HashMap<isize, Instant>,
+
+    /// Windows that have been reported or ignored and should not
+    /// be reported again until they respond or [`Self::wait`] is called.
+    silenced: HashSet<isize>,
+}
+
+impl HangTracker {
+    #[must_use]
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            hung_since: HashMap::new(),
+            silenced: HashSet::new(),
+        }
+    }
+
+    /// Updates the tracker with the windows that are currently hung and
+    /// returns the ones that have been hung for longer than the threshold.
+    /// Windows absent from `hung` are considered responsive again.
+    ///
+    /// A window is only reported once. Call [`Self::wait`] to have it
+    /// reported again after another threshold has passed.
+    pub fn update(&mut self, hung: &[HungWindow], now: Instant, rules: &[Rule]) -> Vec<HangReport> {
+        // Forget windows that have recovered or have been closed
+        let is_hung = |handle: &isize| hung.iter().any(|window| window.handle == *handle);
+        self.hung_since.retain(|handle, _| is_hung(handle));
+        self.silenced.retain(is_hung);
+
+        let mut reports = Vec::new();
+
+        for window in hung {
+            let since = *self.hung_since.entry(window.handle).or_insert(now);
+            let duration = now.saturating_duration_since(since);
+
+            if duration < self.threshold || self.silenced.contains(&window.handle) {
+                continue;
+            }
+
+            self.silenced.insert(window.handle);
+
+            let action = rules::hang_action(rules, &window.process);
+
+            if action == HangAction::Ignore {
+                continue;
+            }
+
+            reports.push(HangReport {
+                window: window.clone(),
+                duration,
+                action,
+            });
+        }
+
+        reports
+    }
+
+    /// Restarts the hang timer for the specified window so that it is reported
+    /// again if it is still unresponsive once another threshold has passed.
+    pub fn wait(&mut self, handle: isize, now: Instant) {
+        self.silenced.remove(&handle);
+
+        if let Some(since) = self.hung_since.get_mut(&handle) {
+            *since = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(15);
+
+    fn hung_window(handle: isize, name: &str) -> HungWindow {
+        HungWindow {
+            handle,
+            title: format!("{name} window"),
+            process: ProcessInfo {
+                pid: u32::try_from(handle).unwrap(),
+                name: name.to_owned(),
+                ..ProcessInfo::default()
+            },
+        }
+    }
+
+    fn rule(name: &str, on_hang: HangAction) -> Rule {
+        Rule {
+            process: Some(name.to_owned()),
+            on_hang: Some(on_hang),
+            ..Rule::default()
+        }
+    }
+
+    fn handles(reports: &[HangReport]) -> Vec<isize> {
+        reports.iter().map(|report| report.window.handle).collect()
+    }
+
+    #[test]
+    fn reports_window_once_after_threshold() {
+        let start = Instant::now();
+        let mut tracker = HangTracker::new(THRESHOLD);
+        let hung = [hung_window(1, "app.exe")];
+
+        assert!(tracker.update(&hung, start, &[]).is_empty());
+        assert!(tracker
+            .update(&hung, start + Duration::from_secs(10), &[])
+            .is_empty());
+
+        let reports = tracker.update(&hung, start + THRESHOLD, &[]);
+        assert_eq!(handles(&reports), [1]);
+        assert_eq!(reports[0].duration, THRESHOLD);
+        assert_eq!(reports[0].action, HangAction::Prompt);
+
+        assert!(tracker.update(&hung, start + THRESHOLD * 2, &[]).is_empty());
+    }
+
+    #[test]
+    fn recovered_window_starts_over() {
+        let start = Instant::now();
+        let mut tracker = HangTracker::new(THRESHOLD);
+        let hung = [hung_window(1, "app.exe")];
+
+        tracker.update(&hung, start, &[]);
+        assert_eq!(handles(&tracker.update(&hung, start + THRESHOLD, &[])), [1]);
+
+        // The window responds again, then hangs once more
+        let recovered = start + THRESHOLD + Duration::from_secs(1);
+        assert!(tracker.update(&[], recovered, &[]).is_empty());
+        assert!(tracker.update(&hung, recovered, &[]).is_empty());
+        assert!(tracker
+            .update(&hung, recovered + THRESHOLD - Duration::from_secs(1), &[])
+            .is_empty());
+        assert_eq!(
+            handles(&tracker.update(&hung, recovered + THRESHOLD, &[])),
+            [1]
+        );
+    }
+
+    #[test]
+    fn wait_reports_again_after_another_threshold() {
+        let start = Instant::now();
+        let mut tracker = HangTracker::new(THRESHOLD);
+        let hung = [hung_window(1, "app.exe")];
+
+        tracker.update(&hung, start, &[]);
+        tracker.update(&hung, start + THRESHOLD, &[]);
+
+        let answered = start + THRESHOLD + Duration::from_secs(30);
+        tracker.wait(1, answered);
+
+        assert!(tracker
+            .update(&hung, answered + Duration::from_secs(1), &[])
+            .is_empty());
+
+        let reports = tracker.update(&hung, answered + THRESHOLD, &[]);
+        assert_eq!(handles(&reports), [1]);
+        assert_eq!(reports[0].duration, THRESHOLD);
+    }
+
+    #[test]
+    fn tracks_windows_independently() {
+        let start = Instant::now();
+        let mut tracker = HangTracker::new(THRESHOLD);
+        let first = hung_window(1, "first.exe");
+        let second = hung_window(2, "second.exe");
+
+        tracker.update(std::slice::from_ref(&first), start, &[]);
+
+        let later = start + Duration::from_secs(10);
+        assert!(tracker
+            .update(&[first.clone(), second.clone()], later, &[])
+            .is_empty());
+        assert_eq!(
+            handles(&tracker.update(&[first.clone(), second.clone()], start + THRESHOLD, &[])),
+            [1]
+        );
+        assert_eq!(
+            handles(&tracker.update(&[first, second], later + THRESHOLD, &[])),
+            [2]
+        );
+    }
+
+    #[test]
+    fn protect_rule_does_not_hide_hang_action() {
+        let start = Instant::now();
+        let mut tracker = HangTracker::new(THRESHOLD);
+
+        // As inserted by protecting the process from the tray menu
+        let rules = [
+            Rule {
+                process: Some(String::from("app.exe")),
+                protected: Some(true),
+                ..Rule::default()
+            },
+            rule("app.exe", HangAction::Kill),
+        ];
+        let hung = [hung_window(1, "app.exe")];
+
+        tracker.update(&hung, start, &rules);
+        let reports = tracker.update(&hung, start + THRESHOLD, &rules);
+
+        assert_eq!(handles(&reports), [1]);
+        assert_eq!(reports[0].action, HangAction::Kill);
+    }
+
+    #[test]
+    fn rules_decide_the_action() {
+        let start = Instant::now();
+        let mut tracker = HangTracker::new(THRESHOLD);
+        let rules = [
+            rule("ignored.exe", HangAction::Ignore),
+            rule("killed.exe", HangAction::Kill),
+        ];
+        let hung = [
+            hung_window(1, "ignored.exe"),
+            hung_window(2, "killed.exe"),
+            hung_window(3, "other.exe"),
+        ];
+
+        tracker.update(&hung, start, &rules);
+        let reports = tracker.update(&hung, start + THRESHOLD, &rules);
+
+        assert_eq!(handles(&reports), [2, 3]);
+        assert_eq!(reports[0].action, HangAction::Kill);
+        assert_eq!(reports[1].action, HangAction::Prompt);
+
+        // Ignored windows stay silent while they are hung
+        assert!(tracker
+            .update(&hung, start + THRESHOLD * 3, &rules)
+            .is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+//! Conversions between Rust strings and the UTF-16 strings used by the
+//! wide-character (`W`) family of Windows APIs. Conversions go through
+//! [`OsString`] so that file names containing unpaired surrogates, which
+//! are valid on Windows but not in UTF-8, survive the round trip.
+
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+/// The maximum length of an extended-length path, in UTF-16 code units.
+pub const MAX_LONG_PATH: usize = 32_767;
+
+/// Encodes `value` as a nul-terminated UTF-16 string.
+#[must_use]
+pub fn to_wide_nul(value: impl AsRef<OsStr>) -> Vec<u16> {
+    value.as_ref().encode_wide().chain(Some(0)).collect()
+}
+
+/// Decodes a UTF-16 string, stopping at the first nul character if there is one.
+#[must_use]
+pub fn from_wide(value: &[u16]) -> OsString {
+    let length = value.iter().position(|c| *c == 0).unwrap_or(value.len());
+
+    OsString::from_wide(&value[..length])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_non_ascii() {
+        let path = OsString::from("C:\\Users\\Jürgen\\Программы\\日本語 🚀.exe");
+        let wide = to_wide_nul(&path);
+
+        assert_eq!(wide.last(), Some(&0));
+        assert_eq!(from_wide(&wide), path);
+    }
+
+    #[test]
+    fn round_trips_unpaired_surrogates() {
+        // Valid as a Windows file name, but not as UTF-8
+        let name = OsString::from_wide(&[u16::from(b'a'), 0xD800, u16::from(b'b')]);
+
+        assert!(name.to_str().is_none());
+        assert_eq!(from_wide(&to_wide_nul(&name)), name);
+        assert_eq!(name.to_string_lossy(), "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn round_trips_extended_length_paths() {
+        let mut path = String::from("\\\\?\\C:");
+        while path.encode_utf16().count() < MAX_LONG_PATH - 16 {
+            path.push_str("\\Verzeichnis-ä");
+        }
+
+        let path = OsString::from(path);
+        let wide = to_wide_nul(&path);
+
+        assert!(wide.len() > 260);
+        assert!(wide.len() <= MAX_LONG_PATH);
+        assert_eq!(from_wide(&wide), path);
+    }
+
+    #[test]
+    fn stops_at_first_nul() {
+        let mut buffer = to_wide_nul("notepad.exe");
+        buffer.extend([u16::from(b'x'); 8]);
+
+        assert_eq!(from_wide(&buffer), OsString::from("notepad.exe"));
+        assert_eq!(from_wide(&[]), OsString::new());
+    }
+}
@@ -1,13 +1,16 @@
+use std::ffi::OsString;
 use std::ops::BitAnd;
 
 use windows::Win32::Foundation::{BOOL, HWND, LPARAM, POINT};
 
 use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetAncestor, GetClassNameA, GetForegroundWindow, GetWindowLongPtrA,
-    GetWindowThreadProcessId, WindowFromPoint, GA_ROOT, GWL_STYLE, WS_DISABLED,
+    EnumWindows, GetAncestor, GetClassNameW, GetForegroundWindow, GetWindowLongPtrA,
+    GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsHungAppWindow,
+    IsWindowVisible, WindowFromPoint, GA_ROOT, GWL_STYLE, WS_DISABLED,
 };
 
 use crate::process::Process;
+use crate::wide;
 
 pub struct Window {
     handle: isize,
@@ -55,31 +58,76 @@ impl Window {
     /// Retrieves and returns the [Process] associated with this [Window].
     #[must_use]
     pub fn process(&self) -> Process {
+        Process::open(self.process_id())
+    }
+
+    /// Opens the [Process] associated with this [Window], or returns an
+    /// error if it has exited in the meantime or cannot be opened.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error reported by [`Process::try_open`].
+    pub fn try_process(&self) -> windows::core::Result<Process> {
+        Process::try_open(self.process_id())
+    }
+
+    /// Returns the ID of the process that created this [Window]
+    /// without opening the process itself.
+    #[must_use]
+    pub fn process_id(&self) -> u32 {
         let mut pid = u32::default();
 
         unsafe { GetWindowThreadProcessId(HWND(self.handle), Some(&mut pid)) };
 
-        Process::open(pid)
+        pid
+    }
+
+    /// Returns this [Window]'s title, or an empty string if it has none.
+    /// The title is returned as it is, use [`OsStr::to_string_lossy`] to display it.
+    ///
+    /// [`OsStr::to_string_lossy`]: std::ffi::OsStr::to_string_lossy
+    #[must_use]
+    pub fn title(&self) -> OsString {
+        let title_len = unsafe { GetWindowTextLengthW(HWND(self.handle())) };
+
+        // Include room for the nul-terminator
+        let mut title = vec![0u16; usize::try_from(title_len).unwrap_or(0) + 1];
+
+        let title_len = unsafe { GetWindowTextW(HWND(self.handle()), &mut title) };
+
+        wide::from_wide(&title[..usize::try_from(title_len).unwrap_or(0)])
+    }
+
+    /// Returns true if Windows considers this [Window] to be
+    /// unresponsive, i.e. "Not Responding".
+    #[must_use]
+    pub fn is_hung(&self) -> bool {
+        unsafe { IsHungAppWindow(HWND(self.handle())).as_bool() }
+    }
+
+    /// Returns true if this [Window] has the `WS_VISIBLE` window-style.
+    #[must_use]
+    pub fn is_visible(&self) -> bool {
+        unsafe { IsWindowVisible(HWND(self.handle())).as_bool() }
     }
 
     /// Returns this [Window]'s class name.
     ///
     /// # Panics
     ///
-    /// This function will panic if [`GetClassNameA()`] returns 0.
+    /// This function will panic if [`GetClassNameW()`] returns 0.
     #[must_use]
     pub fn class_name(&self) -> String {
         // Class name max size is 256 including nul.
-        let mut window_class_name = [0u8; 255];
+        let mut window_class_name = [0u16; 256];
 
-        let class_name_len = unsafe { GetClassNameA(HWND(self.handle()), &mut window_class_name) };
+        let class_name_len = unsafe { GetClassNameW(HWND(self.handle()), &mut window_class_name) };
 
         assert!(class_name_len != 0, "failed to get window class name");
 
-        String::from_utf8_lossy(
-            &window_class_name[0_usize..usize::try_from(class_name_len).unwrap()],
-        )
-        .to_string()
+        wide::from_wide(&window_class_name[..usize::try_from(class_name_len).unwrap()])
+            .to_string_lossy()
+            .into_owned()
     }
 
     /// Returns true if this [Window] is a top-level window, i.e. its top-most
@@ -133,7 +181,8 @@ impl Window {
 
 impl std::fmt::Debug for Window {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Process")
+        f.debug_struct("Window")
+            .field("title", &self.title())
             .field("class_name", &self.class_name())
             .field("handle", &format_args!("0x{0:08X}", self.handle))
             .finish()
@@ -142,6 +191,12 @@ impl std::fmt::Debug for Window {
 
 impl std::fmt::Display for Window {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} (handle: {})", self.class_name(), self.handle)
+        write!(
+            f,
+            "'{}' {} (handle: {})",
+            self.title().to_string_lossy(),
+            self.class_name(),
+            self.handle
+        )
     }
 }